@@ -1,26 +1,35 @@
 use bevy::prelude::*;
 use bevy::window::WindowMode;
 use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
-use bevy_trivia::GamePlugin;
+use bevy_trivia::{GamePlugin, UserAssetsPlugin};
 
 #[cfg(target_os = "android")]
 use keyboard::AndroidKeyboardPlugin;
+#[cfg(target_os = "android")]
+use safe_area::AndroidSafeAreaPlugin;
+#[cfg(target_os = "ios")]
+use safe_area_ios::IosSafeAreaPlugin;
 
 #[cfg(target_os = "android")]
 mod keyboard;
+#[cfg(target_os = "android")]
+mod safe_area;
+#[cfg(target_os = "ios")]
+mod safe_area_ios;
 
 #[bevy_main]
 fn main() {
     let mut app = App::new();
     app.add_plugins((
+        UserAssetsPlugin,
         EmbeddedAssetPlugin {
             mode: PluginMode::ReplaceAndFallback {
                 path: "assets".into(),
             },
         },
         DefaultPlugins.set(WindowPlugin {
+            // Rotating the phone resizes the window, and the UI follows
             primary_window: Some(Window {
-                resizable: false,
                 mode: WindowMode::BorderlessFullscreen,
                 ..default()
             }),
@@ -30,7 +39,11 @@ fn main() {
     ));
     #[cfg(target_os = "android")]
     {
-        app.add_plugins(AndroidKeyboardPlugin);
+        app.add_plugins((AndroidKeyboardPlugin, AndroidSafeAreaPlugin));
+    }
+    #[cfg(target_os = "ios")]
+    {
+        app.add_plugins(IosSafeAreaPlugin);
     }
     app.run();
 }
@@ -0,0 +1,102 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    log::warn,
+    prelude::{EventReader, Query, ResMut, With},
+    window::{PrimaryWindow, Window, WindowResized},
+};
+use bevy_trivia::layout::{Insets, SafeArea};
+use jni::{objects::JObject, JNIEnv};
+
+/// Keeps [`SafeArea`] up to date with the display cutout, which moves when the
+/// phone is rotated
+pub struct AndroidSafeAreaPlugin;
+
+impl Plugin for AndroidSafeAreaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_safe_area);
+    }
+}
+
+fn update_safe_area(
+    mut resized: EventReader<WindowResized>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut safe_area: ResMut<SafeArea>,
+) {
+    if resized.read().last().is_none() {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let insets = match cutout_insets() {
+        Ok(insets) => insets,
+        Err(e) => {
+            warn!("Couldn't read the display cutout: {e}");
+            return;
+        }
+    };
+    // Android gives physical pixels
+    let scale = window.scale_factor();
+    let next = SafeArea(Insets {
+        top: insets[0] as f32 / scale,
+        right: insets[1] as f32 / scale,
+        bottom: insets[2] as f32 / scale,
+        left: insets[3] as f32 / scale,
+    });
+    if *safe_area != next {
+        *safe_area = next;
+    }
+}
+
+/// The cutout's safe insets as top, right, bottom and left, all zero when the
+/// display has no cutout or Android is older than 9
+fn cutout_insets() -> jni::errors::Result<[i32; 4]> {
+    let ctx = ndk_context::android_context();
+    let vm = unsafe { jni::JavaVM::from_raw(ctx.vm().cast()) }?;
+    let mut env = vm.attach_current_thread()?;
+    let activity = unsafe { JObject::from_raw(ctx.context() as jni::sys::jobject) };
+
+    let window = env
+        .call_method(&activity, "getWindow", "()Landroid/view/Window;", &[])?
+        .l()?;
+    let view = env
+        .call_method(&window, "getDecorView", "()Landroid/view/View;", &[])?
+        .l()?;
+    let insets = env
+        .call_method(
+            &view,
+            "getRootWindowInsets",
+            "()Landroid/view/WindowInsets;",
+            &[],
+        )?
+        .l()?;
+    if insets.is_null() {
+        return Ok([0; 4]);
+    }
+    let cutout = match env.call_method(
+        &insets,
+        "getDisplayCutout",
+        "()Landroid/view/DisplayCutout;",
+        &[],
+    ) {
+        Ok(cutout) => cutout.l()?,
+        // Added in Android 9
+        Err(_) => {
+            env.exception_clear()?;
+            return Ok([0; 4]);
+        }
+    };
+    if cutout.is_null() {
+        return Ok([0; 4]);
+    }
+
+    let inset = |env: &mut JNIEnv, name: &str| -> jni::errors::Result<i32> {
+        env.call_method(&cutout, name, "()I", &[])?.i()
+    };
+    Ok([
+        inset(&mut env, "getSafeInsetTop")?,
+        inset(&mut env, "getSafeInsetRight")?,
+        inset(&mut env, "getSafeInsetBottom")?,
+        inset(&mut env, "getSafeInsetLeft")?,
+    ])
+}
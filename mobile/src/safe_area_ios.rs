@@ -0,0 +1,57 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::entity::Entity,
+    prelude::{EventReader, NonSend, Query, ResMut, With},
+    window::{PrimaryWindow, Window, WindowResized},
+    winit::WinitWindows,
+};
+use bevy_trivia::layout::{Insets, SafeArea};
+
+/// Keeps [`SafeArea`] up to date with the view's `safeAreaInsets`, which move
+/// when the phone is rotated
+pub struct IosSafeAreaPlugin;
+
+impl Plugin for IosSafeAreaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_safe_area);
+    }
+}
+
+/// On iOS winit gives the safe area as the window's inner position and size,
+/// and the whole screen as its outer ones. Runs on the main thread, since
+/// UIKit can only be asked from there.
+fn update_safe_area(
+    mut resized: EventReader<WindowResized>,
+    windows: Query<(Entity, &Window), With<PrimaryWindow>>,
+    winit_windows: NonSend<WinitWindows>,
+    mut safe_area: ResMut<SafeArea>,
+) {
+    if resized.read().last().is_none() {
+        return;
+    }
+    let Ok((entity, window)) = windows.get_single() else {
+        return;
+    };
+    let Some(winit_window) = winit_windows.get_window(entity) else {
+        return;
+    };
+    let (Ok(inner), Ok(outer)) = (winit_window.inner_position(), winit_window.outer_position())
+    else {
+        return;
+    };
+    let (inner_size, outer_size) = (winit_window.inner_size(), winit_window.outer_size());
+
+    // Physical pixels, like on Android
+    let scale = window.scale_factor();
+    let top = (inner.y - outer.y) as f32;
+    let left = (inner.x - outer.x) as f32;
+    let next = SafeArea(Insets {
+        top: top / scale,
+        right: (outer_size.width as f32 - inner_size.width as f32 - left) / scale,
+        bottom: (outer_size.height as f32 - inner_size.height as f32 - top) / scale,
+        left: left / scale,
+    });
+    if *safe_area != next {
+        *safe_area = next;
+    }
+}
@@ -0,0 +1,338 @@
+use bevy::{
+    a11y::{
+        accesskit::{Live, NodeBuilder, Role, Toggled},
+        AccessibilityNode,
+    },
+    prelude::*,
+};
+use bevy_quill_obsidian::{controls::Disabled, focus::TabIndex};
+
+use crate::{
+    dialog::{DialogStack, ShowToast},
+    locale::Localization,
+    menu::{
+        serde::{SliderValue, ToggleValue},
+        widgets::{
+            radio_group::RadioGroupSelected, stepper::StepperValue, text_input::TextInputValue,
+        },
+    },
+};
+
+/// Describes menus to screen readers through AccessKit, and reads out whatever
+/// is sent with [`Announce`]
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Announce>()
+            .add_systems(Startup, setup_announcer)
+            .add_systems(
+                Update,
+                (
+                    describe_menu_items,
+                    describe_focusables,
+                    announce_dialogs.run_if(resource_changed::<DialogStack>),
+                    announce_toasts,
+                    apply_announcements,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Reads a message out to screen readers without moving the focus, such as the
+/// question being asked, the time left or who won. The text is shown as is.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct Announce(pub String);
+
+/// How a menu item is described to screen readers. Goes on the item's outermost
+/// element, and is copied onto the focusable entity inside it, along with the
+/// value of whichever widget that is.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct MenuItemAccess {
+    pub role: Role,
+    pub label: String,
+    /// The value, for widgets that don't keep it in a component of their own
+    pub value: Option<String>,
+    /// The lowest and highest values, for sliders and steppers
+    pub range: Option<(f64, f64)>,
+}
+
+impl MenuItemAccess {
+    pub fn new(role: Role, label: impl Into<String>) -> Self {
+        Self {
+            role,
+            label: label.into(),
+            value: None,
+            range: None,
+        }
+    }
+
+    pub fn value(mut self, value: impl Into<String>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+}
+
+/// What was last given to AccessKit for an entity, so its node is only rebuilt
+/// when something changes
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+struct Described {
+    role: Option<Role>,
+    name: String,
+    value: Option<String>,
+    number: Option<f64>,
+    range: Option<(f64, f64)>,
+    toggled: Option<bool>,
+    disabled: bool,
+}
+
+impl Described {
+    fn node(&self) -> AccessibilityNode {
+        let mut node = NodeBuilder::new(self.role.unwrap_or(Role::Button));
+        node.set_name(self.name.clone());
+        if let Some(value) = &self.value {
+            node.set_value(value.clone());
+        }
+        if let Some(number) = self.number {
+            node.set_numeric_value(number);
+        }
+        if let Some((min, max)) = self.range {
+            node.set_min_numeric_value(min);
+            node.set_max_numeric_value(max);
+        }
+        if let Some(toggled) = self.toggled {
+            node.set_toggled(if toggled {
+                Toggled::True
+            } else {
+                Toggled::False
+            });
+        }
+        if self.disabled {
+            node.set_disabled();
+        }
+        AccessibilityNode::from(node)
+    }
+}
+
+/// The live region announcements are read from
+#[derive(Component, Debug, Default, Clone, Copy)]
+struct Announcer;
+
+fn setup_announcer(mut commands: Commands) {
+    let mut node = NodeBuilder::new(Role::Status);
+    node.set_live(Live::Polite);
+    commands.spawn((
+        Announcer,
+        Name::new("Announcer"),
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Px(0.0),
+                height: Val::Px(0.0),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            ..default()
+        },
+        AccessibilityNode::from(node),
+    ));
+}
+
+/// Updates `entity`'s node when its description changed
+fn describe(
+    commands: &mut Commands,
+    described: &Query<&Described>,
+    entity: Entity,
+    description: Described,
+) {
+    if described.get(entity).ok() != Some(&description) {
+        commands
+            .entity(entity)
+            .insert((description.node(), description));
+    }
+}
+
+/// The text shown inside `entity`, which is what a button is called
+fn text_of(entity: Entity, children: &Query<&Children>, texts: &Query<&Text>) -> String {
+    std::iter::once(entity)
+        .chain(children.iter_descendants(entity))
+        .filter_map(|e| texts.get(e).ok())
+        .flat_map(|text| text.sections.iter().map(|s| s.value.as_str()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The focusable entities in `entity`, itself included, in the order they appear
+fn focusables_in(
+    entity: Entity,
+    children: &Query<&Children>,
+    tab_indices: &Query<&TabIndex>,
+) -> Vec<Entity> {
+    std::iter::once(entity)
+        .chain(children.iter_descendants(entity))
+        .filter(|e| tab_indices.get(*e).is_ok_and(|t| t.0 >= 0))
+        .collect()
+}
+
+fn describe_menu_items(
+    mut commands: Commands,
+    items: Query<(
+        Entity,
+        &MenuItemAccess,
+        Option<&SliderValue>,
+        Option<&ToggleValue>,
+    )>,
+    values: Query<(
+        Option<&TextInputValue>,
+        Option<&StepperValue>,
+        Has<Disabled>,
+    )>,
+    radio_groups: Query<&RadioGroupSelected>,
+    children: Query<&Children>,
+    tab_indices: Query<&TabIndex>,
+    texts: Query<&Text>,
+    described: Query<&Described>,
+) {
+    for (entity, access, slider, toggle) in items.iter() {
+        let focusables = focusables_in(entity, &children, &tab_indices);
+
+        if access.role == Role::RadioGroup {
+            let selected = children
+                .iter_descendants(entity)
+                .find_map(|e| radio_groups.get(e).ok())
+                .map(|selected| **selected);
+            describe(
+                &mut commands,
+                &described,
+                entity,
+                Described {
+                    role: Some(Role::RadioGroup),
+                    name: access.label.clone(),
+                    ..default()
+                },
+            );
+            for (i, option) in focusables.into_iter().enumerate() {
+                let disabled = values.get(option).is_ok_and(|(_, _, d)| d);
+                describe(
+                    &mut commands,
+                    &described,
+                    option,
+                    Described {
+                        role: Some(Role::RadioButton),
+                        name: text_of(option, &children, &texts),
+                        toggled: Some(selected == Some(i)),
+                        disabled,
+                        ..default()
+                    },
+                );
+            }
+            continue;
+        }
+
+        let Some(&target) = focusables.first() else {
+            continue;
+        };
+        let (text, stepper, disabled) = values.get(target).unwrap_or_default();
+        let number = slider.map(|v| **v as f64).or(stepper.map(|v| **v as f64));
+        describe(
+            &mut commands,
+            &described,
+            target,
+            Described {
+                role: Some(access.role),
+                name: access.label.clone(),
+                value: access
+                    .value
+                    .clone()
+                    .or(text.map(|t| (**t).clone()))
+                    .or(number.map(|n| n.to_string())),
+                number,
+                range: access.range,
+                toggled: toggle.map(|t| **t),
+                disabled,
+            },
+        );
+    }
+}
+
+/// Anything focusable without a description, like buttons and links, is
+/// described as a button named after its text
+fn describe_focusables(
+    mut commands: Commands,
+    focusables: Query<(Entity, &TabIndex, Has<Disabled>)>,
+    items: Query<(), With<MenuItemAccess>>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    texts: Query<&Text>,
+    described: Query<&Described>,
+) {
+    for (entity, tab_index, disabled) in focusables.iter() {
+        // Left to `describe_menu_items`
+        if tab_index.0 < 0
+            || std::iter::once(entity)
+                .chain(parents.iter_ancestors(entity))
+                .any(|e| items.contains(e))
+        {
+            continue;
+        }
+        let name = text_of(entity, &children, &texts);
+        if name.is_empty() {
+            continue;
+        }
+        describe(
+            &mut commands,
+            &described,
+            entity,
+            Described {
+                role: Some(Role::Button),
+                name,
+                disabled,
+                ..default()
+            },
+        );
+    }
+}
+
+fn announce_dialogs(
+    stack: Res<DialogStack>,
+    localization: Res<Localization>,
+    mut announcements: EventWriter<Announce>,
+) {
+    if let Some(dialog) = stack.top() {
+        let message = localization.text(&dialog.message);
+        announcements.send(Announce(
+            format!("{} {}", localization.text(&dialog.title), message)
+                .trim()
+                .to_string(),
+        ));
+    }
+}
+
+fn announce_toasts(
+    mut toasts: EventReader<ShowToast>,
+    localization: Res<Localization>,
+    mut announcements: EventWriter<Announce>,
+) {
+    for ShowToast(toast) in toasts.read() {
+        announcements.send(Announce(localization.text(&toast.message)));
+    }
+}
+
+fn apply_announcements(
+    mut announcements: EventReader<Announce>,
+    mut announcer: Query<&mut AccessibilityNode, With<Announcer>>,
+) {
+    // Only the last one would be heard anyway
+    let Some(Announce(message)) = announcements.read().last() else {
+        return;
+    };
+    for mut node in announcer.iter_mut() {
+        node.set_name(message.clone());
+    }
+}
@@ -23,6 +23,8 @@ impl Plugin for ActionsPlugin {
 pub enum GameAction {
     Move,
     Select,
+    /// Buzz in to answer, in buzzer games
+    Buzz,
 }
 
 impl GameAction {
@@ -32,7 +34,9 @@ impl GameAction {
             .insert(Self::Move, VirtualDPad::arrow_keys())
             .insert(Self::Move, DualAxis::right_stick())
             .insert(Self::Select, KeyCode::Space)
-            .insert(Self::Select, KeyCode::Enter);
+            .insert(Self::Select, KeyCode::Enter)
+            .insert(Self::Buzz, KeyCode::KeyB)
+            .insert(Self::Buzz, GamepadButtonType::North);
         map
     }
 }
@@ -1,55 +1,264 @@
-use crate::actions::GameAction;
-use crate::loading::AudioAssets;
-use crate::GameState;
-use bevy::prelude::*;
+use std::time::Duration;
+
+use bevy::{a11y::Focus, prelude::*};
 use bevy_kira_audio::prelude::*;
-use leafwing_input_manager::action_state::ActionState;
+use bevy_quill_obsidian::focus::FocusVisible;
+
+use crate::{
+    loading::{AudioAssets, MenuAssets},
+    menu::{serde::Menu, WhichMenu},
+    settings::Settings,
+    trivia::media::QuestionMediaAssets,
+    GameState,
+};
+
+/// How long the old track takes to fade out while the new one fades in
+const CROSSFADE_SECS: f32 = 1.5;
+
+/// How loud the music is, relative to its setting, while a question clip plays
+const DUCKED_VOLUME: f64 = 0.2;
+
+/// How long the music takes to duck under a clip, and to come back after it
+const DUCK_SECS: f32 = 0.3;
+
+/// The seconds left on a question's timer when [`AudioCue::TimerTick`] starts ticking
+pub const TICKING_SECS: u32 = 5;
+
+/// The track played while a game is on
+const GAME_TRACK: &str = "game";
 
 pub struct InternalAudioPlugin;
 
-// This plugin is responsible to control the game audio
+/// This plugin plays music and sound effects on separate channels, with volumes
+/// from [`Settings`]. Game code asks for sounds by sending an [`AudioCue`].
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(AudioPlugin)
-            .add_systems(OnEnter(GameState::Playing), start_audio)
+            .add_audio_channel::<MusicChannel>()
+            .add_audio_channel::<SfxChannel>()
+            .add_audio_channel::<UiChannel>()
+            .add_event::<AudioCue>()
+            .add_event::<PlayClip>()
+            .init_resource::<CurrentMusic>()
+            .init_resource::<CurrentClip>()
+            .add_systems(OnEnter(GameState::Playing), play_game_music)
             .add_systems(
                 Update,
-                control_flying_sound.run_if(in_state(GameState::Playing)),
+                (
+                    apply_volumes.run_if(resource_changed::<Settings>),
+                    play_menu_music
+                        .run_if(in_state(GameState::Menu))
+                        .run_if(state_changed::<WhichMenu>),
+                    menu_cues.run_if(in_state(GameState::Menu)),
+                    play_cues.run_if(resource_exists::<AudioAssets>),
+                    (play_clips, restore_music).chain(),
+                ),
             );
     }
 }
 
+/// Background music, one track at a time
 #[derive(Resource)]
-struct FlyingAudio(Handle<AudioInstance>);
-
-fn start_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Res<Audio>) {
-    audio.pause();
-    let handle = audio
-        .play(audio_assets.flying.clone())
-        .looped()
-        .with_volume(0.3)
-        .handle();
-    commands.insert_resource(FlyingAudio(handle));
-}
-
-fn control_flying_sound(
-    actions: Res<ActionState<GameAction>>,
-    audio: Res<FlyingAudio>,
-    mut audio_instances: ResMut<Assets<AudioInstance>>,
-) {
-    if let Some(instance) = audio_instances.get_mut(&audio.0) {
-        match instance.state() {
-            PlaybackState::Paused { .. } => {
-                if actions.pressed(&GameAction::Move) {
-                    instance.resume(AudioTween::default());
-                }
-            }
-            PlaybackState::Playing { .. } => {
-                if !actions.pressed(&GameAction::Move) {
-                    instance.pause(AudioTween::default());
-                }
-            }
-            _ => {}
-        }
+pub struct MusicChannel;
+
+/// Game sounds, like right and wrong answers
+#[derive(Resource)]
+pub struct SfxChannel;
+
+/// Menu clicks and blips
+#[derive(Resource)]
+pub struct UiChannel;
+
+/// A sound to play in response to something happening
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioCue {
+    /// A new question appeared
+    QuestionShown,
+    /// Once a second for the last [`TICKING_SECS`] seconds of a question
+    TimerTick,
+    /// The player got it right
+    Correct,
+    /// The player got it wrong, or ran out of time
+    Wrong,
+    /// The player got several right in a row
+    Streak,
+    /// The game is over and someone won
+    Winner,
+    /// The focus moved to another menu item
+    UiMove,
+    /// A menu item was picked
+    UiSelect,
+}
+
+/// Plays the clip of a question, given by asset path, on the [`SfxChannel`].
+/// Stops the clip played before, so replaying starts over.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct PlayClip(pub String);
+
+/// The question clip playing, if any
+#[derive(Resource, Default)]
+struct CurrentClip(Option<Handle<AudioInstance>>);
+
+/// The track playing on the [`MusicChannel`]
+#[derive(Resource, Default)]
+struct CurrentMusic {
+    track: Option<String>,
+    instance: Option<Handle<AudioInstance>>,
+}
+
+fn apply_volumes(
+    settings: Res<Settings>,
+    music: Res<AudioChannel<MusicChannel>>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+    ui: Res<AudioChannel<UiChannel>>,
+) {
+    music.set_volume(settings.music_volume as f64 / 100.0);
+    sfx.set_volume(settings.sfx_volume as f64 / 100.0);
+    ui.set_volume(settings.ui_volume as f64 / 100.0);
+}
+
+/// Fades from whatever is playing into `track`, unless it's playing already
+fn crossfade(
+    track: &str,
+    assets: &AudioAssets,
+    channel: &AudioChannel<MusicChannel>,
+    current: &mut CurrentMusic,
+    instances: &mut Assets<AudioInstance>,
+) {
+    if current.track.as_deref() == Some(track) {
+        return;
+    }
+    let Some(source) = assets.track(track) else {
+        warn!("No music track called {track}");
+        return;
+    };
+    let fade = AudioTween::linear(Duration::from_secs_f32(CROSSFADE_SECS));
+    if let Some(instance) = current
+        .instance
+        .take()
+        .and_then(|handle| instances.get_mut(&handle))
+    {
+        instance.stop(fade.clone());
+    }
+    current.instance = Some(channel.play(source).looped().fade_in(fade).handle());
+    current.track = Some(track.to_string());
+}
+
+/// Switches to the music of the menu being shown. Menus without any keep what's playing.
+fn play_menu_music(
+    which: Res<State<WhichMenu>>,
+    menu_assets: Res<MenuAssets>,
+    menus: Res<Assets<Menu>>,
+    assets: Res<AudioAssets>,
+    channel: Res<AudioChannel<MusicChannel>>,
+    mut current: ResMut<CurrentMusic>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+) {
+    if let Some(track) = which.to_view(&menu_assets, &menus).music() {
+        crossfade(track, &assets, &channel, &mut current, &mut instances);
+    }
+}
+
+fn play_game_music(
+    assets: Res<AudioAssets>,
+    channel: Res<AudioChannel<MusicChannel>>,
+    mut current: ResMut<CurrentMusic>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+) {
+    crossfade(GAME_TRACK, &assets, &channel, &mut current, &mut instances);
+}
+
+/// Blips when the keyboard or gamepad moves through a menu, and clicks when
+/// switching menus
+fn menu_cues(
+    focus: Res<Focus>,
+    focus_visible: Res<FocusVisible>,
+    which: Option<Res<State<WhichMenu>>>,
+    mut cues: EventWriter<AudioCue>,
+) {
+    if which.is_some_and(|which| which.is_changed() && !which.is_added()) {
+        cues.send(AudioCue::UiSelect);
+    } else if focus.is_changed() && focus.0.is_some() && focus_visible.0 {
+        cues.send(AudioCue::UiMove);
+    }
+}
+
+fn play_cues(
+    mut cues: EventReader<AudioCue>,
+    assets: Res<AudioAssets>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+    ui: Res<AudioChannel<UiChannel>>,
+) {
+    for cue in cues.read() {
+        match cue {
+            AudioCue::QuestionShown => sfx.play(assets.question.clone()),
+            AudioCue::TimerTick => sfx.play(assets.tick.clone()),
+            AudioCue::Correct => sfx.play(assets.correct.clone()),
+            AudioCue::Wrong => sfx.play(assets.wrong.clone()),
+            AudioCue::Streak => sfx.play(assets.streak.clone()),
+            AudioCue::Winner => sfx.play(assets.fanfare.clone()),
+            AudioCue::UiMove => ui.play(assets.ui_move.clone()),
+            AudioCue::UiSelect => ui.play(assets.ui_select.clone()),
+        };
+    }
+}
+
+/// Fades the music to `volume`, relative to its setting
+fn set_music_volume(music: &CurrentMusic, instances: &mut Assets<AudioInstance>, volume: f64) {
+    if let Some(instance) = music
+        .instance
+        .as_ref()
+        .and_then(|handle| instances.get_mut(handle))
+    {
+        instance.set_volume(
+            volume,
+            AudioTween::linear(Duration::from_secs_f32(DUCK_SECS)),
+        );
+    }
+}
+
+/// Plays question clips, with the music ducked under them so they can be heard
+fn play_clips(
+    mut clips: EventReader<PlayClip>,
+    media: Res<QuestionMediaAssets>,
+    asset_server: Res<AssetServer>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+    music: Res<CurrentMusic>,
+    mut current: ResMut<CurrentClip>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+) {
+    let Some(PlayClip(path)) = clips.read().last() else {
+        return;
+    };
+    if let Some(instance) = current
+        .0
+        .take()
+        .and_then(|handle| instances.get_mut(&handle))
+    {
+        instance.stop(AudioTween::default());
+    }
+    let source = media
+        .clips
+        .get(path)
+        .cloned()
+        .unwrap_or_else(|| asset_server.load(path.clone()));
+    current.0 = Some(sfx.play(source).handle());
+    set_music_volume(&music, &mut instances, DUCKED_VOLUME);
+}
+
+/// Brings the music back up once the question clip has finished
+fn restore_music(
+    sfx: Res<AudioChannel<SfxChannel>>,
+    music: Res<CurrentMusic>,
+    mut current: ResMut<CurrentClip>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+) {
+    let Some(clip) = &current.0 else {
+        return;
+    };
+    if !matches!(sfx.state(clip), PlaybackState::Stopped) {
+        return;
     }
+    current.0 = None;
+    set_music_volume(&music, &mut instances, 1.0);
 }
@@ -0,0 +1,274 @@
+use std::{fmt, path::Path};
+
+use bevy::utils::HashMap;
+use bevy_trivia::{
+    locale::LocalizedText,
+    trivia::{
+        question::{capitalize, Question},
+        source::open_tdb::decode_url3986,
+    },
+};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// What the questions being imported are written as
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A header row naming the columns `category`, `difficulty`, `question`,
+    /// `correct_answer`, any number of `incorrect_answer...` columns, and
+    /// optionally `image` and `audio`
+    Csv,
+    /// A list of questions as found in packs, or a whole pack
+    Json,
+    /// A response from the Open Trivia Database's API, fetched with
+    /// `encode=url3986` so its text is percent-encoded
+    Opentdb,
+}
+
+impl Format {
+    /// Guesses from the file extension, or from the text itself for stdin
+    pub fn guess(path: &Path, text: &str) -> Self {
+        let csv = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+        let trimmed = text.trim_start();
+        if csv || !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+            Self::Csv
+        } else if trimmed.contains("\"type\"") {
+            // Only the Open Trivia Database says whether a question is multiple choice
+            Self::Opentdb
+        } else {
+            Self::Json
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ImportError(String);
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The questions in `text`. With a `locale`, their text is marked as being in it.
+pub fn import(
+    text: &str,
+    format: Format,
+    locale: Option<&str>,
+) -> Result<Vec<Question>, ImportError> {
+    let localize = |text: String| -> LocalizedText {
+        match locale {
+            Some(locale) => LocalizedText::Localized(HashMap::from_iter([(locale.into(), text)])),
+            None => LocalizedText::Plain(text),
+        }
+    };
+    match format {
+        Format::Csv => from_csv(text, &localize),
+        Format::Json => from_json(text),
+        Format::Opentdb => from_open_tdb(text, &localize),
+    }
+}
+
+fn from_csv(
+    text: &str,
+    localize: &dyn Fn(String) -> LocalizedText,
+) -> Result<Vec<Question>, ImportError> {
+    // Quoted cells can hold commas, newlines and doubled quotes, and rows can
+    // have fewer cells than the header
+    let rows = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .into_records()
+        .map(|record| {
+            record
+                .map(|record| record.iter().map(str::to_string).collect::<Vec<_>>())
+                .map_err(|e| ImportError(format!("not valid CSV: {e}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut rows = rows.into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| ImportError("the CSV is empty".into()))?
+        .into_iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let column = |names: &[&str]| header.iter().position(|c| names.contains(&c.as_str()));
+    let required = |names: &[&str]| {
+        column(names).ok_or_else(|| ImportError(format!("no `{}` column", names[0])))
+    };
+    let category = column(&["category"]);
+    let difficulty = column(&["difficulty"]);
+    let question = required(&["question"])?;
+    let correct = required(&["correct_answer", "correct", "answer"])?;
+    let incorrect = header
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.starts_with("incorrect"))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let image = column(&["image"]);
+    let audio = column(&["audio"]);
+
+    Ok(rows
+        .filter(|row| row.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|row| {
+            let cell = |i: Option<usize>| {
+                i.and_then(|i| row.get(i))
+                    .map(|c| c.trim().to_string())
+                    .unwrap_or_default()
+            };
+            let optional = |i: Option<usize>| Some(cell(i)).filter(|c| !c.is_empty());
+            Question {
+                category: localize(cell(category)),
+                difficulty: capitalize(&cell(difficulty)),
+                question: localize(cell(Some(question))),
+                correct_answer: localize(cell(Some(correct))),
+                incorrect_answers: incorrect
+                    .iter()
+                    .map(|i| cell(Some(*i)))
+                    .filter(|answer| !answer.is_empty())
+                    .map(localize)
+                    .collect(),
+                image: optional(image),
+                audio: optional(audio),
+            }
+        })
+        .collect())
+}
+
+fn from_json(text: &str) -> Result<Vec<Question>, ImportError> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Json {
+        Questions(Vec<Question>),
+        Pack { questions: Vec<Question> },
+    }
+    match serde_json::from_str(text) {
+        Ok(Json::Questions(questions) | Json::Pack { questions }) => Ok(questions),
+        Err(e) => Err(ImportError(format!(
+            "not a list of questions or a pack: {e}"
+        ))),
+    }
+}
+
+fn from_open_tdb(
+    text: &str,
+    localize: &dyn Fn(String) -> LocalizedText,
+) -> Result<Vec<Question>, ImportError> {
+    #[derive(Deserialize)]
+    struct OpenTdbQuestion {
+        category: String,
+        difficulty: String,
+        question: String,
+        correct_answer: String,
+        incorrect_answers: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Dump {
+        Response { results: Vec<OpenTdbQuestion> },
+        Results(Vec<OpenTdbQuestion>),
+    }
+
+    let (Dump::Response { results } | Dump::Results(results)) = serde_json::from_str(text)
+        .map_err(|e| ImportError(format!("not an Open Trivia Database response: {e}")))?;
+    let text = |text: String| localize(decode_url3986(&text));
+    Ok(results
+        .into_iter()
+        .map(|question| Question {
+            category: text(question.category),
+            difficulty: capitalize(&question.difficulty),
+            question: text(question.question),
+            correct_answer: text(question.correct_answer),
+            incorrect_answers: question.incorrect_answers.into_iter().map(text).collect(),
+            image: None,
+            audio: None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_columns_are_found_by_name() {
+        let text = "Question,Category,Answer,Incorrect 1,Incorrect 2,Difficulty\n\
+                    \"Red, green or blue?\",Colors,Red,Green,Blue,easy\n\
+                    ,,,,,\n\
+                    \"Say \"\"hi\"\"\nthen\",Words,hi,bye\n";
+        let questions = import(text, Format::Csv, None).unwrap();
+        assert_eq!(questions.len(), 2);
+        assert_eq!(questions[0].question.texts(), ["Red, green or blue?"]);
+        assert_eq!(questions[0].category.texts(), ["Colors"]);
+        assert_eq!(questions[0].difficulty, "Easy");
+        assert_eq!(questions[0].correct_answer.texts(), ["Red"]);
+        assert_eq!(questions[0].incorrect_answers.len(), 2);
+        // A short row leaves out the columns it doesn't have
+        assert_eq!(questions[1].question.texts(), ["Say \"hi\"\nthen"]);
+        assert_eq!(questions[1].incorrect_answers.len(), 1);
+        assert_eq!(questions[1].difficulty, "");
+    }
+
+    #[test]
+    fn csv_needs_a_question_and_an_answer() {
+        let error = import("question,incorrect\nWhy?,No\n", Format::Csv, None).unwrap_err();
+        assert_eq!(error.to_string(), "no `correct_answer` column");
+        assert!(import("", Format::Csv, None).is_err());
+    }
+
+    #[test]
+    fn csv_text_is_marked_with_the_locale() {
+        let questions = import(
+            "question,answer,incorrect\n¿Sí?,Sí,No\n",
+            Format::Csv,
+            Some("es"),
+        )
+        .unwrap();
+        assert_eq!(
+            questions[0].question,
+            LocalizedText::Localized(HashMap::from_iter([("es".into(), "¿Sí?".into())]))
+        );
+    }
+
+    #[test]
+    fn open_tdb_text_is_percent_decoded() {
+        let text = r#"{"response_code":0,"results":[{"type":"multiple","difficulty":"hard",
+            "category":"Science%20%26%20Nature","question":"What%27s%20%E2%80%9CH2O%E2%80%9D%3F",
+            "correct_answer":"Water","incorrect_answers":["Salt","Ice%20%26%20fire"]}]}"#;
+        assert_eq!(Format::guess(Path::new("-"), text), Format::Opentdb);
+        let questions = import(text, Format::Opentdb, None).unwrap();
+        assert_eq!(questions[0].category.texts(), ["Science & Nature"]);
+        assert_eq!(questions[0].question.texts(), ["What's “H2O”?"]);
+        assert_eq!(questions[0].difficulty, "Hard");
+        assert_eq!(questions[0].incorrect_answers[1].texts(), ["Ice & fire"]);
+    }
+
+    #[test]
+    fn json_is_a_list_or_a_pack() {
+        let question = r#"{"category":"Colors","difficulty":"Easy","question":"Red?",
+            "correct_answer":"Yes","incorrect_answers":["No"],"image":null,"audio":null}"#;
+        let list = import(&format!("[{question}]"), Format::Json, None).unwrap();
+        let pack = import(
+            &format!(r#"{{"name":"Colors","questions":[{question}]}}"#),
+            Format::Json,
+            None,
+        )
+        .unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list, pack);
+        assert_eq!(
+            Format::guess(Path::new("pack.json"), question),
+            Format::Json
+        );
+        assert_eq!(
+            Format::guess(Path::new("questions.csv"), question),
+            Format::Csv
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! Manages question packs offline: imports questions from CSV, JSON and Open
+//! Trivia Database dumps, checks them, and writes them out as `*.pack.ron` files
+//! the game can load.
+//!
+//! Reads `-` as stdin and writes to stdout unless told otherwise, so it can sit
+//! in a pipeline. Problems go to stderr, and the exit code is 1 when a pack has
+//! errors and 2 when something couldn't be read or written.
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy_trivia::trivia::{question::normalize, source::pack::QuestionPack};
+use clap::{Parser, Subcommand};
+use import::Format;
+use ron::ser::PrettyConfig;
+use validate::{Issue, Severity};
+
+mod import;
+mod validate;
+
+#[derive(Parser, Debug)]
+#[command(name = "trivia-pack", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Converts questions from other formats into a pack
+    Import {
+        /// The files to import, or `-` for stdin. Their questions end up in one pack.
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// The format of the inputs, guessed from their extension and contents if left out
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
+
+        /// The locale the text is in. Left out, the text is used for every language.
+        #[arg(short, long)]
+        locale: Option<String>,
+
+        /// The name of the pack, the first input's file name if left out
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Where to write the pack, stdout if left out
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Checks packs for duplicate questions, missing answers, too many answers
+    /// and garbled text
+    Validate {
+        /// The packs to check, or `-` for stdin
+        #[arg(required = true)]
+        packs: Vec<PathBuf>,
+
+        /// Fail on warnings too
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+
+    /// Removes questions found in an earlier pack, or earlier in the same pack
+    Dedupe {
+        /// The packs to dedupe, in order of preference
+        #[arg(required = true)]
+        packs: Vec<PathBuf>,
+
+        /// Where to write the deduped packs, under their own file names
+        #[arg(short, long, required_unless_present = "in_place")]
+        out_dir: Option<PathBuf>,
+
+        /// Overwrite the packs instead
+        #[arg(long, conflicts_with = "out_dir")]
+        in_place: bool,
+    },
+
+    /// Splits a pack into one pack per category
+    Split {
+        /// The pack to split, or `-` for stdin
+        pack: PathBuf,
+
+        /// Where to write the packs, named after their category
+        #[arg(short, long)]
+        out_dir: PathBuf,
+    },
+}
+
+/// Why the command failed, which decides the exit code
+enum Failure {
+    /// A pack has errors
+    Invalid,
+    /// Something couldn't be read, parsed or written
+    Io(String),
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Import {
+            inputs,
+            format,
+            locale,
+            name,
+            output,
+        } => run_import(&inputs, format, locale.as_deref(), name, output.as_deref()),
+        Command::Validate {
+            packs,
+            deny_warnings,
+        } => run_validate(&packs, deny_warnings),
+        Command::Dedupe {
+            packs,
+            out_dir,
+            in_place,
+        } => run_dedupe(&packs, out_dir.as_deref(), in_place),
+        Command::Split { pack, out_dir } => run_split(&pack, &out_dir),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Invalid) => ExitCode::from(1),
+        Err(Failure::Io(message)) => {
+            eprintln!("error: {message}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run_import(
+    inputs: &[PathBuf],
+    format: Option<Format>,
+    locale: Option<&str>,
+    name: Option<String>,
+    output: Option<&Path>,
+) -> Result<(), Failure> {
+    let mut pack = QuestionPack {
+        name: name.unwrap_or_else(|| pack_name(&inputs[0])),
+        questions: Vec::new(),
+    };
+    for input in inputs {
+        let (text, warning) = read_text(input)?;
+        if let Some(warning) = warning {
+            report(input, &[Issue::file(Severity::Warning, warning)]);
+        }
+        let format = format.unwrap_or_else(|| Format::guess(input, &text));
+        let questions = import::import(&text, format, locale)
+            .map_err(|e| Failure::Io(format!("{}: {e}", input.display())))?;
+        pack.questions.extend(questions);
+    }
+
+    let issues = validate::validate(&pack);
+    let label = output.unwrap_or(Path::new("-"));
+    report(label, &issues);
+    write_pack(output, &pack)?;
+    eprintln!("{}: {} questions", label.display(), pack.questions.len());
+    check(&issues, false)
+}
+
+fn run_validate(packs: &[PathBuf], deny_warnings: bool) -> Result<(), Failure> {
+    let mut result = Ok(());
+    for path in packs {
+        let pack = read_pack(path)?;
+        let issues = validate::validate(&pack);
+        report(path, &issues);
+        if issues.is_empty() {
+            eprintln!("{}: ok, {} questions", path.display(), pack.questions.len());
+        }
+        if check(&issues, deny_warnings).is_err() {
+            result = Err(Failure::Invalid);
+        }
+    }
+    result
+}
+
+fn run_dedupe(packs: &[PathBuf], out_dir: Option<&Path>, in_place: bool) -> Result<(), Failure> {
+    let mut read = packs
+        .iter()
+        .map(|path| read_pack(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let removed = validate::dedupe(&mut read);
+    if let Some(out_dir) = out_dir {
+        fs::create_dir_all(out_dir)?;
+    }
+
+    for ((path, pack), removed) in packs.iter().zip(&read).zip(removed) {
+        let output = if in_place {
+            path.clone()
+        } else {
+            let file = path
+                .file_name()
+                .ok_or_else(|| Failure::Io(format!("{}: not a file name", path.display())))?;
+            out_dir.expect("required by clap").join(file)
+        };
+        write_pack(Some(&output), pack)?;
+        eprintln!(
+            "{}: removed {removed} duplicates, {} questions left",
+            output.display(),
+            pack.questions.len()
+        );
+    }
+    Ok(())
+}
+
+fn run_split(path: &Path, out_dir: &Path) -> Result<(), Failure> {
+    let pack = read_pack(path)?;
+    fs::create_dir_all(out_dir)?;
+    let categories = validate::split(&pack);
+    let names = file_names(categories.iter().map(|(category, _)| category.as_str()));
+    for ((category, questions), name) in categories.into_iter().zip(names) {
+        let output = out_dir.join(name);
+        let count = questions.len();
+        write_pack(
+            Some(&output),
+            &QuestionPack {
+                name: category,
+                questions,
+            },
+        )?;
+        eprintln!("{}: {count} questions", output.display());
+    }
+    Ok(())
+}
+
+/// The pack's name when none is given: the file name without its extensions
+fn pack_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .filter(|name| !name.is_empty() && *name != "-")
+        .unwrap_or("Imported")
+        .to_string()
+}
+
+/// A file name made from `text`, such as `science_nature` for "Science & Nature"
+fn slug(text: &str) -> String {
+    let slug = normalize(text).replace(' ', "_");
+    if slug.is_empty() {
+        "uncategorized".to_string()
+    } else {
+        slug
+    }
+}
+
+/// The file names of the packs split by `categories`, made from their slugs.
+/// Categories with the same slug, such as "Science & Nature" and "Science:
+/// Nature", are numbered after the first so they don't overwrite each other.
+fn file_names<'a>(categories: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut taken = HashSet::new();
+    categories
+        .into_iter()
+        .map(|category| {
+            let slug = slug(category);
+            let name = std::iter::once(slug.clone())
+                .chain((2..).map(|n| format!("{slug}_{n}")))
+                .find(|name| !taken.contains(name))
+                .expect("numbered names never run out");
+            taken.insert(name.clone());
+            format!("{name}.pack.ron")
+        })
+        .collect()
+}
+
+/// Reads `path`, or stdin for `-`, as text. Text that isn't UTF-8 is read as
+/// Windows-1252, which is what spreadsheets tend to export, with a warning.
+fn read_text(path: &Path) -> Result<(String, Option<String>), Failure> {
+    let mut bytes = Vec::new();
+    if path == Path::new("-") {
+        io::stdin().read_to_end(&mut bytes)?;
+    } else {
+        bytes = fs::read(path).map_err(|e| Failure::Io(format!("{}: {e}", path.display())))?;
+    }
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok((text.to_string(), None)),
+        Err(e) => Ok((
+            encoding_rs::WINDOWS_1252
+                .decode_without_bom_handling(bytes)
+                .0
+                .into_owned(),
+            Some(format!(
+                "not valid UTF-8 (at byte {}), read as Windows-1252",
+                e.valid_up_to()
+            )),
+        )),
+    }
+}
+
+fn read_pack(path: &Path) -> Result<QuestionPack, Failure> {
+    let (text, warning) = read_text(path)?;
+    if let Some(warning) = warning {
+        report(path, &[Issue::file(Severity::Warning, warning)]);
+    }
+    ron::from_str(&text).map_err(|e| Failure::Io(format!("{}: {e}", path.display())))
+}
+
+/// Writes `pack` to `output`, or stdout when there's none
+fn write_pack(output: Option<&Path>, pack: &QuestionPack) -> Result<(), Failure> {
+    let config = PrettyConfig::new().struct_names(true);
+    let text = ron::ser::to_string_pretty(pack, config)
+        .map_err(|e| Failure::Io(format!("couldn't write the pack: {e}")))?;
+    match output {
+        Some(path) if path != Path::new("-") => {
+            fs::write(path, text + "\n")
+                .map_err(|e| Failure::Io(format!("{}: {e}", path.display())))?;
+        }
+        _ => writeln!(io::stdout().lock(), "{text}")?,
+    }
+    Ok(())
+}
+
+fn report(path: &Path, issues: &[Issue]) {
+    for issue in issues {
+        eprintln!("{}: {issue}", path.display());
+    }
+}
+
+/// Fails when there are errors, or any issues at all when denying warnings
+fn check(issues: &[Issue], deny_warnings: bool) -> Result<(), Failure> {
+    let failed = issues
+        .iter()
+        .any(|issue| issue.severity == Severity::Error || deny_warnings);
+    if failed {
+        Err(Failure::Invalid)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_are_lowercase_words_joined_by_underscores() {
+        assert_eq!(slug("Science & Nature"), "science_nature");
+        assert_eq!(slug("  "), "uncategorized");
+    }
+
+    #[test]
+    fn categories_with_the_same_slug_get_their_own_files() {
+        assert_eq!(
+            file_names([
+                "Science & Nature",
+                "History",
+                "science: nature",
+                "SCIENCE NATURE",
+            ]),
+            [
+                "science_nature.pack.ron",
+                "history.pack.ron",
+                "science_nature_2.pack.ron",
+                "science_nature_3.pack.ron",
+            ]
+        );
+    }
+
+    #[test]
+    fn numbered_names_skip_ones_already_taken() {
+        assert_eq!(
+            file_names(["History 2", "History", "history!"]),
+            [
+                "history_2.pack.ron",
+                "history.pack.ron",
+                "history_3.pack.ron",
+            ]
+        );
+    }
+}
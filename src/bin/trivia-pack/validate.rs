@@ -0,0 +1,347 @@
+use std::fmt;
+
+use bevy::utils::{HashMap, HashSet};
+use bevy_trivia::{
+    locale::{LocalizedText, DEFAULT_LOCALE},
+    trivia::{
+        question::{normalize, Question, DIFFICULTIES, MAX_ANSWERS},
+        source::pack::QuestionPack,
+    },
+};
+
+/// Bits of text that show up when text was encoded one way and read another
+const MOJIBAKE: [&str; 6] = ["Ã©", "Ã¨", "Ã±", "Ã¼", "â€", "Â "];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The question can't be asked as it is
+    Error,
+    /// The question can be asked, but probably isn't what was meant
+    Warning,
+}
+
+/// Something wrong with a pack, or one of its questions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    /// The question's position in the pack, counting from 1
+    pub question: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Issue {
+    /// An issue with the whole file rather than a question
+    pub fn file(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            question: None,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(question) = self.question {
+            write!(f, "question {question}: ")?;
+        }
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+/// Everything wrong with the questions of `pack`
+pub fn validate(pack: &QuestionPack) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if pack.name.trim().is_empty() {
+        issues.push(Issue::file(Severity::Warning, "the pack has no name"));
+    }
+    if pack.questions.is_empty() {
+        issues.push(Issue::file(Severity::Error, "the pack has no questions"));
+    }
+
+    let mut seen = HashMap::<String, usize>::new();
+    for (i, question) in pack.questions.iter().enumerate() {
+        let number = i + 1;
+        let mut issue = |severity, message: String| {
+            issues.push(Issue {
+                question: Some(number),
+                severity,
+                message,
+            })
+        };
+
+        if is_blank(&question.question) {
+            issue(Severity::Error, "the question is empty".into());
+        }
+        if is_blank(&question.correct_answer) {
+            issue(Severity::Error, "the right answer is missing".into());
+        }
+        if question.incorrect_answers.is_empty() {
+            issue(Severity::Error, "there are no wrong answers".into());
+        }
+        if question.incorrect_answers.iter().any(is_blank) {
+            issue(Severity::Error, "one of the wrong answers is empty".into());
+        }
+        let answers = question.incorrect_answers.len() + 1;
+        if answers > MAX_ANSWERS {
+            issue(
+                Severity::Error,
+                format!("{answers} answers, but at most {MAX_ANSWERS} fit on screen"),
+            );
+        }
+
+        let mut answer_keys = HashSet::new();
+        for answer in std::iter::once(&question.correct_answer).chain(&question.incorrect_answers) {
+            let keys = answer
+                .texts()
+                .into_iter()
+                .map(normalize)
+                .collect::<Vec<_>>();
+            if keys
+                .iter()
+                .any(|key| !key.is_empty() && answer_keys.contains(key))
+            {
+                issue(
+                    Severity::Error,
+                    format!("the answer {:?} is given twice", answer.texts()[0]),
+                );
+            }
+            answer_keys.extend(keys);
+        }
+
+        if !DIFFICULTIES.contains(&question.difficulty.as_str()) {
+            issue(
+                Severity::Warning,
+                format!(
+                    "the difficulty {:?} isn't one of {}",
+                    question.difficulty,
+                    DIFFICULTIES.join(", ")
+                ),
+            );
+        }
+        if is_blank(&question.category) {
+            issue(Severity::Warning, "the question has no category".into());
+        }
+
+        for text in texts(question) {
+            if let Some(problem) = encoding_problem(text) {
+                issue(Severity::Warning, format!("{problem} in {text:?}"));
+            }
+        }
+
+        for key in question.keys().into_iter().filter(|key| !key.is_empty()) {
+            if let Some(first) = seen.get(&key) {
+                issue(
+                    Severity::Error,
+                    format!("the same question as question {first}"),
+                );
+                break;
+            }
+            seen.insert(key, number);
+        }
+    }
+    issues
+}
+
+/// Removes every question already asked by an earlier one, in the same pack or
+/// an earlier pack. Returns how many were removed from each pack.
+pub fn dedupe(packs: &mut [QuestionPack]) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    packs
+        .iter_mut()
+        .map(|pack| {
+            let before = pack.questions.len();
+            pack.questions.retain(|question| {
+                let keys = question.keys();
+                let duplicate = keys.iter().any(|key| !key.is_empty() && seen.contains(key));
+                seen.extend(keys);
+                !duplicate
+            });
+            before - pack.questions.len()
+        })
+        .collect()
+}
+
+/// The questions of `pack` grouped by category, in the order the categories
+/// first appear
+pub fn split(pack: &QuestionPack) -> Vec<(String, Vec<Question>)> {
+    let mut categories: Vec<(String, Vec<Question>)> = Vec::new();
+    for question in &pack.questions {
+        let category = default_text(&question.category);
+        match categories.iter_mut().find(|(c, _)| *c == category) {
+            Some((_, questions)) => questions.push(question.clone()),
+            None => categories.push((category, vec![question.clone()])),
+        }
+    }
+    categories
+}
+
+/// The text packs are split and named by, when it's given in several languages
+fn default_text(text: &LocalizedText) -> String {
+    text.get(&[DEFAULT_LOCALE.to_string()])
+        .or_else(|| text.texts().into_iter().min())
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+fn is_blank(text: &LocalizedText) -> bool {
+    text.texts().iter().all(|text| text.trim().is_empty())
+}
+
+/// Every bit of text shown for `question`, in every language
+fn texts(question: &Question) -> impl Iterator<Item = &str> {
+    [
+        &question.category,
+        &question.question,
+        &question.correct_answer,
+    ]
+    .into_iter()
+    .chain(&question.incorrect_answers)
+    .flat_map(LocalizedText::texts)
+}
+
+/// What looks garbled about `text`, if anything
+fn encoding_problem(text: &str) -> Option<&'static str> {
+    if text.contains('\u{fffd}') {
+        Some("a replacement character, where text couldn't be decoded")
+    } else if text
+        .chars()
+        .any(|c| c.is_control() && c != '\n' && c != '\t')
+    {
+        Some("a control character")
+    } else if MOJIBAKE.iter().any(|m| text.contains(m)) {
+        Some("UTF-8 read as another encoding")
+    } else if ["&quot;", "&amp;", "&#039;", "&#39;", "&lt;", "&gt;"]
+        .iter()
+        .any(|e| text.contains(e))
+    {
+        Some("an HTML entity that wasn't decoded")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(category: &str, text: &str, correct: &str, incorrect: &[&str]) -> Question {
+        Question {
+            category: category.to_string().into(),
+            difficulty: "Easy".to_string(),
+            question: text.to_string().into(),
+            correct_answer: correct.to_string().into(),
+            incorrect_answers: incorrect.iter().map(|a| a.to_string().into()).collect(),
+            image: None,
+            audio: None,
+        }
+    }
+
+    fn pack(questions: Vec<Question>) -> QuestionPack {
+        QuestionPack {
+            name: "Test".to_string(),
+            questions,
+        }
+    }
+
+    fn issues_of(pack: &QuestionPack) -> Vec<(Option<usize>, Severity)> {
+        validate(pack)
+            .into_iter()
+            .map(|issue| (issue.question, issue.severity))
+            .collect()
+    }
+
+    #[test]
+    fn good_packs_have_no_issues() {
+        let pack = pack(vec![
+            question("Colors", "Red?", "Yes", &["No"]),
+            question("Colors", "Blue?", "No", &["Yes", "Maybe"]),
+        ]);
+        assert_eq!(validate(&pack), []);
+    }
+
+    #[test]
+    fn broken_questions_are_errors() {
+        let pack = pack(vec![
+            question("Colors", "Red?", "", &["No"]),
+            question("Colors", "Blue?", "Yes", &[]),
+            question("Colors", "Green?", "Yes", &["yes!"]),
+            question("Colors", "red", "Yes", &["No"]),
+        ]);
+        assert_eq!(
+            issues_of(&pack),
+            [
+                (Some(1), Severity::Error),
+                (Some(2), Severity::Error),
+                (Some(3), Severity::Error),
+                (Some(4), Severity::Error),
+            ]
+        );
+        assert_eq!(
+            validate(&pack)[3].message,
+            "the same question as question 1"
+        );
+    }
+
+    #[test]
+    fn doubtful_questions_are_warnings() {
+        let mut odd = question("", "Say &quot;hi&quot;?", "Yes", &["No"]);
+        odd.difficulty = "Tricky".to_string();
+        let pack = pack(vec![odd]);
+        assert_eq!(
+            issues_of(&pack),
+            [
+                (Some(1), Severity::Warning),
+                (Some(1), Severity::Warning),
+                (Some(1), Severity::Warning),
+            ]
+        );
+        assert_eq!(
+            issues_of(&QuestionPack::default()),
+            [(None, Severity::Warning), (None, Severity::Error)]
+        );
+    }
+
+    #[test]
+    fn dedupe_keeps_the_first_of_each_question() {
+        let mut packs = [
+            pack(vec![
+                question("Colors", "Red?", "Yes", &["No"]),
+                question("Colors", "RED?", "Yes", &["No"]),
+            ]),
+            pack(vec![
+                question("Colors", "red", "Yes", &["No"]),
+                question("Colors", "Blue?", "Yes", &["No"]),
+            ]),
+        ];
+        assert_eq!(dedupe(&mut packs), [1, 1]);
+        assert_eq!(packs[0].questions[0].question.texts(), ["Red?"]);
+        assert_eq!(packs[1].questions[0].question.texts(), ["Blue?"]);
+    }
+
+    #[test]
+    fn split_groups_by_category_in_order() {
+        let mut localized = question("", "Green?", "Yes", &["No"]);
+        localized.category = LocalizedText::Localized(HashMap::from_iter([
+            ("es".to_string(), "Colores".to_string()),
+            (DEFAULT_LOCALE.to_string(), "Colors".to_string()),
+        ]));
+        let pack = pack(vec![
+            question("Colors", "Red?", "Yes", &["No"]),
+            question("Shapes", "Round?", "Yes", &["No"]),
+            localized,
+        ]);
+        let split = split(&pack)
+            .into_iter()
+            .map(|(category, questions)| (category, questions.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            split,
+            [("Colors".to_string(), 2), ("Shapes".to_string(), 1)]
+        );
+    }
+}
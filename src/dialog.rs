@@ -0,0 +1,445 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{a11y::Focus, ecs::system::SystemState, prelude::*};
+use bevy_mod_picking::prelude::{ListenerInput, On, Pickable};
+use bevy_mod_stylebuilder::{
+    StyleBuilder, StyleBuilderBackground, StyleBuilderBorderColor, StyleBuilderBorderRadius,
+    StyleBuilderFont, StyleBuilderLayout, StyleBuilderZIndex,
+};
+use bevy_quill::*;
+use bevy_quill_obsidian::{
+    controls::{Button, ButtonVariant},
+    focus::{AutoFocus, KeyPressEvent, TabGroup},
+    size::Size,
+    typography,
+};
+
+use crate::{
+    layout::{use_layout, ScreenLayout},
+    locale::Localization,
+    menu::{
+        action::MenuAction,
+        navigation::{navigate, Direction},
+    },
+    theme::{use_theme, Theme},
+};
+
+/// How many toasts are on screen at once, the rest wait their turn
+const MAX_TOASTS: usize = 3;
+
+/// How long a toast stays on screen unless it says otherwise
+const TOAST_SECS: f32 = 4.0;
+
+/// Modal dialogs and toast notifications, shown above everything else and
+/// requested from any system with [`ShowDialog`] and [`ShowToast`] events
+pub struct DialogPlugin;
+
+impl Plugin for DialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShowDialog>()
+            .add_event::<CloseDialog>()
+            .add_event::<ShowToast>()
+            .init_resource::<DialogStack>()
+            .init_resource::<Toasts>()
+            .add_systems(Startup, setup_dialogs)
+            .add_systems(
+                Update,
+                (apply_dialog_events, apply_toast_events, tick_toasts),
+            );
+    }
+}
+
+/// A modal dialog. Any of its buttons closes it.
+/// The text is made of localization keys, translated when shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dialog {
+    pub title: String,
+    pub message: String,
+    pub buttons: Vec<DialogButton>,
+}
+
+impl Dialog {
+    /// A dialog with only an "OK" button
+    pub fn alert(title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            buttons: vec![DialogButton::new("dialog.ok").primary()],
+        }
+    }
+
+    /// A dialog asking whether to perform `then`
+    pub fn confirm(prompt: impl Into<String>, then: MenuAction) -> Self {
+        Self {
+            title: prompt.into(),
+            message: String::new(),
+            buttons: vec![
+                DialogButton::new("dialog.cancel"),
+                DialogButton::new("dialog.confirm").primary().action(then),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DialogButton {
+    pub label: String,
+    /// Performed after the dialog closes
+    pub action: Option<MenuAction>,
+    pub primary: bool,
+}
+
+impl DialogButton {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            action: None,
+            primary: false,
+        }
+    }
+
+    pub fn action(mut self, action: MenuAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn primary(mut self) -> Self {
+        self.primary = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToastKind {
+    #[default]
+    Info,
+    Success,
+    Error,
+}
+
+/// A short message, its text a localization key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub message: String,
+    pub kind: ToastKind,
+    pub duration: f32,
+}
+
+impl Toast {
+    pub fn new(kind: ToastKind, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            kind,
+            duration: TOAST_SECS,
+        }
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(ToastKind::Info, message)
+    }
+
+    pub fn success(message: impl Into<String>) -> Self {
+        Self::new(ToastKind::Success, message)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(ToastKind::Error, message)
+    }
+}
+
+/// Opens a dialog on top of any already open
+#[derive(Event, Debug, Clone)]
+pub struct ShowDialog(pub Dialog);
+
+/// Closes the topmost dialog without performing any of its actions
+#[derive(Event, Debug, Clone, Default)]
+pub struct CloseDialog;
+
+/// Queues a toast
+#[derive(Event, Debug, Clone)]
+pub struct ShowToast(pub Toast);
+
+/// The open dialogs, the last one on top, with whatever had focus before each opened
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct DialogStack(Vec<(Dialog, Option<Entity>)>);
+
+impl DialogStack {
+    pub fn is_open(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    /// The dialog on top, the one the player sees
+    pub fn top(&self) -> Option<&Dialog> {
+        self.0.last().map(|(dialog, _)| dialog)
+    }
+}
+
+/// The toasts on screen with the time each one goes away, and those waiting their turn
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+struct Toasts {
+    shown: Vec<(Toast, Duration)>,
+    pending: VecDeque<Toast>,
+}
+
+/// Closes the topmost dialog, giving focus back to what had it before
+fn close_dialog(world: &mut World) -> Option<Dialog> {
+    let (dialog, focus) = world.resource_mut::<DialogStack>().0.pop()?;
+    world.resource_mut::<Focus>().0 = focus;
+    Some(dialog)
+}
+
+fn setup_dialogs(mut commands: Commands) {
+    commands.spawn(DialogLayer.to_root());
+}
+
+fn apply_dialog_events(
+    mut shows: EventReader<ShowDialog>,
+    mut closes: EventReader<CloseDialog>,
+    mut commands: Commands,
+    mut stack: ResMut<DialogStack>,
+    focus: Res<Focus>,
+) {
+    for ShowDialog(dialog) in shows.read() {
+        stack.0.push((dialog.clone(), focus.0));
+    }
+    for _ in closes.read() {
+        commands.add(|world: &mut World| {
+            close_dialog(world);
+        });
+    }
+}
+
+fn apply_toast_events(mut events: EventReader<ShowToast>, mut toasts: ResMut<Toasts>) {
+    for ShowToast(toast) in events.read() {
+        toasts.pending.push_back(toast.clone());
+    }
+}
+
+fn tick_toasts(time: Res<Time<Real>>, mut toasts: ResMut<Toasts>) {
+    let now = time.elapsed();
+    let expired = toasts.shown.iter().any(|(_, until)| *until <= now);
+    let room = toasts.shown.len() < MAX_TOASTS && !toasts.pending.is_empty();
+    // Only touch the resource when something changes, the view re-renders when it does
+    if !expired && !room {
+        return;
+    }
+    toasts.shown.retain(|(_, until)| *until > now);
+    while toasts.shown.len() < MAX_TOASTS {
+        let Some(toast) = toasts.pending.pop_front() else {
+            break;
+        };
+        let until = now + Duration::from_secs_f32(toast.duration);
+        toasts.shown.push((toast, until));
+    }
+}
+
+/// The root view for dialogs and toasts, above every other view
+#[derive(Clone, PartialEq)]
+struct DialogLayer;
+
+impl ViewTemplate for DialogLayer {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let dialog = cx
+            .use_resource::<DialogStack>()
+            .0
+            .last()
+            .map(|(dialog, _)| dialog.clone());
+        let toasts = cx
+            .use_resource::<Toasts>()
+            .shown
+            .iter()
+            .map(|(toast, _)| toast.clone())
+            .collect::<Vec<_>>();
+        let theme = use_theme(cx);
+        let layout = use_layout(cx);
+        let localization = cx.use_resource::<Localization>();
+
+        Element::<NodeBundle>::new()
+            .named("DialogLayer")
+            .style(style_dialog_layer)
+            .insert_dyn(|_| Pickable::IGNORE, ())
+            .children((
+                dialog.map(|dialog| DialogView { dialog }),
+                Element::<NodeBundle>::new()
+                    .style_dyn(style_toasts, layout)
+                    .insert_dyn(|_| Pickable::IGNORE, ())
+                    .children(
+                        toasts
+                            .into_iter()
+                            .map(|toast| {
+                                Element::<NodeBundle>::new()
+                                    .style(typography::text_default)
+                                    .style_dyn(style_toast, (theme, toast.kind))
+                                    .children(localization.text(&toast.message))
+                                    .into_view_child()
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+            ))
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct DialogView {
+    dialog: Dialog,
+}
+
+impl ViewTemplate for DialogView {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let id = cx.create_entity();
+        let dialog = self.dialog.clone();
+        let localization = cx.use_resource::<Localization>().clone();
+        let theme = use_theme(cx);
+
+        Element::<NodeBundle>::for_entity(id)
+            .named("Dialog")
+            .style(style_dialog_overlay)
+            // Trap focus inside the dialog, and close it with Escape
+            .insert_dyn(
+                move |_| {
+                    (
+                        TabGroup {
+                            modal: true,
+                            ..default()
+                        },
+                        AutoFocus,
+                        On::<KeyPressEvent>::run(move |world: &mut World| {
+                            let mut state =
+                                SystemState::<ResMut<ListenerInput<KeyPressEvent>>>::new(world);
+                            let key_code = state.get_mut(world).key_code;
+                            let handled = match key_code {
+                                KeyCode::Escape => close_dialog(world).is_some(),
+                                key_code => Direction::from_key(key_code)
+                                    .is_some_and(|direction| navigate(world, id, direction)),
+                            };
+                            if handled {
+                                state.get_mut(world).stop_propagation();
+                            }
+                        }),
+                    )
+                },
+                (),
+            )
+            .children(
+                Element::<NodeBundle>::new()
+                    .style(typography::text_default)
+                    .style_dyn(style_dialog, theme)
+                    .children((
+                        Element::<NodeBundle>::new()
+                            .style(typography::text_strong)
+                            .style_dyn(style_dialog_title, theme)
+                            .children(localization.text(&dialog.title)),
+                        Cond::new(
+                            !dialog.message.is_empty(),
+                            localization.text(&dialog.message),
+                            (),
+                        ),
+                        Element::<NodeBundle>::new()
+                            .style(style_dialog_buttons)
+                            .children(
+                                dialog
+                                    .buttons
+                                    .iter()
+                                    .map(|button| {
+                                        let action = button.action.clone();
+                                        Button::new()
+                                            .size(Size::Md)
+                                            .variant(if button.primary {
+                                                ButtonVariant::Primary
+                                            } else {
+                                                ButtonVariant::Default
+                                            })
+                                            .on_click(cx.create_callback(
+                                                move |world: &mut World| {
+                                                    close_dialog(world);
+                                                    if let Some(action) = &action {
+                                                        action.run(world);
+                                                    }
+                                                },
+                                            ))
+                                            .children(localization.text(&button.label))
+                                            .into_view_child()
+                                    })
+                                    .collect::<Vec<_>>(),
+                            ),
+                    )),
+            )
+    }
+}
+
+fn style_dialog_layer(ss: &mut StyleBuilder) {
+    ss.position(PositionType::Absolute)
+        .left(0)
+        .right(0)
+        .top(0)
+        .bottom(0)
+        .z_index(ZIndex::Global(100));
+}
+
+fn style_dialog_overlay(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .position(PositionType::Absolute)
+        .left(0)
+        .right(0)
+        .top(0)
+        .bottom(0)
+        .align_items(AlignItems::Center)
+        .justify_content(JustifyContent::Center)
+        .background_color(Color::srgba(0.0, 0.0, 0.0, 0.6));
+}
+
+fn style_dialog(theme: Theme, ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .align_items(AlignItems::Center)
+        .min_width(Val::Px(300.0))
+        .max_width(Val::Percent(90.0))
+        .padding(theme.spacing.gap * 2.0)
+        .row_gap(theme.spacing.gap * 1.5)
+        .border(1)
+        .border_color(theme.palette.foreground.with_alpha(0.2))
+        .border_radius(theme.radius)
+        .background_color(theme.palette.surface)
+        .font_size(theme.font_sizes.body)
+        .color(theme.palette.foreground);
+}
+
+fn style_dialog_title(theme: Theme, ss: &mut StyleBuilder) {
+    ss.font_size(theme.font_sizes.heading);
+}
+
+fn style_dialog_buttons(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Row)
+        .column_gap(10);
+}
+
+/// Toasts stay clear of notches and system bars in the corner they're shown in
+fn style_toasts(layout: ScreenLayout, ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .position(PositionType::Absolute)
+        .right(20.0 + layout.insets.right)
+        .bottom(20.0 + layout.insets.bottom)
+        .row_gap(8)
+        .align_items(AlignItems::End);
+}
+
+fn style_toast((theme, kind): (Theme, ToastKind), ss: &mut StyleBuilder) {
+    ss.padding((16, 8))
+        .border(2)
+        .border_radius(theme.radius * 0.75)
+        .border_color(match kind {
+            ToastKind::Info => theme.palette.foreground,
+            ToastKind::Success => theme.palette.success,
+            ToastKind::Error => theme.palette.destructive,
+        })
+        .max_width(Val::Px(400.0))
+        .background_color(theme.palette.surface)
+        .font_size(theme.font_sizes.body)
+        .color(theme.palette.foreground);
+}
@@ -0,0 +1,624 @@
+use bevy::{prelude::*, utils::HashSet};
+use ron::ser::PrettyConfig;
+
+use crate::{
+    dialog::{ShowToast, Toast},
+    locale::{Localization, LocalizedText, DEFAULT_LOCALE},
+    menu::{
+        action::RegisterMenuAction,
+        condition::{sync_menu_values, MenuValue, MenuValues},
+        widgets::{dropdown::DropdownSelected, text_input::TextInputEditor},
+        WhichMenu,
+    },
+    storage,
+    trivia::{
+        question::{Question, DIFFICULTIES, MAX_ANSWERS},
+        source::{pack::QuestionPack, StringSource, TriviaSource},
+    },
+};
+
+/// Where the editor keeps its pack, in the user data directory
+pub const LOCAL_PACK: &str = "packs/local.pack.ron";
+
+/// Where images picked from elsewhere are copied to, in the user data directory
+const IMAGES_DIR: &str = "images";
+
+/// How much of each question the question list shows, in characters
+const TITLE_LENGTH: usize = 40;
+
+/// Lets hosts write their own questions in the pack editor menu, and saves them
+/// to the user data directory
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditedPack>()
+            .register_menu_action("editor_new", new_question)
+            .register_menu_action("editor_delete", delete_question)
+            .register_menu_action("editor_move_up", move_question(-1))
+            .register_menu_action("editor_move_down", move_question(1))
+            .register_menu_action("editor_save", save_pack)
+            .add_systems(OnEnter(WhichMenu::PackEditor), open_local_pack)
+            .add_systems(
+                Update,
+                (
+                    apply_editor_form.run_if(resource_changed::<MenuValues>),
+                    fill_editor_form,
+                )
+                    .chain()
+                    .after(sync_menu_values)
+                    .run_if(in_state(WhichMenu::PackEditor)),
+            );
+    }
+}
+
+/// The pack open in the editor, and which of its questions the form shows.
+/// There is always at least one question, blank in a new pack.
+///
+/// Every field of the form is edited by the widget with the same name, like
+/// [`crate::settings::Settings`], with text in the language the game is shown in.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct EditedPack {
+    pub pack: QuestionPack,
+
+    /// The index of the question in the form
+    pub current: usize,
+
+    /// The form shows another question, or nothing yet, and needs filling in
+    stale: bool,
+
+    /// The pack has been read from the user data directory
+    opened: bool,
+}
+
+impl Default for EditedPack {
+    fn default() -> Self {
+        Self {
+            pack: QuestionPack {
+                name: "My Pack".to_string(),
+                questions: vec![blank_question()],
+            },
+            current: 0,
+            stale: true,
+            opened: false,
+        }
+    }
+}
+
+impl EditedPack {
+    /// The question in the form
+    pub fn question(&self) -> &Question {
+        &self.pack.questions[self.current]
+    }
+
+    /// Shows the question at `index` in the form, or the last one past the end
+    fn select(&mut self, index: usize) {
+        self.current = index.min(self.pack.questions.len() - 1);
+        self.stale = true;
+    }
+
+    /// The value of the form field called `name`, with text in `locale`
+    fn value(&self, name: &str, locale: &str) -> Option<MenuValue> {
+        let question = self.question();
+        let text = |text: &LocalizedText| MenuValue::Text {
+            value: text
+                .get(&[locale.to_string()])
+                .unwrap_or_default()
+                .to_string(),
+            valid: true,
+        };
+        match name {
+            "pack_name" => Some(MenuValue::Text {
+                value: self.pack.name.clone(),
+                valid: true,
+            }),
+            "question_list" => Some(MenuValue::Selection(HashSet::from_iter([self.current]))),
+            "question" => Some(text(&question.question)),
+            "correct_answer" => Some(text(&question.correct_answer)),
+            "category" => Some(text(&question.category)),
+            "difficulty" => Some(MenuValue::Selection(
+                DIFFICULTIES
+                    .iter()
+                    .position(|d| *d == question.difficulty)
+                    .into_iter()
+                    .collect(),
+            )),
+            "image" => Some(MenuValue::Text {
+                value: question.image.clone().unwrap_or_default(),
+                valid: true,
+            }),
+            name => {
+                let index = wrong_answer_index(name)?;
+                Some(text(
+                    question
+                        .incorrect_answers
+                        .get(index)
+                        .unwrap_or(&LocalizedText::default()),
+                ))
+            }
+        }
+    }
+
+    /// Updates the form field called `name` from the widget editing it
+    fn apply(&mut self, name: &str, value: &MenuValue, locale: &str) {
+        let current = self.current;
+        let question = &mut self.pack.questions[current];
+        match (name, value) {
+            ("pack_name", MenuValue::Text { value, .. }) => self.pack.name = value.clone(),
+            ("question", MenuValue::Text { value, .. }) => {
+                question.question.set(locale, value.clone())
+            }
+            ("correct_answer", MenuValue::Text { value, .. }) => {
+                question.correct_answer.set(locale, value.clone())
+            }
+            ("category", MenuValue::Text { value, .. }) => {
+                question.category.set(locale, value.clone())
+            }
+            ("difficulty", MenuValue::Selection(selected)) => {
+                if let Some(difficulty) = selected.iter().find_map(|i| DIFFICULTIES.get(*i)) {
+                    question.difficulty = difficulty.to_string();
+                }
+            }
+            // Half typed paths would fail to load in the preview
+            ("image", MenuValue::Text { value, valid: true }) => {
+                question.image = Some(value.trim().to_string()).filter(|path| !path.is_empty());
+            }
+            (name, MenuValue::Text { value, .. }) => {
+                let Some(index) = wrong_answer_index(name) else {
+                    return;
+                };
+                let answers = &mut question.incorrect_answers;
+                if answers.len() <= index {
+                    answers.resize(index + 1, LocalizedText::default());
+                }
+                answers[index].set(locale, value.clone());
+                // Blanks in between keep the answers after them in their fields
+                while answers.last().is_some_and(is_blank) {
+                    answers.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The entries of the question list, numbered, with long questions cut short
+    fn titles(&self, chain: &[String]) -> Vec<String> {
+        self.pack
+            .questions
+            .iter()
+            .enumerate()
+            .map(|(i, question)| {
+                let text = question.question.get(chain).unwrap_or_default().trim();
+                let mut title = format!("{}. ", i + 1);
+                title.extend(text.chars().take(TITLE_LENGTH));
+                if text.chars().count() > TITLE_LENGTH {
+                    title.push('…');
+                }
+                title
+            })
+            .collect()
+    }
+
+    /// The pack as it's saved, without blank wrong answers or untouched questions
+    fn to_saved(&self) -> QuestionPack {
+        let mut pack = self.pack.clone();
+        pack.questions.retain(|question| {
+            !is_blank(&question.question) || !is_blank(&question.correct_answer)
+        });
+        for question in &mut pack.questions {
+            question
+                .incorrect_answers
+                .retain(|answer| !is_blank(answer));
+        }
+        pack
+    }
+}
+
+/// The index among the wrong answers edited by the field called `name`, which
+/// are `wrong_answer_1` and up
+fn wrong_answer_index(name: &str) -> Option<usize> {
+    let number: usize = name.strip_prefix("wrong_answer_")?.parse().ok()?;
+    (1..MAX_ANSWERS).contains(&number).then(|| number - 1)
+}
+
+fn is_blank(text: &LocalizedText) -> bool {
+    text.texts().iter().all(|text| text.trim().is_empty())
+}
+
+fn blank_question() -> Question {
+    Question {
+        category: LocalizedText::default(),
+        difficulty: DIFFICULTIES[0].to_string(),
+        question: LocalizedText::default(),
+        correct_answer: LocalizedText::default(),
+        incorrect_answers: Vec::new(),
+        image: None,
+        audio: None,
+    }
+}
+
+/// The locale the form edits text in
+fn form_locale(localization: &Localization) -> &str {
+    localization
+        .chain()
+        .first()
+        .map_or(DEFAULT_LOCALE, String::as_str)
+}
+
+/// Reads the saved pack the first time the editor is opened, and has the form
+/// filled in each time
+fn open_local_pack(mut edited: ResMut<EditedPack>, mut toasts: EventWriter<ShowToast>) {
+    if !edited.opened {
+        edited.opened = true;
+        if let Some(text) = storage::read(LOCAL_PACK) {
+            match ron::from_str::<QuestionPack>(&text) {
+                Ok(pack) if pack.questions.is_empty() => edited.pack.name = pack.name,
+                Ok(pack) => edited.pack = pack,
+                Err(error) => {
+                    warn!("Couldn't read {LOCAL_PACK}: {error}");
+                    toasts.send(ShowToast(Toast::error("toast.pack_open_failed")));
+                }
+            }
+        }
+    }
+    let current = edited.current;
+    edited.select(current);
+}
+
+/// Copies edits made in the form into the question it shows. Only values that
+/// changed are copied, so that filling the form in doesn't write the question
+/// shown before over the new one.
+fn apply_editor_form(
+    values: Res<MenuValues>,
+    mut last: Local<MenuValues>,
+    mut edited: ResMut<EditedPack>,
+    localization: Res<Localization>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let previous = std::mem::replace(&mut *last, values.clone());
+    // The form is still being filled in, so its values are of no question yet
+    if edited.stale {
+        return;
+    }
+    let locale = form_locale(&localization);
+    let mut selected = None;
+    for (name, value) in values.iter() {
+        if previous.get(name) == Some(value) {
+            continue;
+        }
+        match (name.as_str(), value) {
+            ("question_list", MenuValue::Selection(selection)) => {
+                selected = selection.iter().next().copied();
+            }
+            (
+                "image",
+                MenuValue::Text {
+                    value: path,
+                    valid: true,
+                },
+            ) => match import_image(path) {
+                Ok(imported) => {
+                    let value = imported.map_or_else(
+                        || value.clone(),
+                        |path| MenuValue::Text {
+                            value: path,
+                            valid: true,
+                        },
+                    );
+                    edited.apply("image", &value, locale);
+                }
+                Err(error) => {
+                    warn!("Couldn't copy the image {path}: {error}");
+                    toasts.send(ShowToast(Toast::error("toast.image_copy_failed")));
+                }
+            },
+            (name, value) => edited.apply(name, value, locale),
+        }
+    }
+    // Switch questions last, so that edits go to the question they were made on
+    if let Some(index) = selected.filter(|index| *index != edited.current) {
+        edited.select(index);
+    }
+}
+
+/// Images picked from elsewhere on the computer, by their full path, are copied
+/// into the user data directory and loaded from there, so the pack doesn't
+/// break when the original moves. The path to load the image from, if it was
+/// copied.
+#[cfg(not(target_arch = "wasm32"))]
+fn import_image(path: &str) -> Result<Option<String>, String> {
+    let path = std::path::Path::new(path.trim());
+    if !path.is_absolute() || !path.is_file() {
+        return Ok(None);
+    }
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(None);
+    };
+    let content = std::fs::read(path).map_err(|error| error.to_string())?;
+    let name = format!("{IMAGES_DIR}/{}", imported_name(file_name, &content));
+    storage::copy_in(path, &name)?;
+    Ok(Some(format!("{}://{name}", storage::ASSET_SOURCE)))
+}
+
+/// What an image is copied in as: its file name with a hash of its content,
+/// so images with the same name from different folders don't replace each
+/// other, while picking the same image again reuses its copy
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn imported_name(file_name: &str, content: &[u8]) -> String {
+    // FNV-1a, which unlike the standard library's hasher is the same on every run
+    let hash = content
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => {
+            format!("{stem}-{hash:016x}.{extension}")
+        }
+        _ => format!("{file_name}-{hash:016x}"),
+    }
+}
+
+/// Browsers don't hand out paths to the player's files, so there's nothing to copy
+#[cfg(target_arch = "wasm32")]
+fn import_image(_path: &str) -> Result<Option<String>, String> {
+    Ok(None)
+}
+
+/// Keeps the question list up to date, and fills the form in with the current
+/// question when it shows another one
+fn fill_editor_form(
+    mut edited: ResMut<EditedPack>,
+    localization: Res<Localization>,
+    mut texts: Query<(&Name, TextInputEditor)>,
+    mut dropdowns: Query<(&Name, &mut DropdownSelected)>,
+    mut sources: Query<(&Name, &mut TriviaSource)>,
+) {
+    if !edited.is_changed() && !localization.is_changed() && !edited.stale {
+        return;
+    }
+    let titles = TriviaSource::String(StringSource::new(edited.titles(localization.chain())));
+    for (_, mut source) in sources
+        .iter_mut()
+        .filter(|(name, _)| name.as_str() == "question_list")
+    {
+        source.set_if_neq(titles.clone());
+    }
+
+    if !edited.stale {
+        return;
+    }
+    let locale = form_locale(&localization);
+    let mut filled = false;
+    for (name, mut editor) in texts.iter_mut() {
+        if let Some(MenuValue::Text { value, .. }) = edited.value(name, locale) {
+            editor.set_value(&value);
+            filled = true;
+        }
+    }
+    for (name, mut dropdown) in dropdowns.iter_mut() {
+        if let Some(MenuValue::Selection(selected)) = edited.value(name, locale) {
+            let selected = selected.iter().next().copied();
+            if **dropdown != selected {
+                **dropdown = selected;
+            }
+        }
+    }
+    // The menu's widgets may not have been spawned yet
+    if filled {
+        edited.stale = false;
+    }
+}
+
+fn new_question(mut edited: ResMut<EditedPack>) {
+    let index = edited.current + 1;
+    edited.pack.questions.insert(index, blank_question());
+    edited.select(index);
+}
+
+fn delete_question(mut edited: ResMut<EditedPack>) {
+    let current = edited.current;
+    edited.pack.questions.remove(current);
+    if edited.pack.questions.is_empty() {
+        edited.pack.questions.push(blank_question());
+    }
+    edited.select(current);
+}
+
+/// Moves the current question `offset` places through the pack
+fn move_question(offset: isize) -> impl FnMut(ResMut<EditedPack>) {
+    move |mut edited: ResMut<EditedPack>| {
+        let current = edited.current;
+        let Some(target) = current
+            .checked_add_signed(offset)
+            .filter(|target| *target < edited.pack.questions.len())
+        else {
+            return;
+        };
+        edited.pack.questions.swap(current, target);
+        edited.select(target);
+    }
+}
+
+fn save_pack(edited: Res<EditedPack>, mut toasts: EventWriter<ShowToast>) {
+    let config = PrettyConfig::new().struct_names(true);
+    let saved = ron::ser::to_string_pretty(&edited.to_saved(), config)
+        .map_err(|error| error.to_string())
+        .and_then(|text| storage::write(LOCAL_PACK, &(text + "\n")));
+    match saved {
+        Ok(()) => toasts.send(ShowToast(Toast::success("toast.pack_saved"))),
+        Err(error) => {
+            warn!("Couldn't save {LOCAL_PACK}: {error}");
+            toasts.send(ShowToast(Toast::error("toast.pack_save_failed")))
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn text(value: &str) -> MenuValue {
+        MenuValue::Text {
+            value: value.to_string(),
+            valid: true,
+        }
+    }
+
+    fn question(text: &str) -> Question {
+        Question {
+            question: LocalizedText::Plain(text.to_string()),
+            ..blank_question()
+        }
+    }
+
+    /// A pack of questions called "1", "2" and so on, with the one at `current` in the form
+    fn pack(count: usize, current: usize) -> World {
+        let mut world = World::new();
+        let mut edited = EditedPack::default();
+        edited.pack.questions = (1..=count).map(|i| question(&i.to_string())).collect();
+        edited.select(current);
+        world.insert_resource(edited);
+        world
+    }
+
+    /// The questions of the pack, and the one in the form
+    fn order(world: &World) -> (Vec<String>, usize) {
+        let edited = world.resource::<EditedPack>();
+        let questions = edited
+            .pack
+            .questions
+            .iter()
+            .map(|question| question.question.texts().concat())
+            .collect();
+        (questions, edited.current)
+    }
+
+    #[test]
+    fn fields_read_back_what_was_applied() {
+        let mut edited = EditedPack::default();
+        for (name, value) in [
+            ("pack_name", text("Capitals")),
+            ("question", text("Capital of France?")),
+            ("correct_answer", text("Paris")),
+            ("category", text("Geography")),
+            ("wrong_answer_1", text("Lyon")),
+            ("wrong_answer_3", text("Nice")),
+            ("difficulty", MenuValue::Selection(HashSet::from_iter([2]))),
+            ("image", text(" flags/fr.png ")),
+        ] {
+            edited.apply(name, &value, "en");
+        }
+        assert_eq!(edited.value("pack_name", "en"), Some(text("Capitals")));
+        assert_eq!(
+            edited.value("question", "en"),
+            Some(text("Capital of France?"))
+        );
+        assert_eq!(edited.value("correct_answer", "en"), Some(text("Paris")));
+        assert_eq!(edited.value("category", "en"), Some(text("Geography")));
+        assert_eq!(edited.value("wrong_answer_1", "en"), Some(text("Lyon")));
+        assert_eq!(edited.value("wrong_answer_2", "en"), Some(text("")));
+        assert_eq!(edited.value("wrong_answer_3", "en"), Some(text("Nice")));
+        assert_eq!(
+            edited.value("difficulty", "en"),
+            Some(MenuValue::Selection(HashSet::from_iter([2])))
+        );
+        assert_eq!(edited.value("image", "en"), Some(text("flags/fr.png")));
+        assert_eq!(edited.value("unknown", "en"), None);
+    }
+
+    #[test]
+    fn blank_trailing_wrong_answers_are_dropped() {
+        let mut edited = EditedPack::default();
+        edited.apply("wrong_answer_1", &text("Lyon"), "en");
+        edited.apply("wrong_answer_3", &text("Nice"), "en");
+        assert_eq!(edited.question().incorrect_answers.len(), 3);
+        edited.apply("wrong_answer_3", &text(" "), "en");
+        assert_eq!(edited.question().incorrect_answers.len(), 1);
+    }
+
+    #[test]
+    fn invalid_image_paths_are_not_applied() {
+        let mut edited = EditedPack::default();
+        let half_typed = MenuValue::Text {
+            value: "fla".to_string(),
+            valid: false,
+        };
+        edited.apply("image", &half_typed, "en");
+        assert_eq!(edited.question().image, None);
+    }
+
+    #[test]
+    fn wrong_answer_fields_are_numbered_from_one() {
+        assert_eq!(wrong_answer_index("wrong_answer_1"), Some(0));
+        assert_eq!(
+            wrong_answer_index(&format!("wrong_answer_{}", MAX_ANSWERS - 1)),
+            Some(MAX_ANSWERS - 2)
+        );
+        assert_eq!(
+            wrong_answer_index(&format!("wrong_answer_{MAX_ANSWERS}")),
+            None
+        );
+        assert_eq!(wrong_answer_index("wrong_answer_0"), None);
+        assert_eq!(wrong_answer_index("wrong_answer_"), None);
+        assert_eq!(wrong_answer_index("wrong_answer_x"), None);
+        assert_eq!(wrong_answer_index("correct_answer"), None);
+    }
+
+    #[test]
+    fn questions_move_up_and_down() {
+        let mut world = pack(3, 1);
+        world.run_system_once(move_question(-1));
+        assert_eq!(order(&world), (vec!["2".into(), "1".into(), "3".into()], 0));
+        world.run_system_once(move_question(1));
+        world.run_system_once(move_question(1));
+        assert_eq!(order(&world), (vec!["1".into(), "3".into(), "2".into()], 2));
+    }
+
+    #[test]
+    fn questions_dont_move_past_either_end() {
+        let mut world = pack(3, 0);
+        world.run_system_once(move_question(-1));
+        assert_eq!(order(&world), (vec!["1".into(), "2".into(), "3".into()], 0));
+        let mut world = pack(3, 2);
+        world.run_system_once(move_question(1));
+        assert_eq!(order(&world), (vec!["1".into(), "2".into(), "3".into()], 2));
+    }
+
+    #[test]
+    fn deleting_the_last_question_shows_the_one_before() {
+        let mut world = pack(3, 2);
+        world.run_system_once(delete_question);
+        assert_eq!(order(&world), (vec!["1".into(), "2".into()], 1));
+    }
+
+    #[test]
+    fn deleting_the_only_question_leaves_a_blank_one() {
+        let mut world = pack(1, 0);
+        world.run_system_once(delete_question);
+        assert_eq!(order(&world), (vec![String::new()], 0));
+        assert_eq!(world.resource::<EditedPack>().question(), &blank_question());
+    }
+
+    #[test]
+    fn new_questions_go_after_the_current_one() {
+        let mut world = pack(2, 0);
+        world.run_system_once(new_question);
+        assert_eq!(
+            order(&world),
+            (vec!["1".into(), String::new(), "2".into()], 1)
+        );
+    }
+
+    #[test]
+    fn imported_images_are_named_after_their_content() {
+        let a = imported_name("flag.png", b"france");
+        let b = imported_name("flag.png", b"italy");
+        assert_ne!(a, b);
+        assert_eq!(a, imported_name("flag.png", b"france"));
+        assert!(a.starts_with("flag-") && a.ends_with(".png"));
+        assert!(imported_name("flag", b"france").starts_with("flag-"));
+    }
+}
@@ -0,0 +1,412 @@
+use bevy::{prelude::*, utils::HashSet};
+use leafwing_input_manager::action_state::ActionState;
+
+use crate::{
+    accessibility::Announce,
+    actions::GameAction,
+    audio::AudioCue,
+    game::{
+        announce_result, award, net::Latency, run_clock, wager::Wager, Answer, Contestant, Game,
+        GamePhase, LocalContestant, QuestionClock, Score, Streak, SubmitAnswer,
+    },
+    lobby::{format::GameFormat, AllowNegativeScores, GameMode},
+    locale::Localization,
+    trivia::set::QuestionSet,
+};
+
+/// How long buzzing stays open once the question is typed out, in rounds
+/// without a time limit
+pub const BUZZ_SECS: f32 = 10.0;
+
+/// How long whoever buzzed in first has to answer
+pub const ANSWER_SECS: f32 = 5.0;
+
+/// How fast the question is typed out, in characters per second
+const CHARS_PER_SEC: f32 = 30.0;
+
+/// Buzzer games: the question is typed out bit by bit, and whoever buzzes in
+/// first gets to answer it alone. A wrong answer locks them out of the
+/// question and opens buzzing again.
+pub struct BuzzerPlugin;
+
+impl Plugin for BuzzerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Buzz>()
+            .init_resource::<Buzzer>()
+            .init_resource::<BuzzQueue>()
+            .init_resource::<RevealedText>()
+            .add_systems(
+                OnEnter(GamePhase::Question),
+                open_buzzer.run_if(resource_equals(GameMode::Buzzer)),
+            )
+            .add_systems(
+                Update,
+                (
+                    buzz_from_input,
+                    take_buzzes,
+                    take_buzzer_answer,
+                    type_question,
+                )
+                    .chain()
+                    .run_if(in_state(GamePhase::Question))
+                    .run_if(resource_equals(GameMode::Buzzer)),
+            );
+    }
+}
+
+/// A contestant buzzed in
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct Buzz {
+    pub contestant: Entity,
+    /// When they pressed the buzzer, as the host's [`Time::elapsed_seconds`]
+    pub at: f32,
+}
+
+/// Who may buzz in on the question being asked
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct Buzzer {
+    /// Who buzzed in first, and when their time to answer runs out, as the
+    /// host's [`Time::elapsed_seconds`]
+    pub answering: Option<(Entity, f32)>,
+    /// Who answered wrong, and can't buzz in again on this question
+    pub locked_out: HashSet<Entity>,
+}
+
+impl Buzzer {
+    pub fn can_buzz(&self, contestant: Entity) -> bool {
+        self.answering.is_none() && !self.locked_out.contains(&contestant)
+    }
+}
+
+/// How many characters of the question have been typed out
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Deref)]
+pub struct RevealedText(usize);
+
+/// The buzzes waiting on slower connections before the first is picked, and
+/// how far the question has been typed out. Apart from [`Buzzer`] so that views
+/// don't follow every frame.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+struct BuzzQueue {
+    /// Who buzzed, and when they pressed it
+    pending: Vec<Buzz>,
+    /// When the first of the pending buzzes arrived
+    first_arrival: f32,
+    /// In characters
+    typed: f32,
+}
+
+fn open_buzzer(
+    mut buzzer: ResMut<Buzzer>,
+    mut queue: ResMut<BuzzQueue>,
+    mut revealed: ResMut<RevealedText>,
+) {
+    *buzzer = Buzzer::default();
+    *queue = BuzzQueue::default();
+    revealed.set_if_neq(RevealedText(0));
+}
+
+/// Buzzes in for the player on this device
+fn buzz_from_input(
+    actions: Res<ActionState<GameAction>>,
+    time: Res<Time>,
+    local: Query<Entity, With<LocalContestant>>,
+    mut buzzes: EventWriter<Buzz>,
+) {
+    if !actions.just_pressed(&GameAction::Buzz) {
+        return;
+    }
+    for contestant in local.iter() {
+        buzzes.send(Buzz {
+            contestant,
+            at: time.elapsed_seconds(),
+        });
+    }
+}
+
+/// Gives the answer to whoever pressed their buzzer first. Once a buzz
+/// arrives, buzzes from slower connections are waited for as long as the
+/// slowest one takes, so they're judged by when they were pressed rather than
+/// when they arrived. Buzzes that arrive while someone answers are dropped.
+fn take_buzzes(
+    time: Res<Time>,
+    mut buzzes: EventReader<Buzz>,
+    mut buzzer: ResMut<Buzzer>,
+    mut queue: ResMut<BuzzQueue>,
+    latencies: Query<&Latency, With<Contestant>>,
+) {
+    let now = time.elapsed_seconds();
+    for buzz in buzzes.read() {
+        let queued = queue
+            .pending
+            .iter()
+            .any(|pending| pending.contestant == buzz.contestant);
+        if queued || !buzzer.can_buzz(buzz.contestant) {
+            continue;
+        }
+        if queue.pending.is_empty() {
+            queue.first_arrival = now;
+        }
+        queue.pending.push(*buzz);
+    }
+    if queue.pending.is_empty() {
+        return;
+    }
+    let wait = latencies
+        .iter()
+        .map(|latency| latency.compensation())
+        .fold(0.0, f32::max);
+    if now < queue.first_arrival + wait {
+        return;
+    }
+    // Buzzes pressed at the same moment go to whoever's arrived first
+    let first = queue
+        .pending
+        .drain(..)
+        .min_by(|a, b| a.at.total_cmp(&b.at))
+        .expect("A buzz is pending");
+    buzzer.answering = Some((first.contestant, now + ANSWER_SECS));
+}
+
+/// Scores the answer of whoever buzzed in. A right answer ends the question; a
+/// wrong one, or none in time, locks them out and opens buzzing again, until
+/// nobody is left to buzz.
+fn take_buzzer_answer(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut submitted: EventReader<SubmitAnswer>,
+    mut buzzer: ResMut<Buzzer>,
+    clock: Res<QuestionClock>,
+    game: Res<Game>,
+    set: Res<QuestionSet>,
+    format: Res<GameFormat>,
+    negative_scores: Res<AllowNegativeScores>,
+    localization: Res<Localization>,
+    mut contestants: Query<(
+        Entity,
+        &mut Score,
+        &mut Streak,
+        Option<&Wager>,
+        Has<LocalContestant>,
+    )>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+    mut cues: EventWriter<AudioCue>,
+    mut announcements: EventWriter<Announce>,
+) {
+    let Some((answering, deadline)) = buzzer.answering else {
+        submitted.clear();
+        return;
+    };
+    let answer = submitted
+        .read()
+        .filter(|event| event.contestant == answering)
+        .map(|event| event.answer.clone())
+        .next();
+    if answer.is_none() && time.elapsed_seconds() < deadline {
+        return;
+    }
+    let Some(question) = game.current(&set) else {
+        return;
+    };
+    let right = answer
+        .as_ref()
+        .is_some_and(|answer| *answer == question.question.correct_answer);
+    let answer = answer.map(|answer| Answer {
+        answer,
+        secs: **clock,
+    });
+    if let Some(answer) = &answer {
+        commands.entity(answering).insert(answer.clone());
+    }
+    let points = game
+        .round(&set, &format)
+        .map_or(1, |round| round.scoring.points(**clock, *round.time_limit));
+    if let Ok((_, mut score, mut streak, wager, local)) = contestants.get_mut(answering) {
+        // In wager rounds the stake is won or lost instead
+        let (points, negative_scores) = match wager {
+            Some(wager) => (**wager, true),
+            None => (points, **negative_scores),
+        };
+        let cue = award(&mut score, &mut streak, right, points, negative_scores);
+        if local {
+            cues.send(cue);
+            announcements.send(announce_result(
+                &localization,
+                answer.as_ref(),
+                &question.question.correct_answer,
+                **score,
+            ));
+        }
+    }
+
+    buzzer.answering = None;
+    if right {
+        next_phase.set(GamePhase::Reveal);
+        return;
+    }
+    buzzer.locked_out.insert(answering);
+    if contestants
+        .iter()
+        .all(|(contestant, ..)| buzzer.locked_out.contains(&contestant))
+    {
+        next_phase.set(GamePhase::Reveal);
+    }
+}
+
+/// Types the question out, then runs the clock until buzzing closes. Both
+/// stop while someone answers.
+fn type_question(
+    time: Res<Time>,
+    buzzer: Res<Buzzer>,
+    mut queue: ResMut<BuzzQueue>,
+    mut revealed: ResMut<RevealedText>,
+    mut clock: ResMut<QuestionClock>,
+    game: Res<Game>,
+    set: Res<QuestionSet>,
+    format: Res<GameFormat>,
+    localization: Res<Localization>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+    mut cues: EventWriter<AudioCue>,
+    mut announcements: EventWriter<Announce>,
+) {
+    if buzzer.answering.is_some() {
+        return;
+    }
+    let length = game
+        .current(&set)
+        .map_or(0, |question| question.question.question.chars().count());
+    if queue.typed < length as f32 {
+        queue.typed += time.delta_seconds() * CHARS_PER_SEC;
+        revealed.set_if_neq(RevealedText((queue.typed as usize).min(length)));
+        return;
+    }
+    let limit = game.time_limit(&set, &format, GameMode::Buzzer);
+    if run_clock(
+        &mut clock,
+        time.delta_seconds(),
+        limit,
+        &localization,
+        &mut cues,
+        &mut announcements,
+    ) {
+        next_phase.set(GamePhase::Reveal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<Buzz>()
+            .init_resource::<Buzzer>()
+            .init_resource::<BuzzQueue>()
+            .init_resource::<Time>()
+            .add_systems(Update, take_buzzes);
+        app
+    }
+
+    fn contestant(app: &mut App, latency: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                Contestant {
+                    name: "Contestant".to_string(),
+                },
+                Latency(latency),
+            ))
+            .id()
+    }
+
+    /// Runs a frame at `now`, with `buzzes` arriving in it in this order
+    fn frame(app: &mut App, now: f32, buzzes: &[(Entity, f32)]) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_to(Duration::from_secs_f32(now));
+        for (contestant, at) in buzzes {
+            app.world_mut().send_event(Buzz {
+                contestant: *contestant,
+                at: *at,
+            });
+        }
+        app.update();
+    }
+
+    fn answering(app: &App) -> Option<(Entity, f32)> {
+        app.world().resource::<Buzzer>().answering
+    }
+
+    #[test]
+    fn takes_a_buzz_at_once_without_latency() {
+        let mut app = app();
+        let local = contestant(&mut app, 0.0);
+        frame(&mut app, 1.0, &[(local, 1.0)]);
+        assert_eq!(answering(&app), Some((local, 1.0 + ANSWER_SECS)));
+    }
+
+    #[test]
+    fn waits_for_slower_connections() {
+        let mut app = app();
+        let near = contestant(&mut app, 0.0);
+        contestant(&mut app, 0.25);
+        frame(&mut app, 1.0, &[(near, 1.0)]);
+        assert_eq!(answering(&app), None);
+        frame(&mut app, 1.125, &[]);
+        assert_eq!(answering(&app), None);
+        frame(&mut app, 1.25, &[]);
+        assert_eq!(answering(&app), Some((near, 1.25 + ANSWER_SECS)));
+    }
+
+    #[test]
+    fn the_first_press_wins_over_the_first_arrival() {
+        let mut app = app();
+        let near = contestant(&mut app, 0.0);
+        let far = contestant(&mut app, 0.25);
+        frame(&mut app, 1.0, &[(near, 1.0)]);
+        // Pressed before the near buzz, but it took longer to arrive
+        frame(&mut app, 1.125, &[(far, 0.875)]);
+        assert_eq!(answering(&app), None);
+        frame(&mut app, 1.25, &[]);
+        assert_eq!(answering(&app), Some((far, 1.25 + ANSWER_SECS)));
+    }
+
+    #[test]
+    fn presses_at_the_same_moment_go_to_the_first_arrival() {
+        let mut app = app();
+        let first = contestant(&mut app, 0.25);
+        let second = contestant(&mut app, 0.25);
+        frame(&mut app, 1.0, &[(first, 0.75)]);
+        frame(&mut app, 1.125, &[(second, 0.75)]);
+        frame(&mut app, 1.25, &[]);
+        assert_eq!(answering(&app), Some((first, 1.25 + ANSWER_SECS)));
+    }
+
+    #[test]
+    fn buzzes_after_the_wait_are_dropped() {
+        let mut app = app();
+        let near = contestant(&mut app, 0.0);
+        let far = contestant(&mut app, 0.25);
+        frame(&mut app, 1.0, &[(near, 1.0)]);
+        frame(&mut app, 1.25, &[]);
+        // Pressed first, but arrived after the answer was given away
+        frame(&mut app, 1.5, &[(far, 0.5)]);
+        assert_eq!(answering(&app), Some((near, 1.25 + ANSWER_SECS)));
+        assert!(app.world().resource::<BuzzQueue>().pending.is_empty());
+    }
+
+    #[test]
+    fn locked_out_contestants_cant_buzz() {
+        let mut app = app();
+        let locked = contestant(&mut app, 0.0);
+        let other = contestant(&mut app, 0.0);
+        app.world_mut()
+            .resource_mut::<Buzzer>()
+            .locked_out
+            .insert(locked);
+        frame(&mut app, 1.0, &[(locked, 1.0)]);
+        assert_eq!(answering(&app), None);
+        frame(&mut app, 1.125, &[(other, 1.125)]);
+        assert_eq!(answering(&app), Some((other, 1.125 + ANSWER_SECS)));
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{Answer, Contestant, Game, GamePhase},
+    lobby::{GameMode, StartingLives, SuddenDeath},
+    trivia::set::QuestionSet,
+};
+
+/// Elimination games: each wrong or missed answer costs a life, and players
+/// out of lives watch the rest of the game, guessing for fun, until one player
+/// is left or the questions run out. With sudden death, extra questions are
+/// asked until one player is left.
+pub struct EliminationPlugin;
+
+impl Plugin for EliminationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnExit(GamePhase::Waiting),
+            give_lives.run_if(resource_equals(GameMode::Elimination)),
+        )
+        .add_systems(
+            OnEnter(GamePhase::Reveal),
+            take_lives.run_if(resource_equals(GameMode::Elimination)),
+        );
+    }
+}
+
+/// How many more wrong or missed answers knock a contestant out
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Deref, DerefMut)]
+pub struct Lives(pub u32);
+
+/// A contestant out of lives, who can still guess but doesn't score
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Eliminated;
+
+/// Whether an elimination game is over: everyone is out, or only one player
+/// is left of several
+pub fn last_standing(remaining: usize, contestants: usize) -> bool {
+    remaining == 0 || (remaining == 1 && contestants > 1)
+}
+
+fn give_lives(
+    mut commands: Commands,
+    lives: Res<StartingLives>,
+    contestants: Query<Entity, With<Contestant>>,
+) {
+    for contestant in contestants.iter() {
+        commands.entity(contestant).insert(Lives(**lives));
+    }
+}
+
+/// Takes a life from each contestant still in who got the question wrong or
+/// didn't answer, and knocks out those left without any. With sudden death,
+/// a question that would knock out everyone left costs nobody anything.
+fn take_lives(
+    mut commands: Commands,
+    game: Res<Game>,
+    set: Res<QuestionSet>,
+    sudden_death: Res<SuddenDeath>,
+    mut remaining: Query<(Entity, &mut Lives, Option<&Answer>), Without<Eliminated>>,
+) {
+    let Some(question) = game.current(&set) else {
+        return;
+    };
+    let missed = |answer: Option<&Answer>| {
+        !answer.is_some_and(|answer| answer.answer == question.question.correct_answer)
+    };
+    let knocked_out = remaining
+        .iter()
+        .filter(|(_, lives, answer)| ***lives <= 1 && missed(*answer))
+        .count();
+    let count = remaining.iter().len();
+    if **sudden_death && count > 1 && knocked_out == count {
+        return;
+    }
+    for (contestant, mut lives, answer) in remaining.iter_mut() {
+        if !missed(answer) {
+            continue;
+        }
+        **lives = lives.saturating_sub(1);
+        if **lives == 0 {
+            commands.entity(contestant).insert(Eliminated);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::trivia::{question::LocalizedQuestion, set::SetQuestion};
+
+    fn world(sudden_death: bool) -> World {
+        let mut world = World::new();
+        world.insert_resource(Game::default());
+        world.insert_resource(QuestionSet {
+            questions: vec![SetQuestion {
+                question: LocalizedQuestion {
+                    correct_answer: "Right".to_string(),
+                    ..default()
+                },
+                round: 0,
+            }],
+            ..default()
+        });
+        let mut sudden = SuddenDeath::default();
+        *sudden = sudden_death;
+        world.insert_resource(sudden);
+        world
+    }
+
+    fn contestant(world: &mut World, lives: u32, answer: Option<&str>) -> Entity {
+        let mut contestant = world.spawn(Lives(lives));
+        if let Some(answer) = answer {
+            contestant.insert(Answer {
+                answer: answer.to_string(),
+                secs: 1.0,
+            });
+        }
+        contestant.id()
+    }
+
+    fn lives(world: &World, contestant: Entity) -> (u32, bool) {
+        (
+            **world.get::<Lives>(contestant).unwrap(),
+            world.get::<Eliminated>(contestant).is_some(),
+        )
+    }
+
+    #[test]
+    fn the_last_player_standing_wins() {
+        assert!(last_standing(0, 3));
+        assert!(last_standing(1, 3));
+        assert!(!last_standing(2, 3));
+        // Playing alone goes on until the player is out
+        assert!(!last_standing(1, 1));
+        assert!(last_standing(0, 1));
+    }
+
+    #[test]
+    fn wrong_and_missed_answers_cost_a_life() {
+        let mut world = world(false);
+        let right = contestant(&mut world, 2, Some("Right"));
+        let wrong = contestant(&mut world, 2, Some("Wrong"));
+        let missed = contestant(&mut world, 2, None);
+        world.run_system_once(take_lives);
+        assert_eq!(lives(&world, right), (2, false));
+        assert_eq!(lives(&world, wrong), (1, false));
+        assert_eq!(lives(&world, missed), (1, false));
+    }
+
+    #[test]
+    fn the_last_life_knocks_out() {
+        let mut world = world(false);
+        let right = contestant(&mut world, 1, Some("Right"));
+        let wrong = contestant(&mut world, 1, Some("Wrong"));
+        world.run_system_once(take_lives);
+        assert_eq!(lives(&world, right), (1, false));
+        assert_eq!(lives(&world, wrong), (0, true));
+    }
+
+    #[test]
+    fn without_sudden_death_everyone_can_be_knocked_out() {
+        let mut world = world(false);
+        let first = contestant(&mut world, 1, Some("Wrong"));
+        let second = contestant(&mut world, 1, None);
+        world.run_system_once(take_lives);
+        assert_eq!(lives(&world, first), (0, true));
+        assert_eq!(lives(&world, second), (0, true));
+    }
+
+    #[test]
+    fn sudden_death_spares_everyone_left() {
+        let mut world = world(true);
+        let first = contestant(&mut world, 1, Some("Wrong"));
+        let second = contestant(&mut world, 1, None);
+        let out = contestant(&mut world, 0, Some("Wrong"));
+        world.entity_mut(out).insert(Eliminated);
+        world.run_system_once(take_lives);
+        assert_eq!(lives(&world, first), (1, false));
+        assert_eq!(lives(&world, second), (1, false));
+        assert_eq!(lives(&world, out), (0, true));
+    }
+
+    #[test]
+    fn sudden_death_still_knocks_out_some() {
+        let mut world = world(true);
+        let right = contestant(&mut world, 1, Some("Right"));
+        let wrong = contestant(&mut world, 1, Some("Wrong"));
+        world.run_system_once(take_lives);
+        assert_eq!(lives(&world, right), (1, false));
+        assert_eq!(lives(&world, wrong), (0, true));
+    }
+}
@@ -0,0 +1,735 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_quill::View;
+use rand::seq::SliceRandom;
+
+use crate::{
+    accessibility::Announce,
+    audio::{AudioCue, TICKING_SECS},
+    dialog::{ShowToast, Toast},
+    lobby::{
+        format::{GameFormat, Round, Scoring},
+        AllowNegativeScores, GameMode, ShuffleAnswers, SuddenDeath,
+    },
+    locale::Localization,
+    trivia::{
+        question::normalize,
+        set::{QuestionSet, QuestionSetReady, SetQuestion},
+    },
+    GameState,
+};
+use buzzer::BuzzerPlugin;
+use elimination::{last_standing, Eliminated, EliminationPlugin, Lives};
+use net::NetPlugin;
+use view::GameScreen;
+use wager::{Wager, WagerPlugin};
+
+pub mod buzzer;
+pub mod elimination;
+pub mod net;
+pub mod view;
+pub mod wager;
+
+/// How long the right answer is shown before the game moves on
+const REVEAL_SECS: f32 = 3.0;
+
+/// How many right answers in a row make a streak
+const STREAK: u32 = 3;
+
+/// Plays a game: asks the questions of the [`QuestionSet`] one at a time,
+/// scores the answers, and shows the scores after each round of the
+/// [`GameFormat`] and at the end
+pub struct PlayPlugin;
+
+impl Plugin for PlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((BuzzerPlugin, EliminationPlugin, NetPlugin, WagerPlugin))
+            .add_sub_state::<GamePhase>()
+            .add_event::<SubmitAnswer>()
+            .init_resource::<Game>()
+            .init_resource::<QuestionClock>()
+            .init_resource::<Standings>()
+            .add_systems(OnEnter(GameState::Playing), start_game)
+            .add_systems(OnExit(GameState::Playing), end_game)
+            .add_systems(OnEnter(GamePhase::Question), ask_question)
+            .add_systems(
+                OnEnter(GamePhase::Reveal),
+                (
+                    start_reveal,
+                    score_answers.run_if(not(resource_equals(GameMode::Buzzer))),
+                ),
+            )
+            .add_systems(OnEnter(GamePhase::Finished), announce_winner)
+            .add_systems(
+                Update,
+                (
+                    begin_game.run_if(in_state(GamePhase::Waiting)),
+                    (tick_question, take_answers)
+                        .chain()
+                        .run_if(in_state(GamePhase::Question))
+                        .run_if(not(resource_equals(GameMode::Buzzer))),
+                    next_question.run_if(in_state(GamePhase::Reveal)),
+                    update_standings,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// What the game being played is doing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, SubStates, Default)]
+#[source(GameState = GameState::Playing)]
+pub enum GamePhase {
+    /// The questions are being put together
+    #[default]
+    Waiting,
+    /// Players stake points on the question about to be asked, in wager rounds
+    Wager,
+    /// A question is up, and answers are taken
+    Question,
+    /// The right answer is shown
+    Reveal,
+    /// A round is over, and the scores are shown until the next one starts
+    Intermission,
+    /// Every question has been asked
+    Finished,
+}
+
+/// Where the game being played is at
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct Game {
+    /// The index in the [`QuestionSet`] of the question being asked, or last asked
+    pub question: usize,
+    /// The answers of the question, in the order they're shown
+    pub answers: Vec<String>,
+}
+
+impl Game {
+    pub fn current<'a>(&self, set: &'a QuestionSet) -> Option<&'a SetQuestion> {
+        set.questions.get(self.question)
+    }
+
+    /// The round of the [`GameFormat`] the question is asked in
+    pub fn round<'a>(&self, set: &QuestionSet, format: &'a GameFormat) -> Option<&'a Round> {
+        self.current(set)
+            .and_then(|question| format.rounds.get(question.round))
+    }
+
+    /// The phase that asks the question: wagers are placed on it first in
+    /// wager rounds
+    pub fn asking(&self, set: &QuestionSet, format: &GameFormat) -> GamePhase {
+        match self.round(set, format) {
+            Some(round) if round.scoring == Scoring::Wager => GamePhase::Wager,
+            _ => GamePhase::Question,
+        }
+    }
+
+    /// How many seconds the question is up for, if it's timed. Buzzing always
+    /// closes, so an untimed round still gives [`buzzer::BUZZ_SECS`].
+    pub fn time_limit(
+        &self,
+        set: &QuestionSet,
+        format: &GameFormat,
+        mode: GameMode,
+    ) -> Option<f32> {
+        let limit = self
+            .round(set, format)
+            .and_then(|round| *round.time_limit)
+            .map(|limit| limit as f32);
+        match mode {
+            GameMode::Buzzer => Some(limit.unwrap_or(buzzer::BUZZ_SECS)),
+            _ => limit,
+        }
+    }
+}
+
+/// How long the question, or its answer, has been shown, in seconds. Apart from
+/// [`Game`] so that views of the question don't re-render with every frame.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Deref, DerefMut)]
+pub struct QuestionClock(f32);
+
+/// Someone playing the game
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Contestant {
+    pub name: String,
+}
+
+/// The contestant playing on this device
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct LocalContestant;
+
+/// The points a contestant has won
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Deref, DerefMut)]
+pub struct Score(pub i32);
+
+/// How many questions in a row a contestant got right
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Deref, DerefMut)]
+pub struct Streak(pub u32);
+
+/// What a contestant answered to the question being asked
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Answer {
+    pub answer: String,
+    /// How long the question had been up, in seconds
+    pub secs: f32,
+}
+
+/// A contestant picked an answer. Only the first one to each question counts.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct SubmitAnswer {
+    pub contestant: Entity,
+    pub answer: String,
+}
+
+/// A contestant's place in the game
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub score: i32,
+    pub local: bool,
+    /// The lives left, in elimination games
+    pub lives: Option<u32>,
+    pub eliminated: bool,
+}
+
+/// The contestants by score, highest first, for views to show. In elimination
+/// games the ones still in come first, by lives left.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Deref)]
+pub struct Standings(Vec<Standing>);
+
+#[derive(Component)]
+struct GameScreenMarker;
+
+fn start_game(
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut clock: ResMut<QuestionClock>,
+    localization: Res<Localization>,
+) {
+    *game = Game::default();
+    **clock = 0.0;
+    commands.spawn((
+        Name::new("Contestant"),
+        Contestant {
+            name: localization.text("game.you"),
+        },
+        LocalContestant,
+        Score::default(),
+        Streak::default(),
+    ));
+    commands.spawn((GameScreen.to_root(), GameScreenMarker));
+}
+
+fn end_game(
+    mut commands: Commands,
+    contestants: Query<Entity, With<Contestant>>,
+    screen: Query<Entity, With<GameScreenMarker>>,
+) {
+    for entity in contestants.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for entity in screen.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Asks the first question once the questions are ready, or goes back to the
+/// menu if none could be found
+fn begin_game(
+    mut ready: EventReader<QuestionSetReady>,
+    game: Res<Game>,
+    set: Res<QuestionSet>,
+    format: Res<GameFormat>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if ready.read().last().is_none() {
+        return;
+    }
+    if set.questions.is_empty() {
+        toasts.send(ShowToast(Toast::error("toast.no_questions")));
+        next_state.set(GameState::Menu);
+        return;
+    }
+    next_phase.set(game.asking(&set, &format));
+}
+
+/// Puts the answers of the question in order, clears the last answers, and
+/// reads the question and its answers out
+fn ask_question(
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut clock: ResMut<QuestionClock>,
+    set: Res<QuestionSet>,
+    shuffle: Res<ShuffleAnswers>,
+    answered: Query<Entity, With<Answer>>,
+    localization: Res<Localization>,
+    mut cues: EventWriter<AudioCue>,
+    mut announcements: EventWriter<Announce>,
+) {
+    let Some(question) = game.current(&set) else {
+        return;
+    };
+    let question = &question.question;
+    let mut answers = std::iter::once(&question.correct_answer)
+        .chain(&question.incorrect_answers)
+        .cloned()
+        .collect::<Vec<_>>();
+    if **shuffle {
+        answers.shuffle(&mut rand::thread_rng());
+    } else {
+        answers.sort_by_key(|answer| normalize(answer));
+    }
+    announcements.send(Announce(localization.format(
+        "game.announce_question",
+        &[
+            ("question", &question.question),
+            ("answers", &answers.join(", ")),
+        ],
+    )));
+    game.answers = answers;
+    **clock = 0.0;
+    for entity in answered.iter() {
+        commands.entity(entity).remove::<Answer>();
+    }
+    cues.send(AudioCue::QuestionShown);
+}
+
+/// Moves `clock` on by `delta` seconds, ticking through the last seconds
+/// before `limit`, if there is one, and saying how many are left when the
+/// ticking starts. Whether time is up.
+fn run_clock(
+    clock: &mut QuestionClock,
+    delta: f32,
+    limit: Option<f32>,
+    localization: &Localization,
+    cues: &mut EventWriter<AudioCue>,
+    announcements: &mut EventWriter<Announce>,
+) -> bool {
+    let before = **clock;
+    **clock += delta;
+    let Some(limit) = limit else {
+        return false;
+    };
+    let left = (limit - **clock).ceil();
+    if left < (limit - before).ceil() && left > 0.0 && left <= TICKING_SECS as f32 {
+        cues.send(AudioCue::TimerTick);
+        if left == TICKING_SECS as f32 {
+            announcements.send(Announce(
+                localization.format("game.announce_time_left", &[("seconds", &TICKING_SECS)]),
+            ));
+        }
+    }
+    **clock >= limit
+}
+
+/// Runs the question's clock, and reveals the answer when time is up
+fn tick_question(
+    time: Res<Time>,
+    mut clock: ResMut<QuestionClock>,
+    game: Res<Game>,
+    set: Res<QuestionSet>,
+    format: Res<GameFormat>,
+    mode: Res<GameMode>,
+    localization: Res<Localization>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+    mut cues: EventWriter<AudioCue>,
+    mut announcements: EventWriter<Announce>,
+) {
+    let limit = game.time_limit(&set, &format, *mode);
+    if run_clock(
+        &mut clock,
+        time.delta_seconds(),
+        limit,
+        &localization,
+        &mut cues,
+        &mut announcements,
+    ) {
+        next_phase.set(GamePhase::Reveal);
+    }
+}
+
+/// Takes the first answer of each contestant, and reveals the right one once
+/// everyone still in has answered
+fn take_answers(
+    mut commands: Commands,
+    mut submitted: EventReader<SubmitAnswer>,
+    clock: Res<QuestionClock>,
+    contestants: Query<(Entity, Has<Answer>, Has<Eliminated>), With<Contestant>>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+) {
+    let mut answered = contestants
+        .iter()
+        .filter(|(_, answered, _)| *answered)
+        .map(|(entity, ..)| entity)
+        .collect::<HashSet<_>>();
+    for event in submitted.read() {
+        if !contestants.contains(event.contestant) || !answered.insert(event.contestant) {
+            continue;
+        }
+        commands.entity(event.contestant).insert(Answer {
+            answer: event.answer.clone(),
+            secs: **clock,
+        });
+    }
+    if contestants
+        .iter()
+        .all(|(contestant, _, eliminated)| eliminated || answered.contains(&contestant))
+    {
+        next_phase.set(GamePhase::Reveal);
+    }
+}
+
+/// Gives a contestant `points` for a right answer, or takes them for a wrong
+/// one if negative scores are allowed. The cue to play for the answer.
+fn award(
+    score: &mut Score,
+    streak: &mut Streak,
+    right: bool,
+    points: i32,
+    negative_scores: bool,
+) -> AudioCue {
+    if right {
+        **score += points;
+        **streak += 1;
+        if **streak >= STREAK {
+            AudioCue::Streak
+        } else {
+            AudioCue::Correct
+        }
+    } else {
+        **streak = 0;
+        if negative_scores {
+            **score -= points;
+        }
+        AudioCue::Wrong
+    }
+}
+
+/// What the player on this device hears when the answer is revealed
+fn announce_result(
+    localization: &Localization,
+    answer: Option<&Answer>,
+    correct_answer: &str,
+    score: i32,
+) -> Announce {
+    let key = match answer {
+        Some(answer) if answer.answer == correct_answer => "game.announce_right",
+        Some(_) => "game.announce_wrong",
+        None => "game.announce_missed",
+    };
+    Announce(localization.format(key, &[("answer", &correct_answer), ("score", &score)]))
+}
+
+fn start_reveal(mut clock: ResMut<QuestionClock>) {
+    **clock = 0.0;
+}
+
+/// Gives each contestant the points of their answer, by the scoring of the
+/// round. Wrong answers lose as much if negative scores are allowed, but
+/// running out of time costs nothing. In wager rounds the stake is won or lost
+/// either way. Eliminated contestants only hear how they did.
+fn score_answers(
+    game: Res<Game>,
+    set: Res<QuestionSet>,
+    format: Res<GameFormat>,
+    negative_scores: Res<AllowNegativeScores>,
+    localization: Res<Localization>,
+    mut contestants: Query<(
+        &mut Score,
+        &mut Streak,
+        Option<&Answer>,
+        Option<&Wager>,
+        Has<LocalContestant>,
+        Has<Eliminated>,
+    )>,
+    mut cues: EventWriter<AudioCue>,
+    mut announcements: EventWriter<Announce>,
+) {
+    let Some(question) = game.current(&set) else {
+        return;
+    };
+    let round = game.round(&set, &format);
+    for (mut score, mut streak, answer, wager, local, eliminated) in contestants.iter_mut() {
+        let right = answer.is_some_and(|answer| answer.answer == question.question.correct_answer);
+        let (points, negative_scores) = match wager {
+            Some(wager) => (**wager, true),
+            None => (
+                answer.map_or(0, |answer| {
+                    round.map_or(1, |round| {
+                        round.scoring.points(answer.secs, *round.time_limit)
+                    })
+                }),
+                **negative_scores,
+            ),
+        };
+        let cue = match (eliminated, right) {
+            (false, _) => award(&mut score, &mut streak, right, points, negative_scores),
+            (true, true) => AudioCue::Correct,
+            (true, false) => AudioCue::Wrong,
+        };
+        if local {
+            cues.send(cue);
+            announcements.send(announce_result(
+                &localization,
+                answer,
+                &question.question.correct_answer,
+                **score,
+            ));
+        }
+    }
+}
+
+/// Moves on once the answer has been shown long enough: to the next question,
+/// the scores of the round that just ended, or the end of the game once the
+/// questions run out or an elimination game has its last player standing.
+/// With sudden death, questions keep coming past the end until one is left.
+fn next_question(
+    time: Res<Time>,
+    mut clock: ResMut<QuestionClock>,
+    mut game: ResMut<Game>,
+    mut set: ResMut<QuestionSet>,
+    format: Res<GameFormat>,
+    mode: Res<GameMode>,
+    sudden_death: Res<SuddenDeath>,
+    contestants: Query<Has<Eliminated>, With<Contestant>>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+) {
+    **clock += time.delta_seconds();
+    if **clock < REVEAL_SECS {
+        return;
+    }
+    let ends_round = set.ends_round(game.question);
+    game.question += 1;
+    let remaining = contestants.iter().filter(|eliminated| !eliminated).count();
+    let over = last_standing(remaining, contestants.iter().len());
+    let tied = *mode == GameMode::Elimination && **sudden_death && remaining > 1;
+    let out_of_questions = game.question >= set.questions.len();
+    if out_of_questions && tied {
+        set.add_tie_breaker();
+    }
+    next_phase.set(if over || game.question >= set.questions.len() {
+        GamePhase::Finished
+    } else if ends_round && !out_of_questions {
+        GamePhase::Intermission
+    } else {
+        game.asking(&set, &format)
+    });
+}
+
+/// Plays the fanfare and says who won, from the standings as they were after
+/// the last question
+fn announce_winner(
+    standings: Res<Standings>,
+    localization: Res<Localization>,
+    mut cues: EventWriter<AudioCue>,
+    mut announcements: EventWriter<Announce>,
+) {
+    cues.send(AudioCue::Winner);
+    if let Some(winner) = standings.first() {
+        announcements.send(Announce(localization.format(
+            "game.announce_winner",
+            &[("name", &winner.name), ("score", &winner.score)],
+        )));
+    }
+}
+
+fn update_standings(
+    contestants: Query<(
+        &Contestant,
+        &Score,
+        Has<LocalContestant>,
+        Option<&Lives>,
+        Has<Eliminated>,
+    )>,
+    mut standings: ResMut<Standings>,
+) {
+    let mut updated = contestants
+        .iter()
+        .map(|(contestant, score, local, lives, eliminated)| Standing {
+            name: contestant.name.clone(),
+            score: **score,
+            local,
+            lives: lives.map(|lives| **lives),
+            eliminated,
+        })
+        .collect::<Vec<_>>();
+    updated.sort_by(|a, b| {
+        a.eliminated
+            .cmp(&b.eliminated)
+            .then_with(|| b.lives.cmp(&a.lives))
+            .then_with(|| b.score.cmp(&a.score))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    standings.set_if_neq(Standings(updated));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::trivia::question::LocalizedQuestion;
+
+    /// A game at `question` of a set whose questions are of `rounds`, with the
+    /// answer shown long enough to move on
+    fn world(rounds: &[usize], format: Vec<Round>, question: usize) -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(QuestionClock(REVEAL_SECS));
+        world.insert_resource(Game {
+            question,
+            ..default()
+        });
+        world.insert_resource(QuestionSet {
+            questions: rounds
+                .iter()
+                .map(|round| SetQuestion {
+                    question: LocalizedQuestion::default(),
+                    round: *round,
+                })
+                .collect(),
+            ..default()
+        });
+        world.insert_resource(GameFormat { rounds: format });
+        world.insert_resource(GameMode::Classic);
+        world.init_resource::<SuddenDeath>();
+        world.init_resource::<NextState<GamePhase>>();
+        world
+    }
+
+    fn contestant(world: &mut World, eliminated: bool) {
+        let mut contestant = world.spawn(Contestant {
+            name: "Contestant".to_string(),
+        });
+        if eliminated {
+            contestant.insert(Eliminated);
+        }
+    }
+
+    fn next_phase(world: &World) -> Option<GamePhase> {
+        match world.resource::<NextState<GamePhase>>() {
+            NextState::Pending(phase) => Some(*phase),
+            NextState::Unchanged => None,
+        }
+    }
+
+    fn two_rounds() -> Vec<Round> {
+        vec![Round::default(), Round::default()]
+    }
+
+    #[test]
+    fn right_answers_build_a_streak() {
+        let mut score = Score(0);
+        let mut streak = Streak(0);
+        for _ in 1..STREAK {
+            assert_eq!(
+                award(&mut score, &mut streak, true, 2, false),
+                AudioCue::Correct
+            );
+        }
+        assert_eq!(
+            award(&mut score, &mut streak, true, 2, false),
+            AudioCue::Streak
+        );
+        assert_eq!(*score, 2 * STREAK as i32);
+        assert_eq!(
+            award(&mut score, &mut streak, false, 2, false),
+            AudioCue::Wrong
+        );
+        assert_eq!((*score, *streak), (2 * STREAK as i32, 0));
+    }
+
+    #[test]
+    fn wrong_answers_cost_points_only_if_negative_scores_are_allowed() {
+        let mut score = Score(1);
+        let mut streak = Streak(0);
+        award(&mut score, &mut streak, false, 2, false);
+        assert_eq!(*score, 1);
+        award(&mut score, &mut streak, false, 2, true);
+        assert_eq!(*score, -1);
+    }
+
+    #[test]
+    fn the_answer_is_shown_for_a_while() {
+        let mut world = world(&[0, 0], two_rounds(), 0);
+        world.insert_resource(QuestionClock(REVEAL_SECS / 2.0));
+        contestant(&mut world, false);
+        world.run_system_once(next_question);
+        assert_eq!(world.resource::<Game>().question, 0);
+        assert_eq!(next_phase(&world), None);
+    }
+
+    #[test]
+    fn questions_of_a_round_follow_each_other() {
+        let mut world = world(&[0, 0, 1], two_rounds(), 0);
+        contestant(&mut world, false);
+        world.run_system_once(next_question);
+        assert_eq!(world.resource::<Game>().question, 1);
+        assert_eq!(next_phase(&world), Some(GamePhase::Question));
+    }
+
+    #[test]
+    fn the_scores_are_shown_between_rounds() {
+        let mut world = world(&[0, 0, 1], two_rounds(), 1);
+        contestant(&mut world, false);
+        world.run_system_once(next_question);
+        assert_eq!(world.resource::<Game>().question, 2);
+        assert_eq!(next_phase(&world), Some(GamePhase::Intermission));
+    }
+
+    #[test]
+    fn wagers_are_placed_before_each_question_of_a_wager_round() {
+        let wager = Round {
+            scoring: Scoring::Wager,
+            ..default()
+        };
+        let mut world = world(&[0, 0], vec![wager], 0);
+        contestant(&mut world, false);
+        world.run_system_once(next_question);
+        assert_eq!(next_phase(&world), Some(GamePhase::Wager));
+    }
+
+    #[test]
+    fn the_game_ends_after_the_last_question() {
+        let mut world = world(&[0, 1], two_rounds(), 1);
+        contestant(&mut world, false);
+        world.run_system_once(next_question);
+        assert_eq!(next_phase(&world), Some(GamePhase::Finished));
+    }
+
+    #[test]
+    fn elimination_games_end_with_the_last_player_standing() {
+        let mut world = world(&[0, 0, 0], two_rounds(), 0);
+        world.insert_resource(GameMode::Elimination);
+        contestant(&mut world, false);
+        contestant(&mut world, true);
+        world.run_system_once(next_question);
+        assert_eq!(next_phase(&world), Some(GamePhase::Finished));
+    }
+
+    #[test]
+    fn sudden_death_asks_tie_breakers_past_the_last_question() {
+        let mut world = world(&[0, 1], two_rounds(), 1);
+        world.insert_resource(GameMode::Elimination);
+        **world.resource_mut::<SuddenDeath>() = true;
+        contestant(&mut world, false);
+        contestant(&mut world, false);
+        world.run_system_once(next_question);
+        let set = world.resource::<QuestionSet>();
+        assert_eq!(set.questions.len(), 3);
+        assert_eq!(set.questions[2].round, 1);
+        assert_eq!(next_phase(&world), Some(GamePhase::Question));
+    }
+
+    #[test]
+    fn sudden_death_only_breaks_ties_in_elimination_games() {
+        let mut world = world(&[0, 1], two_rounds(), 1);
+        **world.resource_mut::<SuddenDeath>() = true;
+        contestant(&mut world, false);
+        contestant(&mut world, false);
+        world.run_system_once(next_question);
+        assert_eq!(world.resource::<QuestionSet>().questions.len(), 2);
+        assert_eq!(next_phase(&world), Some(GamePhase::Finished));
+    }
+}
@@ -0,0 +1,93 @@
+//! What remote contestants send the host. There's no transport yet, so
+//! nothing is sent over the network: whatever carries the messages hands each
+//! one to the game as a [`ReceivedMessage`], and keeps the [`Latency`] of the
+//! contestant it came from up to date.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{buzzer::Buzz, Game, SubmitAnswer},
+    GameState,
+};
+
+/// The most a contestant's latency is made up for, so a slow connection can't
+/// hold up the game or claim a buzz from the past
+pub const MAX_LATENCY_SECS: f32 = 0.5;
+
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReceivedMessage>().add_systems(
+            Update,
+            receive_messages.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// A message from a contestant to the host
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ContestantMessage {
+    /// Buzzed in on the question at this index of the question set
+    Buzz { question: usize },
+    /// Picked an answer to the question at this index of the question set
+    Answer { question: usize, answer: String },
+}
+
+/// A message arrived from a remote contestant
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct ReceivedMessage {
+    pub from: Entity,
+    pub message: ContestantMessage,
+    /// When it arrived, as the host's [`Time::elapsed_seconds`]
+    pub received_at: f32,
+}
+
+/// How long a message takes to get from a remote contestant to the host, in
+/// seconds, as measured by the transport. Local contestants have none.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Deref, DerefMut)]
+pub struct Latency(pub f32);
+
+impl Latency {
+    /// How much of the latency is made up for
+    pub fn compensation(self) -> f32 {
+        self.0.clamp(0.0, MAX_LATENCY_SECS)
+    }
+}
+
+/// Turns the messages of remote contestants into what local ones do. Buzzes
+/// are dated back to when they were pressed, by the host's clock, so the
+/// fastest finger wins however far away it is. Messages about another
+/// question than the one being asked come too late, and are dropped.
+fn receive_messages(
+    mut received: EventReader<ReceivedMessage>,
+    game: Res<Game>,
+    latencies: Query<&Latency>,
+    mut buzzes: EventWriter<Buzz>,
+    mut answers: EventWriter<SubmitAnswer>,
+) {
+    for ReceivedMessage {
+        from,
+        message,
+        received_at,
+    } in received.read()
+    {
+        match message {
+            ContestantMessage::Buzz { question } if *question == game.question => {
+                let latency = latencies.get(*from).copied().unwrap_or_default();
+                buzzes.send(Buzz {
+                    contestant: *from,
+                    at: received_at - latency.compensation(),
+                });
+            }
+            ContestantMessage::Answer { question, answer } if *question == game.question => {
+                answers.send(SubmitAnswer {
+                    contestant: *from,
+                    answer: answer.clone(),
+                });
+            }
+            message => debug!("Dropped a late message from {from}: {message:?}"),
+        }
+    }
+}
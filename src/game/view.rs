@@ -0,0 +1,457 @@
+use bevy::prelude::*;
+use bevy_mod_stylebuilder::{StyleBuilder, StyleBuilderFont, StyleBuilderLayout};
+use bevy_quill::*;
+use bevy_quill_obsidian::{
+    controls::{Button, ButtonVariant},
+    focus::TabGroup,
+    size::Size,
+    typography,
+};
+
+use crate::{
+    game::{
+        buzzer::{Buzz, Buzzer, RevealedText},
+        wager::{max_wager, PlaceWager, Wager, WAGER_SECS},
+        Answer, Contestant, Game, GamePhase, LocalContestant, QuestionClock, Score, Standing,
+        Standings, SubmitAnswer,
+    },
+    layout::use_layout,
+    lobby::{format::GameFormat, GameMode},
+    locale::{use_text, Localization},
+    menu::{menu_content_style, menu_style, menu_title_style},
+    theme::{use_theme, Theme},
+    trivia::{card::QuestionCard, set::QuestionSet},
+    GameState,
+};
+
+/// The game being played: where it's at, the question, and the scores
+/// between rounds and at the end
+#[derive(Clone, PartialEq)]
+pub struct GameScreen;
+
+impl ViewTemplate for GameScreen {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let theme = use_theme(cx);
+        let layout = use_layout(cx);
+        let phase = *cx.use_resource::<State<GamePhase>>().get();
+        let asking = matches!(phase, GamePhase::Question | GamePhase::Reveal);
+        let buzzing =
+            phase == GamePhase::Question && *cx.use_resource::<GameMode>() == GameMode::Buzzer;
+
+        Element::<NodeBundle>::new()
+            .named("GameScreen")
+            .style_dyn(menu_style, (theme, layout))
+            .insert_dyn(|_| TabGroup::default(), ())
+            .children(
+                Element::<NodeBundle>::new()
+                    .style_dyn(menu_content_style, (theme, layout))
+                    .children(Cond::new(
+                        phase == GamePhase::Waiting,
+                        Element::<NodeBundle>::new()
+                            .style(typography::text_default)
+                            .children(use_text(cx, "game.waiting")),
+                        Cond::new(
+                            asking,
+                            (GameHeader, AskedQuestion, Cond::new(buzzing, BuzzPanel, ())),
+                            Cond::new(
+                                phase == GamePhase::Wager,
+                                (GameHeader, WagerPanel),
+                                Scoreboard {
+                                    finished: phase == GamePhase::Finished,
+                                },
+                            ),
+                        ),
+                    )),
+            )
+    }
+}
+
+/// The round and question being asked, the seconds left, and the player's
+/// score and lives
+#[derive(Clone, PartialEq)]
+struct GameHeader;
+
+impl ViewTemplate for GameHeader {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let theme = use_theme(cx);
+        let index = cx.use_resource::<Game>().question;
+        let (round, question, questions) = progress(cx.use_resource::<QuestionSet>(), index);
+        let rounds = cx.use_resource::<QuestionSet>().rounds();
+        let standing = cx
+            .use_resource::<Standings>()
+            .iter()
+            .find(|standing| standing.local)
+            .cloned();
+        let score = standing.as_ref().map_or(0, |standing| standing.score);
+        let localization = cx.use_resource::<Localization>();
+        let progress = localization.format(
+            "game.progress",
+            &[
+                ("round", &round),
+                ("rounds", &rounds),
+                ("question", &question),
+                ("questions", &questions),
+            ],
+        );
+        let score = localization.format("game.score", &[("score", &score)]);
+        let lives = match standing {
+            Some(standing) if standing.eliminated => localization.text("game.spectating"),
+            Some(Standing {
+                lives: Some(lives), ..
+            }) => localization.format("game.lives", &[("lives", &lives)]),
+            _ => String::new(),
+        };
+
+        Element::<NodeBundle>::new()
+            .style(style_game_header)
+            .style_dyn(style_game_status, theme)
+            .children((
+                Element::<NodeBundle>::new().children(progress),
+                Countdown,
+                Element::<NodeBundle>::new().children(score),
+                Element::<NodeBundle>::new().children(lives),
+            ))
+    }
+}
+
+/// The seconds left to answer, if the question is timed, or to place a wager
+#[derive(Clone, PartialEq)]
+struct Countdown;
+
+impl ViewTemplate for Countdown {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let phase = *cx.use_resource::<State<GamePhase>>().get();
+        let asking = phase == GamePhase::Question;
+        let secs = **cx.use_resource::<QuestionClock>();
+        // Tracked one by one, then read together
+        cx.use_resource::<Game>();
+        cx.use_resource::<QuestionSet>();
+        cx.use_resource::<GameFormat>();
+        cx.use_resource::<GameMode>();
+        let world = cx.world();
+        let limit = world
+            .resource::<Game>()
+            .time_limit(
+                world.resource::<QuestionSet>(),
+                world.resource::<GameFormat>(),
+                *world.resource::<GameMode>(),
+            )
+            .filter(|_| asking)
+            .or((phase == GamePhase::Wager).then_some(WAGER_SECS));
+        let text = limit.map(|limit| {
+            let seconds = (limit - secs).ceil().max(0.0) as usize;
+            cx.use_resource::<Localization>()
+                .format("game.seconds_left", &[("seconds", &seconds)])
+        });
+        Element::<NodeBundle>::new().children(text.unwrap_or_default())
+    }
+}
+
+/// The question being asked, which the player answers by picking one of its
+/// answers, and then the right answer
+#[derive(Clone, PartialEq)]
+struct AskedQuestion;
+
+impl ViewTemplate for AskedQuestion {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let revealed = *cx.use_resource::<State<GamePhase>>().get() == GamePhase::Reveal;
+        let game = cx.use_resource::<Game>().clone();
+        let mut question = game
+            .current(cx.use_resource::<QuestionSet>())
+            .map(|question| question.question.clone())
+            .unwrap_or_default();
+        let local = local_contestant(cx);
+        // In buzzer games the question is typed out, and only whoever buzzed
+        // in first can answer
+        let buzzer = *cx.use_resource::<GameMode>() == GameMode::Buzzer;
+        let answering = cx
+            .use_resource::<Buzzer>()
+            .answering
+            .map(|(contestant, _)| contestant);
+        if buzzer && !revealed {
+            let typed = **cx.use_resource::<RevealedText>();
+            question.question = question.question.chars().take(typed).collect();
+        }
+        let picked = local
+            .and_then(|local| cx.use_component::<Answer>(local))
+            .map(|answer| answer.answer.clone());
+        let may_answer = local.filter(|local| !buzzer || answering == Some(*local));
+        let on_answer = may_answer.map(|contestant| {
+            cx.create_callback(move |answer: In<String>, world: &mut World| {
+                world.send_event(SubmitAnswer {
+                    contestant,
+                    answer: answer.0,
+                });
+            })
+        });
+
+        QuestionCard {
+            question,
+            answers: game.answers,
+            on_answer,
+            picked,
+            revealed,
+        }
+    }
+}
+
+/// The buzzer of the player on this device, or who's answering instead
+#[derive(Clone, PartialEq)]
+struct BuzzPanel;
+
+impl ViewTemplate for BuzzPanel {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let theme = use_theme(cx);
+        let local = local_contestant(cx);
+        let buzzer = cx.use_resource::<Buzzer>().clone();
+        let can_buzz = local.is_some_and(|local| buzzer.can_buzz(local));
+        let answering = buzzer.answering.and_then(|(contestant, _)| {
+            cx.use_component::<Contestant>(contestant)
+                .map(|contestant| contestant.name.clone())
+        });
+        let locked_out = local.is_some_and(|local| buzzer.locked_out.contains(&local));
+        let localization = cx.use_resource::<Localization>();
+        let status = match answering {
+            Some(name) => localization.format("game.answering", &[("name", &name)]),
+            None if locked_out => localization.text("game.locked_out"),
+            None => String::new(),
+        };
+        let label = localization.text("game.buzz");
+        let on_buzz = cx.create_callback(move |world: &mut World| {
+            let at = world.resource::<Time>().elapsed_seconds();
+            if let Some(contestant) = local {
+                world.send_event(Buzz { contestant, at });
+            }
+        });
+
+        Element::<NodeBundle>::new()
+            .style(style_buzz_panel)
+            .style_dyn(style_game_status, theme)
+            .children((
+                Button::new()
+                    .size(Size::Xl)
+                    .variant(ButtonVariant::Primary)
+                    .disabled(!can_buzz)
+                    .on_click(on_buzz)
+                    .children(label),
+                Element::<NodeBundle>::new().children(status),
+            ))
+    }
+}
+
+/// Stakes for the player on this device to pick from, before the question of
+/// a wager round is asked, or what they staked
+#[derive(Clone, PartialEq)]
+struct WagerPanel;
+
+impl ViewTemplate for WagerPanel {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let theme = use_theme(cx);
+        let local = local_contestant(cx);
+        let score = local
+            .and_then(|local| cx.use_component::<Score>(local))
+            .map_or(0, |score| **score);
+        let placed = local
+            .and_then(|local| cx.use_component::<Wager>(local))
+            .map(|wager| **wager);
+        // Nothing, a quarter, half or all of the score
+        let max = max_wager(score);
+        let mut stakes = vec![0, max / 4, max / 2, max];
+        stakes.dedup();
+        let localization = cx.use_resource::<Localization>();
+        let status = match placed {
+            Some(points) => localization.format("game.wager_placed", &[("points", &points)]),
+            None => localization.text("game.wager"),
+        };
+        let labels = stakes
+            .iter()
+            .map(|points| localization.format("game.wager_points", &[("points", points)]))
+            .collect::<Vec<_>>();
+        let buttons = stakes
+            .into_iter()
+            .zip(labels)
+            .map(|(points, label)| {
+                let on_click = cx.create_callback(move |world: &mut World| {
+                    if let Some(contestant) = local {
+                        world.send_event(PlaceWager { contestant, points });
+                    }
+                });
+                Button::new()
+                    .size(Size::Xl)
+                    .variant(if placed == Some(points) {
+                        ButtonVariant::Selected
+                    } else {
+                        ButtonVariant::Default
+                    })
+                    .disabled(placed.is_some())
+                    .on_click(on_click)
+                    .children(label)
+                    .into_view_child()
+            })
+            .collect::<Vec<_>>();
+
+        Element::<NodeBundle>::new()
+            .style(style_buzz_panel)
+            .style_dyn(style_game_status, theme)
+            .children((Element::<NodeBundle>::new().children(status), buttons))
+    }
+}
+
+/// The scores of the contestants, after a round or at the end of the game,
+/// with a button to move on
+#[derive(Clone, PartialEq)]
+struct Scoreboard {
+    finished: bool,
+}
+
+impl ViewTemplate for Scoreboard {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let theme = use_theme(cx);
+        let layout = use_layout(cx);
+        let finished = self.finished;
+        let index = cx.use_resource::<Game>().question;
+        // The game has moved past the last question of the round that ended
+        let (round, ..) = progress(cx.use_resource::<QuestionSet>(), index.saturating_sub(1));
+        let standings = cx.use_resource::<Standings>().clone();
+        let localization = cx.use_resource::<Localization>();
+        let title = if finished {
+            localization.text("game.final_scores")
+        } else {
+            localization.format("game.intermission", &[("round", &round)])
+        };
+        let rows = standings
+            .iter()
+            .enumerate()
+            .map(|(place, standing)| {
+                let key = match standing.lives {
+                    _ if standing.eliminated => "game.standing_out",
+                    Some(_) => "game.standing_lives",
+                    None => "game.standing",
+                };
+                let text = localization.format(
+                    key,
+                    &[
+                        ("place", &(place + 1)),
+                        ("name", &standing.name),
+                        ("score", &standing.score),
+                        ("lives", &standing.lives.unwrap_or_default()),
+                    ],
+                );
+                Element::<NodeBundle>::new()
+                    .style(if standing.local {
+                        typography::text_strong
+                    } else {
+                        typography::text_default
+                    })
+                    .style_dyn(style_standing, (theme, standing.local))
+                    .children(text)
+                    .into_view_child()
+            })
+            .collect::<Vec<_>>();
+        let label = localization.text(if finished {
+            "game.back_to_menu"
+        } else {
+            "game.next_round"
+        });
+        let on_click = cx.create_callback(move |world: &mut World| {
+            if finished {
+                world
+                    .resource_mut::<NextState<GameState>>()
+                    .set(GameState::Menu);
+            } else {
+                let phase = world.resource::<Game>().asking(
+                    world.resource::<QuestionSet>(),
+                    world.resource::<GameFormat>(),
+                );
+                world.resource_mut::<NextState<GamePhase>>().set(phase);
+            }
+        });
+
+        (
+            Element::<NodeBundle>::new()
+                .style(typography::text_strong)
+                .style_dyn(menu_title_style, (theme, layout))
+                .children(title),
+            rows,
+            Button::new()
+                .size(Size::Xl)
+                .variant(ButtonVariant::Primary)
+                .on_click(on_click)
+                .children(label),
+        )
+    }
+}
+
+/// The contestant playing on this device
+fn local_contestant(cx: &mut Cx) -> Option<Entity> {
+    let mut local = cx
+        .world_mut()
+        .query_filtered::<Entity, With<LocalContestant>>();
+    local.get_single(cx.world()).ok()
+}
+
+/// The round of the question at `index` among the rounds with questions, its
+/// place in the round, and how many questions the round has, counting from 1
+fn progress(set: &QuestionSet, index: usize) -> (usize, usize, usize) {
+    let Some(current) = set.questions.get(index) else {
+        return (0, 0, 0);
+    };
+    let mut rounds = set.questions[..=index]
+        .iter()
+        .map(|question| question.round)
+        .collect::<Vec<_>>();
+    rounds.dedup();
+    let in_round = set
+        .questions
+        .iter()
+        .enumerate()
+        .filter(|(_, question)| question.round == current.round)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let question = in_round.iter().filter(|i| **i <= index).count();
+    (rounds.len(), question, in_round.len())
+}
+
+/// Wraps its parts when there's no room for them on one line
+fn style_game_header(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Row)
+        .flex_wrap(FlexWrap::Wrap)
+        .justify_content(JustifyContent::SpaceBetween)
+        .width(Val::Percent(100.0));
+}
+
+fn style_buzz_panel(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .align_items(AlignItems::Center);
+}
+
+fn style_game_status(theme: Theme, ss: &mut StyleBuilder) {
+    ss.column_gap(theme.spacing.gap)
+        .font_size(theme.font_sizes.body)
+        .color(theme.palette.foreground);
+}
+
+/// The player's own standing stands out
+fn style_standing((theme, local): (Theme, bool), ss: &mut StyleBuilder) {
+    ss.font_size(theme.font_sizes.heading).color(if local {
+        theme.palette.primary
+    } else {
+        theme.palette.foreground
+    });
+}
@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{elimination::Eliminated, Contestant, GamePhase, QuestionClock, Score},
+    GameState,
+};
+
+/// How long players have to place their wagers before the question is asked
+pub const WAGER_SECS: f32 = 20.0;
+
+/// Wager rounds: before each question, players stake some of their points on
+/// it, and win or lose their stake by their answer
+pub struct WagerPlugin;
+
+impl Plugin for WagerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaceWager>()
+            .add_systems(OnEnter(GamePhase::Wager), open_wagers)
+            .add_systems(OnExit(GamePhase::Reveal), clear_wagers)
+            .add_systems(
+                Update,
+                take_wagers
+                    .run_if(in_state(GamePhase::Wager))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// What a contestant staked on the question about to be asked
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Deref, DerefMut)]
+pub struct Wager(pub i32);
+
+/// A contestant staked `points` on the next question. Only the first wager
+/// counts, and it's kept between nothing and their whole score.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlaceWager {
+    pub contestant: Entity,
+    pub points: i32,
+}
+
+/// The most a contestant with `score` points can stake
+pub fn max_wager(score: i32) -> i32 {
+    score.max(0)
+}
+
+fn open_wagers(mut clock: ResMut<QuestionClock>) {
+    **clock = 0.0;
+}
+
+/// Takes the first wager of each contestant, and asks the question once
+/// everyone still in has placed one. Whoever hasn't when time is up stakes
+/// nothing.
+fn take_wagers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut clock: ResMut<QuestionClock>,
+    mut placed: EventReader<PlaceWager>,
+    contestants: Query<(Entity, &Score, Has<Wager>, Has<Eliminated>), With<Contestant>>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+) {
+    let mut wagered = contestants
+        .iter()
+        .filter(|(.., wagered, _)| *wagered)
+        .map(|(entity, ..)| entity)
+        .collect::<Vec<_>>();
+    for PlaceWager { contestant, points } in placed.read() {
+        let Ok((_, score, ..)) = contestants.get(*contestant) else {
+            continue;
+        };
+        if wagered.contains(contestant) {
+            continue;
+        }
+        wagered.push(*contestant);
+        let points = (*points).clamp(0, max_wager(**score));
+        commands.entity(*contestant).insert(Wager(points));
+    }
+    **clock += time.delta_seconds();
+    let waiting = contestants
+        .iter()
+        .filter(|(entity, _, _, eliminated)| !eliminated && !wagered.contains(entity))
+        .map(|(entity, ..)| entity)
+        .collect::<Vec<_>>();
+    if !waiting.is_empty() && **clock < WAGER_SECS {
+        return;
+    }
+    for contestant in waiting {
+        commands.entity(contestant).insert(Wager(0));
+    }
+    next_phase.set(GamePhase::Question);
+}
+
+fn clear_wagers(mut commands: Commands, wagered: Query<Entity, With<Wager>>) {
+    for entity in wagered.iter() {
+        commands.entity(entity).remove::<Wager>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<PlaceWager>()
+            .init_resource::<Time>()
+            .init_resource::<QuestionClock>()
+            .init_resource::<NextState<GamePhase>>()
+            .add_systems(Update, take_wagers);
+        app
+    }
+
+    fn contestant(app: &mut App, score: i32) -> Entity {
+        app.world_mut()
+            .spawn((
+                Contestant {
+                    name: "Contestant".to_string(),
+                },
+                Score(score),
+            ))
+            .id()
+    }
+
+    /// Runs a frame `secs` long, with `wagers` placed in it
+    fn frame(app: &mut App, secs: f32, wagers: &[(Entity, i32)]) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(secs));
+        for (contestant, points) in wagers {
+            app.world_mut().send_event(PlaceWager {
+                contestant: *contestant,
+                points: *points,
+            });
+        }
+        app.update();
+    }
+
+    fn wager(app: &App, contestant: Entity) -> Option<i32> {
+        app.world().get::<Wager>(contestant).map(|wager| **wager)
+    }
+
+    fn asked(app: &App) -> bool {
+        matches!(
+            app.world().resource::<NextState<GamePhase>>(),
+            NextState::Pending(GamePhase::Question)
+        )
+    }
+
+    #[test]
+    fn players_can_stake_up_to_their_whole_score() {
+        assert_eq!(max_wager(5), 5);
+        assert_eq!(max_wager(0), 0);
+        assert_eq!(max_wager(-3), 0);
+    }
+
+    #[test]
+    fn wagers_are_kept_between_nothing_and_the_whole_score() {
+        let mut app = app();
+        let over = contestant(&mut app, 5);
+        let under = contestant(&mut app, 5);
+        let within = contestant(&mut app, 5);
+        let negative = contestant(&mut app, -2);
+        frame(
+            &mut app,
+            1.0,
+            &[(over, 10), (under, -3), (within, 3), (negative, 3)],
+        );
+        assert_eq!(wager(&app, over), Some(5));
+        assert_eq!(wager(&app, under), Some(0));
+        assert_eq!(wager(&app, within), Some(3));
+        assert_eq!(wager(&app, negative), Some(0));
+    }
+
+    #[test]
+    fn only_the_first_wager_counts() {
+        let mut app = app();
+        let local = contestant(&mut app, 5);
+        contestant(&mut app, 5);
+        frame(&mut app, 1.0, &[(local, 2), (local, 4)]);
+        frame(&mut app, 1.0, &[(local, 5)]);
+        assert_eq!(wager(&app, local), Some(2));
+    }
+
+    #[test]
+    fn the_question_is_asked_once_everyone_still_in_has_wagered() {
+        let mut app = app();
+        let first = contestant(&mut app, 5);
+        let second = contestant(&mut app, 5);
+        let out = contestant(&mut app, 5);
+        app.world_mut().entity_mut(out).insert(Eliminated);
+        frame(&mut app, 1.0, &[(first, 1)]);
+        assert!(!asked(&app));
+        frame(&mut app, 1.0, &[(second, 1)]);
+        assert!(asked(&app));
+        assert_eq!(wager(&app, out), None);
+    }
+
+    #[test]
+    fn whoever_hasnt_wagered_when_time_is_up_stakes_nothing() {
+        let mut app = app();
+        let local = contestant(&mut app, 5);
+        let late = contestant(&mut app, 5);
+        frame(&mut app, 1.0, &[(local, 3)]);
+        frame(&mut app, WAGER_SECS - 2.0, &[]);
+        assert!(!asked(&app));
+        frame(&mut app, 1.0, &[]);
+        assert!(asked(&app));
+        assert_eq!(wager(&app, local), Some(3));
+        assert_eq!(wager(&app, late), Some(0));
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_quill::Cx;
+
+use crate::settings::Settings;
+
+/// Windows narrower than this, in logical pixels, get the [`Breakpoint::Compact`] layout
+const COMPACT_WIDTH: f32 = 600.0;
+
+/// Windows narrower than this get the [`Breakpoint::Medium`] layout
+const MEDIUM_WIDTH: f32 = 1000.0;
+
+/// Windows at least this wide get the [`Breakpoint::Large`] layout
+const LARGE_WIDTH: f32 = 1800.0;
+
+/// Sizes the UI to the window, following resizes and rotations, and scales it by
+/// the player's UI scale setting
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenLayout>()
+            .init_resource::<SafeArea>()
+            .register_type::<SafeArea>()
+            .add_systems(PreUpdate, update_layout);
+    }
+}
+
+/// How much room the window has, from phones to TVs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Breakpoint {
+    /// Phones, and narrow windows
+    Compact,
+    /// Tablets, and phones on their side
+    Medium,
+    /// Desktop windows
+    #[default]
+    Expanded,
+    /// Big screens seen from afar
+    Large,
+}
+
+impl Breakpoint {
+    fn from_width(width: f32) -> Self {
+        match width {
+            w if w < COMPACT_WIDTH => Self::Compact,
+            w if w < MEDIUM_WIDTH => Self::Medium,
+            w if w < LARGE_WIDTH => Self::Expanded,
+            _ => Self::Large,
+        }
+    }
+
+    /// How much to scale the UI by, before the player's own UI scale
+    fn scale(self) -> f32 {
+        match self {
+            Self::Compact => 0.8,
+            Self::Medium | Self::Expanded => 1.0,
+            Self::Large => 1.25,
+        }
+    }
+
+    /// How much of the window's width the menus take up
+    pub fn content_width(self) -> f32 {
+        match self {
+            Self::Compact => 100.0,
+            Self::Medium => 85.0,
+            Self::Expanded => 75.0,
+            Self::Large => 60.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    Portrait,
+    #[default]
+    Landscape,
+}
+
+/// Space along each edge of the window, in logical pixels
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Insets {
+    fn scaled(self, scale: f32) -> Self {
+        Self {
+            top: self.top * scale,
+            right: self.right * scale,
+            bottom: self.bottom * scale,
+            left: self.left * scale,
+        }
+    }
+}
+
+/// The edges of the screen hidden behind notches, rounded corners and system bars.
+/// Set by the platform, since only it knows where they are.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Deref, DerefMut)]
+#[reflect(Resource)]
+pub struct SafeArea(pub Insets);
+
+/// The layout the UI should take for the current window
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScreenLayout {
+    pub breakpoint: Breakpoint,
+    pub orientation: Orientation,
+    /// The [`SafeArea`] in UI units, which [`UiScale`] multiplies, to be kept clear
+    pub insets: Insets,
+}
+
+impl ScreenLayout {
+    /// Whether there's only room for one item across
+    pub fn is_narrow(&self) -> bool {
+        self.breakpoint == Breakpoint::Compact && self.orientation == Orientation::Portrait
+    }
+}
+
+/// Works out the layout and UI scale for the window as it is now, so resizes and
+/// rotations are picked up. Only styles depend on these, so nothing is rebuilt.
+fn update_layout(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<Settings>,
+    safe_area: Res<SafeArea>,
+    mut layout: ResMut<ScreenLayout>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let (width, height) = (window.width(), window.height());
+    let breakpoint = Breakpoint::from_width(width);
+    let scale = breakpoint.scale() * settings.ui_scale as f32 / 100.0;
+
+    layout.set_if_neq(ScreenLayout {
+        breakpoint,
+        orientation: if height > width {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        },
+        insets: safe_area.scaled(1.0 / scale),
+    });
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+/// The layout in use, re-rendering when it changes
+pub fn use_layout(cx: &mut Cx) -> ScreenLayout {
+    *cx.use_resource::<ScreenLayout>()
+}
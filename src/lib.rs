@@ -1,20 +1,33 @@
 #![feature(impl_trait_in_assoc_type, associated_type_defaults)]
-#![allow(clippy::type_complexity)]
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
 
+mod accessibility;
 mod actions;
-mod audio;
+pub mod audio;
+mod dialog;
+mod editor;
+mod game;
+pub mod layout;
 pub mod loading;
 mod lobby;
+pub mod locale;
 pub mod menu;
-mod player;
-mod trivia;
+mod settings;
+mod storage;
+mod theme;
+pub mod trivia;
 pub mod utils;
 
 use crate::{
-    actions::ActionsPlugin, audio::InternalAudioPlugin, loading::LoadingPlugin, lobby::LobbyPlugin,
-    menu::MenuPlugin, player::PlayerPlugin,
+    accessibility::AccessibilityPlugin, actions::ActionsPlugin, audio::InternalAudioPlugin,
+    dialog::DialogPlugin, editor::EditorPlugin, game::PlayPlugin, layout::LayoutPlugin,
+    loading::LoadingPlugin, lobby::LobbyPlugin, locale::LocalePlugin, menu::MenuPlugin,
+    settings::SettingsPlugin, theme::ThemePlugin, trivia::media::MediaPlugin,
+    trivia::set::QuestionSetPlugin,
 };
 
+pub use storage::UserAssetsPlugin;
+
 use bevy::app::App;
 use bevy::prelude::*;
 use bevy_http_client::HttpClientPlugin;
@@ -55,11 +68,19 @@ impl Plugin for GamePlugin {
             // These are our own plugins
             .add_plugins((
                 LoadingPlugin,
+                DialogPlugin,
                 MenuPlugin,
                 ActionsPlugin,
                 InternalAudioPlugin,
-                PlayerPlugin,
                 LobbyPlugin,
+                SettingsPlugin,
+                LocalePlugin,
+                ThemePlugin,
+                AccessibilityPlugin,
+                LayoutPlugin,
+                MediaPlugin,
+                EditorPlugin,
+                (QuestionSetPlugin, PlayPlugin),
             ))
             .insert_resource(ClearColor(colors::BACKGROUND.into()))
             .insert_resource(ShowInspectorUi(false))
@@ -97,7 +118,10 @@ mod debug {
     use bevy_quill_obsidian::focus::{DefaultKeyListener, KeyPressEvent};
 
     use crate::{
-        lobby::{GameName, NumQuestions, QuestionDifficulty, QuestionTypes},
+        lobby::{
+            format::GameFormat, AllowNegativeScores, DifficultyRamp, GameMode, GameName,
+            QuestionLanguage, ShuffleAnswers, StartingLives, SuddenDeath,
+        },
         ShowInspectorUi,
     };
 
@@ -161,9 +185,14 @@ mod debug {
                     .default_open(true)
                     .show(ui, |ui| {
                         ui_for_resource!(GameName, world, ui);
-                        ui_for_resource!(NumQuestions, world, ui);
-                        ui_for_resource!(QuestionDifficulty, world, ui);
-                        ui_for_resource!(QuestionTypes, world, ui);
+                        ui_for_resource!(GameFormat, world, ui);
+                        ui_for_resource!(GameMode, world, ui);
+                        ui_for_resource!(ShuffleAnswers, world, ui);
+                        ui_for_resource!(AllowNegativeScores, world, ui);
+                        ui_for_resource!(QuestionLanguage, world, ui);
+                        ui_for_resource!(StartingLives, world, ui);
+                        ui_for_resource!(DifficultyRamp, world, ui);
+                        ui_for_resource!(SuddenDeath, world, ui);
                     });
 
                 // ui.heading("Entities");
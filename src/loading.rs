@@ -3,7 +3,9 @@ use bevy_asset_loader::prelude::*;
 use bevy_common_assets::ron::RonAssetPlugin;
 use bevy_kira_audio::AudioSource;
 
-use crate::{menu::serde::Menu, GameState};
+use crate::{
+    locale::Locale, menu::serde::Menu, theme::Theme, trivia::source::pack::QuestionPack, GameState,
+};
 
 pub struct LoadingPlugin;
 
@@ -12,14 +14,21 @@ pub struct LoadingPlugin;
 /// If interested, take a look at <https://bevy-cheatbook.github.io/features/assets.html>
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(RonAssetPlugin::<Menu>::new(&["menu.ron"]))
-            .add_loading_state(
-                LoadingState::new(GameState::Loading)
-                    .continue_to_state(GameState::Menu)
-                    .load_collection::<AudioAssets>()
-                    .load_collection::<TextureAssets>()
-                    .load_collection::<MenuAssets>(),
-            );
+        app.add_plugins((
+            RonAssetPlugin::<Menu>::new(&["menu.ron"]),
+            RonAssetPlugin::<Locale>::new(&["locale.ron"]),
+            RonAssetPlugin::<Theme>::new(&["theme.ron"]),
+        ))
+        .add_loading_state(
+            LoadingState::new(GameState::Loading)
+                .continue_to_state(GameState::Menu)
+                .load_collection::<AudioAssets>()
+                .load_collection::<TextureAssets>()
+                .load_collection::<MenuAssets>()
+                .load_collection::<LocaleAssets>()
+                .load_collection::<ThemeAssets>()
+                .load_collection::<PackAssets>(),
+        );
     }
 }
 
@@ -28,8 +37,50 @@ impl Plugin for LoadingPlugin {
 
 #[derive(AssetCollection, Resource)]
 pub struct AudioAssets {
-    #[asset(path = "audio/flying.ogg")]
-    pub flying: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/question.wav")]
+    pub question: Handle<AudioSource>,
+
+    #[asset(path = "audio/sfx/tick.wav")]
+    pub tick: Handle<AudioSource>,
+
+    #[asset(path = "audio/sfx/correct.wav")]
+    pub correct: Handle<AudioSource>,
+
+    #[asset(path = "audio/sfx/wrong.wav")]
+    pub wrong: Handle<AudioSource>,
+
+    #[asset(path = "audio/sfx/streak.wav")]
+    pub streak: Handle<AudioSource>,
+
+    #[asset(path = "audio/sfx/fanfare.wav")]
+    pub fanfare: Handle<AudioSource>,
+
+    #[asset(path = "audio/ui/move.wav")]
+    pub ui_move: Handle<AudioSource>,
+
+    #[asset(path = "audio/ui/select.wav")]
+    pub ui_select: Handle<AudioSource>,
+
+    /// Background music, looked up by file name without the extension
+    #[asset(
+        paths(
+            "audio/music/menu.wav",
+            "audio/music/game.wav",
+            "audio/music/credits.wav"
+        ),
+        collection(typed, mapped)
+    )]
+    pub music: HashMap<String, Handle<AudioSource>>,
+}
+
+impl AudioAssets {
+    /// The music track called `name`, from `audio/music/<name>.wav`
+    pub fn track(&self, name: &str) -> Option<Handle<AudioSource>> {
+        self.music.iter().find_map(|(path, handle)| {
+            let file = path.rsplit('/').next()?;
+            (file.strip_suffix(".wav")? == name).then(|| handle.clone())
+        })
+    }
 }
 
 pub const BEVY: &str = "textures/bevy.png";
@@ -58,6 +109,39 @@ pub struct MenuAssets {
     #[asset(path = "menus/host.menu.ron")]
     pub host: Handle<Menu>,
 
+    #[asset(path = "menus/editor.menu.ron")]
+    pub editor: Handle<Menu>,
+
     #[asset(path = "fonts/Cursor.ttf")]
     pub cursor_font: Handle<Font>,
 }
+
+/// Listed one by one rather than as a folder, since folders can't be loaded on the web
+#[derive(AssetCollection, Resource)]
+pub struct LocaleAssets {
+    #[asset(
+        paths("locales/en.locale.ron", "locales/es.locale.ron"),
+        collection(typed, mapped)
+    )]
+    pub locales: HashMap<String, Handle<Locale>>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ThemeAssets {
+    #[asset(
+        paths(
+            "themes/dark.theme.ron",
+            "themes/light.theme.ron",
+            "themes/high_contrast.theme.ron"
+        ),
+        collection(typed, mapped)
+    )]
+    pub themes: HashMap<String, Handle<Theme>>,
+}
+
+/// The question packs that ship with the game
+#[derive(AssetCollection, Resource)]
+pub struct PackAssets {
+    #[asset(paths("packs/sample.pack.ron"), collection(typed))]
+    pub packs: Vec<Handle<QuestionPack>>,
+}
@@ -0,0 +1,755 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    lobby::{QuestionCategories, QuestionDifficulty, QuestionTypes, TimeLimit},
+    locale::Localization,
+    menu::{
+        action::RegisterMenuAction,
+        condition::{sync_menu_values, MenuValue, MenuValues},
+        serde::ToggleValue,
+        widgets::{
+            dropdown::DropdownSelected, multi_dropdown::MultiDropdownSelected,
+            radio_group::RadioGroupSelected, stepper::StepperValue,
+        },
+        WhichMenu,
+    },
+    trivia::{
+        set::{MixOrder, MixPart, QuestionOrigin, SourceMix},
+        source::{
+            provider::ProviderSource, DoneFetching, FetchError, Fetching, FromCache, StringSource,
+            TriviaSource,
+        },
+    },
+};
+
+/// How many seconds players get to answer when a round is first timed
+const DEFAULT_TIME_LIMIT: usize = 20;
+
+/// Lets hosts set up the rounds of a game in the host menu, one at a time
+pub struct FormatPlugin;
+
+impl Plugin for FormatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditedFormat>()
+            .init_resource::<GameFormat>()
+            .register_type::<GameFormat>()
+            .register_menu_action("round_new", new_round)
+            .register_menu_action("round_delete", delete_round)
+            .register_menu_action("round_move_up", move_round(-1))
+            .register_menu_action("round_move_down", move_round(1))
+            .add_systems(OnEnter(WhichMenu::HostGame), open_format)
+            .add_systems(
+                Update,
+                (
+                    apply_round_form.run_if(resource_changed::<MenuValues>),
+                    bind_categories,
+                    list_rounds,
+                    fill_round_form,
+                )
+                    .chain()
+                    .after(sync_menu_values)
+                    .run_if(in_state(WhichMenu::HostGame)),
+            );
+    }
+}
+
+/// How a round scores right answers
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum Scoring {
+    /// A point for each right answer
+    #[default]
+    Standard,
+    /// Two points for each right answer
+    Double,
+    /// Two points for answering right in the first half of the time, one after.
+    /// Untimed rounds score like [`Scoring::Standard`].
+    Speed,
+    /// Players stake some of their points before each question, and win
+    /// their stake with a right answer or lose it with a wrong one or none.
+    /// See [`crate::game::wager`].
+    Wager,
+}
+
+impl Scoring {
+    /// In the order the host menu lists them
+    pub const ALL: [Self; 4] = [Self::Standard, Self::Double, Self::Speed, Self::Wager];
+
+    /// The points for a right answer given `secs` into a question with
+    /// `time_limit` seconds to answer it. Wagers score their stake instead.
+    pub fn points(self, secs: f32, time_limit: Option<usize>) -> i32 {
+        match (self, time_limit) {
+            (Self::Standard, _) | (Self::Speed, None) | (Self::Wager, _) => 1,
+            (Self::Double, _) => 2,
+            (Self::Speed, Some(limit)) if secs * 2.0 < limit as f32 => 2,
+            (Self::Speed, Some(_)) => 1,
+        }
+    }
+}
+
+/// A round of a game: where its questions come from, how long players get to
+/// answer them and how they're scored
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct Round {
+    /// The id of the question provider the categories are of
+    pub provider: String,
+    pub categories: QuestionCategories,
+    pub difficulty: QuestionDifficulty,
+    pub types: QuestionTypes,
+    /// How many questions the provider gives
+    pub provider_questions: usize,
+    /// The name of the pack the rest of the questions come from, if any
+    pub pack: Option<String>,
+    pub pack_questions: usize,
+    pub order: MixOrder,
+    pub time_limit: TimeLimit,
+    pub scoring: Scoring,
+}
+
+impl Default for Round {
+    fn default() -> Self {
+        Self {
+            provider: "opentdb".to_string(),
+            categories: QuestionCategories::default(),
+            difficulty: QuestionDifficulty::default(),
+            types: QuestionTypes::default(),
+            provider_questions: 10,
+            pack: None,
+            pack_questions: 0,
+            order: MixOrder::default(),
+            time_limit: TimeLimit::default(),
+            scoring: Scoring::default(),
+        }
+    }
+}
+
+impl Round {
+    /// The sources of the round's questions, and how many each gives
+    pub fn mix(&self) -> SourceMix {
+        let provider = MixPart {
+            origin: QuestionOrigin::Provider(self.provider.clone()),
+            quota: self.provider_questions,
+        };
+        let pack = self.pack.clone().map(|name| MixPart {
+            origin: QuestionOrigin::Pack(name),
+            quota: self.pack_questions,
+        });
+        SourceMix {
+            parts: std::iter::once(provider)
+                .chain(pack)
+                .filter(|part| part.quota > 0)
+                .collect(),
+            order: self.order,
+        }
+    }
+
+    /// How many questions the round would have if every source had enough
+    pub fn len(&self) -> usize {
+        self.mix().total()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of the form field called `name`, listing the options of `source`
+    fn value(&self, name: &str, source: Option<&TriviaSource>) -> Option<MenuValue> {
+        match name {
+            "provider" => {
+                let Some(TriviaSource::Providers(list)) = source else {
+                    return None;
+                };
+                let index = list.position(&self.provider)?;
+                Some(MenuValue::Selection(HashSet::from_iter([index])))
+            }
+            "categories" => {
+                let Some(TriviaSource::Provider(source)) = source else {
+                    return None;
+                };
+                // Categories that haven't been fetched yet are all selected once they are
+                if source.is_empty() {
+                    return None;
+                }
+                Some(MenuValue::Selection(
+                    (0..source.len())
+                        .filter(|i| {
+                            self.categories.is_empty() || self.categories.contains(&source[*i])
+                        })
+                        .collect(),
+                ))
+            }
+            "difficulty" => Some(MenuValue::Selection(selection(source?, &self.difficulty))),
+            "question_types" => Some(MenuValue::Selection(selection(source?, &self.types))),
+            "num_questions" => Some(MenuValue::Number(self.provider_questions as f64)),
+            "pack" => Some(MenuValue::Selection(
+                self.pack
+                    .as_ref()
+                    .and_then(|pack| source?.iter().position(|name| name == *pack))
+                    .into_iter()
+                    .collect(),
+            )),
+            "pack_questions" => Some(MenuValue::Number(self.pack_questions as f64)),
+            "question_order" => index_of(&MixOrder::ALL, self.order),
+            "timed" => Some(MenuValue::Bool(self.time_limit.is_some())),
+            "time_limit" => self.time_limit.map(|secs| MenuValue::Number(secs as f64)),
+            "scoring" => index_of(&Scoring::ALL, self.scoring),
+            _ => None,
+        }
+    }
+
+    /// Updates the form field called `name` from the widget editing it, which
+    /// lists the options of `source`
+    fn apply(&mut self, name: &str, value: &MenuValue, source: Option<&TriviaSource>) {
+        match (name, value) {
+            ("provider", MenuValue::Selection(selected)) => {
+                let Some(TriviaSource::Providers(list)) = source else {
+                    return;
+                };
+                let Some(id) = selected.iter().find_map(|i| list.id(*i)) else {
+                    return;
+                };
+                // The categories of one provider mean nothing to another
+                if id != self.provider {
+                    self.provider = id.to_string();
+                    self.categories = QuestionCategories::default();
+                }
+            }
+            ("categories", MenuValue::Selection(selected)) => {
+                let Some(TriviaSource::Provider(source)) = source else {
+                    return;
+                };
+                // Still listing the categories of the provider picked before
+                if source.id != self.provider {
+                    return;
+                }
+                // Any category will do when they all are
+                self.categories = QuestionCategories(if selected.len() == source.len() {
+                    Vec::new()
+                } else {
+                    sorted(selected)
+                        .filter_map(|i| source.category(i).cloned())
+                        .collect()
+                });
+            }
+            ("difficulty", MenuValue::Selection(selected)) => {
+                self.difficulty = QuestionDifficulty(options(source, selected));
+            }
+            ("question_types", MenuValue::Selection(selected)) => {
+                self.types = QuestionTypes(options(source, selected));
+            }
+            ("num_questions", MenuValue::Number(value)) => {
+                self.provider_questions = value.max(0.0) as usize;
+            }
+            ("pack", MenuValue::Selection(selected)) => {
+                self.pack = options(source, selected).into_iter().next();
+            }
+            ("pack_questions", MenuValue::Number(value)) => {
+                self.pack_questions = value.max(0.0) as usize;
+            }
+            ("question_order", MenuValue::Selection(selected)) => {
+                if let Some(order) = selected.iter().find_map(|i| MixOrder::ALL.get(*i)) {
+                    self.order = *order;
+                }
+            }
+            ("timed", MenuValue::Bool(timed)) => {
+                if !timed {
+                    self.time_limit = TimeLimit(None);
+                } else if self.time_limit.is_none() {
+                    self.time_limit = TimeLimit(Some(DEFAULT_TIME_LIMIT));
+                }
+            }
+            // Hidden, and so missing, while the round isn't timed
+            ("time_limit", MenuValue::Number(value)) => {
+                if self.time_limit.is_some() {
+                    self.time_limit = TimeLimit(Some(value.max(1.0) as usize));
+                }
+            }
+            ("scoring", MenuValue::Selection(selected)) => {
+                if let Some(scoring) = selected.iter().find_map(|i| Scoring::ALL.get(*i)) {
+                    self.scoring = *scoring;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The indices of `chosen` among the options of `source`, all of them if
+/// nothing was chosen, since that means any will do
+fn selection(source: &TriviaSource, chosen: &[String]) -> HashSet<usize> {
+    source
+        .iter()
+        .enumerate()
+        .filter(|(_, option)| chosen.is_empty() || chosen.contains(option))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// The options of `source` that are `selected`, in the order it lists them
+fn options(source: Option<&TriviaSource>, selected: &HashSet<usize>) -> Vec<String> {
+    let Some(source) = source else {
+        return Vec::new();
+    };
+    sorted(selected)
+        .filter(|i| *i < source.len())
+        .map(|i| source[i].clone())
+        .collect()
+}
+
+fn sorted(selected: &HashSet<usize>) -> impl Iterator<Item = usize> {
+    let mut selected = selected.iter().copied().collect::<Vec<_>>();
+    selected.sort_unstable();
+    selected.into_iter()
+}
+
+/// The selection of a radio group listing `options`, with `value` chosen
+fn index_of<T: PartialEq>(options: &[T], value: T) -> Option<MenuValue> {
+    options
+        .iter()
+        .position(|option| *option == value)
+        .map(|i| MenuValue::Selection(HashSet::from_iter([i])))
+}
+
+/// The rounds of the game being hosted, in the order they're played
+#[derive(Resource, Default, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct GameFormat {
+    pub rounds: Vec<Round>,
+}
+
+/// The rounds set up in the host menu, and which of them the form shows.
+/// There is always at least one round.
+///
+/// Every field of the form is edited by the widget with the same name, like
+/// [`crate::editor::EditedPack`].
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct EditedFormat {
+    pub rounds: Vec<Round>,
+
+    /// The index of the round in the form
+    pub current: usize,
+
+    /// The form shows another round, or nothing yet, and needs filling in
+    stale: bool,
+
+    /// The categories list hasn't been filled in with the round's categories
+    /// yet, since they're still being fetched
+    categories_stale: bool,
+}
+
+impl Default for EditedFormat {
+    fn default() -> Self {
+        Self {
+            rounds: vec![Round::default()],
+            current: 0,
+            stale: true,
+            categories_stale: true,
+        }
+    }
+}
+
+impl EditedFormat {
+    /// The round in the form
+    pub fn round(&self) -> &Round {
+        &self.rounds[self.current]
+    }
+
+    /// Shows the round at `index` in the form, or the last one past the end
+    fn select(&mut self, index: usize) {
+        self.current = index.min(self.rounds.len() - 1);
+        self.stale = true;
+        self.categories_stale = true;
+    }
+
+    /// The rounds to play, leaving out those without any questions
+    pub fn format(&self) -> GameFormat {
+        GameFormat {
+            rounds: self
+                .rounds
+                .iter()
+                .filter(|round| !round.is_empty())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The entries of the round list, numbered, with how many questions each has
+    fn titles(&self, localization: &Localization) -> Vec<String> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .map(|(i, round)| {
+                localization.format(
+                    "round.title",
+                    &[("number", &(i + 1)), ("questions", &round.len())],
+                )
+            })
+            .collect()
+    }
+}
+
+/// Has the form filled in with the current round each time the host menu opens
+fn open_format(mut edited: ResMut<EditedFormat>) {
+    let current = edited.current;
+    edited.select(current);
+}
+
+/// Copies edits made in the form into the round it shows. Only values that
+/// changed are copied, so that filling the form in doesn't write the round
+/// shown before over the new one.
+fn apply_round_form(
+    values: Res<MenuValues>,
+    mut last: Local<MenuValues>,
+    mut edited: ResMut<EditedFormat>,
+    sources: Query<(&Name, &TriviaSource)>,
+) {
+    let previous = std::mem::replace(&mut *last, values.clone());
+    // The form is still being filled in, so its values are of no round yet
+    if edited.stale {
+        return;
+    }
+    let current = edited.current;
+    let mut selected = None;
+    for (name, value) in values.iter() {
+        if previous.get(name) == Some(value) {
+            continue;
+        }
+        match (name.as_str(), value) {
+            ("round_list", MenuValue::Selection(selection)) => {
+                selected = selection.iter().next().copied();
+            }
+            // Until it's filled in, the selection is whatever was fetched
+            ("categories", _) if edited.categories_stale => {}
+            (name, value) => {
+                let source = sources
+                    .iter()
+                    .find_map(|(n, source)| (n.as_str() == name).then_some(source));
+                edited.rounds[current].apply(name, value, source);
+            }
+        }
+    }
+    // Switch rounds last, so that edits go to the round they were made on
+    if let Some(index) = selected.filter(|index| *index != edited.current) {
+        edited.select(index);
+    }
+}
+
+/// Has the categories list show the categories of the current round's
+/// provider, fetching them again when the host picks another
+fn bind_categories(
+    mut commands: Commands,
+    mut edited: ResMut<EditedFormat>,
+    mut sources: Query<(Entity, &Name, &mut TriviaSource)>,
+) {
+    for (entity, name, mut source) in sources.iter_mut() {
+        if name.as_str() != "categories" {
+            continue;
+        }
+        let provider = &edited.round().provider;
+        match &*source {
+            TriviaSource::Provider(bound) if bound.id != *provider => {}
+            _ => continue,
+        }
+        *source = TriviaSource::Provider(ProviderSource::from(provider.clone()));
+        commands
+            .entity(entity)
+            .remove::<(Fetching, DoneFetching, FetchError, FromCache)>();
+        edited.categories_stale = true;
+    }
+}
+
+/// Keeps the round list up to date
+fn list_rounds(
+    edited: Res<EditedFormat>,
+    localization: Res<Localization>,
+    mut sources: Query<(&Name, &mut TriviaSource)>,
+) {
+    if !edited.is_changed() && !localization.is_changed() {
+        return;
+    }
+    let titles = TriviaSource::String(StringSource::new(edited.titles(&localization)));
+    for (_, mut source) in sources
+        .iter_mut()
+        .filter(|(name, _)| name.as_str() == "round_list")
+    {
+        source.set_if_neq(titles.clone());
+    }
+}
+
+/// Fills the form in with the current round when it shows another one. The
+/// categories are filled in once they've been fetched.
+fn fill_round_form(
+    mut edited: ResMut<EditedFormat>,
+    mut multi_dropdowns: Query<
+        (&Name, &mut TriviaSource, &mut MultiDropdownSelected),
+        Without<DropdownSelected>,
+    >,
+    mut dropdowns: Query<(&Name, &TriviaSource, &mut DropdownSelected)>,
+    mut steppers: Query<(&Name, &mut StepperValue)>,
+    mut toggles: Query<(&Name, &mut ToggleValue)>,
+    mut radio_groups: Query<(&Name, &mut RadioGroupSelected)>,
+) {
+    if edited.categories_stale {
+        let round = edited.round();
+        let categories = multi_dropdowns
+            .iter_mut()
+            .find(|(name, ..)| name.as_str() == "categories");
+        if let Some((name, mut source, mut selected)) = categories {
+            if let Some(MenuValue::Selection(value)) = round.value(name, Some(&source)) {
+                // The source's own selection is what the list shows once fetched
+                source.set_selected(value.clone());
+                if **selected != value {
+                    **selected = value;
+                }
+                edited.categories_stale = false;
+            }
+        }
+    }
+    if !edited.stale {
+        return;
+    }
+    let round = edited.round();
+    let current = edited.current;
+    let mut filled = false;
+    for (name, source, mut selected) in multi_dropdowns.iter_mut() {
+        if name.as_str() == "categories" {
+            continue;
+        }
+        if let Some(MenuValue::Selection(value)) = round.value(name, Some(&source)) {
+            if **selected != value {
+                **selected = value;
+            }
+            filled = true;
+        }
+    }
+    for (name, source, mut selected) in dropdowns.iter_mut() {
+        let value = match name.as_str() {
+            "round_list" => Some(current),
+            name => match round.value(name, Some(source)) {
+                Some(MenuValue::Selection(value)) => value.iter().next().copied(),
+                _ => continue,
+            },
+        };
+        if **selected != value {
+            **selected = value;
+        }
+        filled = true;
+    }
+    for (name, mut stepper) in steppers.iter_mut() {
+        if let Some(MenuValue::Number(value)) = round.value(name, None) {
+            if **stepper != value as i64 {
+                **stepper = value as i64;
+            }
+            filled = true;
+        }
+    }
+    for (name, mut toggle) in toggles.iter_mut() {
+        if let Some(MenuValue::Bool(value)) = round.value(name, None) {
+            if **toggle != value {
+                **toggle = value;
+            }
+            filled = true;
+        }
+    }
+    for (name, mut radio_group) in radio_groups.iter_mut() {
+        if let Some(MenuValue::Selection(value)) = round.value(name, None) {
+            if let Some(value) = value.iter().next().copied() {
+                if **radio_group != value {
+                    **radio_group = value;
+                }
+                filled = true;
+            }
+        }
+    }
+    // The menu's widgets may not have been spawned yet
+    if filled {
+        edited.stale = false;
+    }
+}
+
+/// Adds a round after the current one, set up like it
+fn new_round(mut edited: ResMut<EditedFormat>) {
+    let index = edited.current + 1;
+    let round = edited.round().clone();
+    edited.rounds.insert(index, round);
+    edited.select(index);
+}
+
+fn delete_round(mut edited: ResMut<EditedFormat>) {
+    let current = edited.current;
+    edited.rounds.remove(current);
+    if edited.rounds.is_empty() {
+        edited.rounds.push(Round::default());
+    }
+    edited.select(current);
+}
+
+/// Moves the current round `offset` places through the game
+fn move_round(offset: isize) -> impl FnMut(ResMut<EditedFormat>) {
+    move |mut edited: ResMut<EditedFormat>| {
+        let current = edited.current;
+        let Some(target) = current
+            .checked_add_signed(offset)
+            .filter(|target| *target < edited.rounds.len())
+        else {
+            return;
+        };
+        edited.rounds.swap(current, target);
+        edited.select(target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// A round told apart from the others by how many questions it has
+    fn round(questions: usize) -> Round {
+        Round {
+            provider_questions: questions,
+            ..default()
+        }
+    }
+
+    fn format(count: usize, current: usize) -> World {
+        let mut world = World::new();
+        let mut edited = EditedFormat {
+            rounds: (1..=count).map(round).collect(),
+            ..default()
+        };
+        edited.select(current);
+        world.insert_resource(edited);
+        world
+    }
+
+    /// The rounds by how many questions they have, and which one is current
+    fn order(world: &World) -> (Vec<usize>, usize) {
+        let edited = world.resource::<EditedFormat>();
+        (
+            edited
+                .rounds
+                .iter()
+                .map(|round| round.provider_questions)
+                .collect(),
+            edited.current,
+        )
+    }
+
+    #[test]
+    fn speed_rounds_score_double_in_the_first_half_of_the_time() {
+        assert_eq!(Scoring::Speed.points(4.0, Some(10)), 2);
+        assert_eq!(Scoring::Speed.points(5.0, Some(10)), 1);
+        assert_eq!(Scoring::Speed.points(9.0, Some(10)), 1);
+    }
+
+    #[test]
+    fn untimed_rounds_dont_score_by_time() {
+        assert_eq!(Scoring::Speed.points(0.0, None), 1);
+        assert_eq!(Scoring::Speed.points(100.0, None), 1);
+        for secs in [0.0, 15.0] {
+            for limit in [None, Some(10)] {
+                assert_eq!(Scoring::Standard.points(secs, limit), 1);
+                assert_eq!(Scoring::Double.points(secs, limit), 2);
+                assert_eq!(Scoring::Wager.points(secs, limit), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn timing_a_round_gives_it_the_default_time_limit() {
+        let mut round = Round::default();
+        assert_eq!(round.value("timed", None), Some(MenuValue::Bool(false)));
+        assert_eq!(round.value("time_limit", None), None);
+        // Hidden while the round isn't timed
+        round.apply("time_limit", &MenuValue::Number(30.0), None);
+        assert_eq!(*round.time_limit, None);
+
+        round.apply("timed", &MenuValue::Bool(true), None);
+        assert_eq!(*round.time_limit, Some(DEFAULT_TIME_LIMIT));
+        round.apply("time_limit", &MenuValue::Number(30.0), None);
+        assert_eq!(
+            round.value("time_limit", None),
+            Some(MenuValue::Number(30.0))
+        );
+        // Still timed, so the time limit is kept
+        round.apply("timed", &MenuValue::Bool(true), None);
+        assert_eq!(*round.time_limit, Some(30));
+        round.apply("time_limit", &MenuValue::Number(0.0), None);
+        assert_eq!(*round.time_limit, Some(1));
+
+        round.apply("timed", &MenuValue::Bool(false), None);
+        assert_eq!(*round.time_limit, None);
+    }
+
+    #[test]
+    fn scoring_reads_back_what_was_applied() {
+        let mut round = Round::default();
+        for (i, scoring) in Scoring::ALL.into_iter().enumerate() {
+            let selected = MenuValue::Selection(HashSet::from_iter([i]));
+            round.apply("scoring", &selected, None);
+            assert_eq!(round.scoring, scoring);
+            assert_eq!(round.value("scoring", None), Some(selected));
+        }
+        // Out of range selections leave the scoring as it was
+        round.apply(
+            "scoring",
+            &MenuValue::Selection(HashSet::from_iter([Scoring::ALL.len()])),
+            None,
+        );
+        assert_eq!(round.scoring, Scoring::Wager);
+    }
+
+    #[test]
+    fn rounds_move_up_and_down() {
+        let mut world = format(3, 1);
+        world.run_system_once(move_round(-1));
+        assert_eq!(order(&world), (vec![2, 1, 3], 0));
+        world.run_system_once(move_round(1));
+        world.run_system_once(move_round(1));
+        assert_eq!(order(&world), (vec![1, 3, 2], 2));
+    }
+
+    #[test]
+    fn rounds_dont_move_past_either_end() {
+        let mut world = format(3, 0);
+        world.run_system_once(move_round(-1));
+        assert_eq!(order(&world), (vec![1, 2, 3], 0));
+
+        let mut world = format(3, 2);
+        world.run_system_once(move_round(1));
+        assert_eq!(order(&world), (vec![1, 2, 3], 2));
+    }
+
+    #[test]
+    fn deleting_the_last_round_shows_the_one_before() {
+        let mut world = format(3, 2);
+        world.run_system_once(delete_round);
+        assert_eq!(order(&world), (vec![1, 2], 1));
+    }
+
+    #[test]
+    fn deleting_the_only_round_leaves_a_default_one() {
+        let mut world = format(1, 0);
+        world.run_system_once(delete_round);
+        let edited = world.resource::<EditedFormat>();
+        assert_eq!(edited.rounds, vec![Round::default()]);
+        assert_eq!(edited.current, 0);
+    }
+
+    #[test]
+    fn new_rounds_copy_the_current_one_after_it() {
+        let mut world = format(2, 0);
+        world.run_system_once(new_round);
+        assert_eq!(order(&world), (vec![1, 1, 2], 1));
+    }
+
+    #[test]
+    fn rounds_without_questions_are_left_out_of_the_game() {
+        let edited = EditedFormat {
+            rounds: vec![round(0), round(5)],
+            ..default()
+        };
+        assert_eq!(edited.format().rounds, vec![round(5)]);
+    }
+}
@@ -1,75 +1,205 @@
-use bevy::{
-    ecs::system::{SystemId, SystemParam},
-    prelude::*,
-};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use format::{EditedFormat, FormatPlugin, GameFormat};
 
 use crate::{
+    dialog::{ShowToast, Toast},
+    locale::Localization,
     menu::{
-        serde::SliderValue,
+        action::RegisterMenuAction,
+        serde::ToggleValue,
         widgets::{
-            multi_dropdown::MultiDropdownSelected,
+            radio_group::{RadioGroupKeys, RadioGroupSelected},
+            stepper::StepperValue,
             text_input::{TextInputValue, TextInputValueInvalid},
         },
     },
-    trivia::source::TriviaSource,
+    trivia::{
+        question::{Question, DIFFICULTIES},
+        source::provider::Category,
+    },
     GameState,
 };
 
+pub mod format;
+
 pub struct LobbyPlugin;
 
 impl Plugin for LobbyPlugin {
     fn build(&self, app: &mut App) {
-        let system_id = app.register_system(start_host_lobby);
-        app.insert_resource(HostLobby(system_id))
-            .init_resource::<QuestionDifficulty>()
-            .init_resource::<NumQuestions>()
-            .init_resource::<QuestionTypes>()
+        app.add_plugins(FormatPlugin)
+            .register_menu_action("host_lobby", start_host_lobby)
             .init_resource::<GameName>()
-            .register_type::<QuestionDifficulty>()
-            .register_type::<NumQuestions>()
-            .register_type::<QuestionTypes>()
-            .register_type::<GameName>();
+            .init_resource::<GameMode>()
+            .init_resource::<ShuffleAnswers>()
+            .init_resource::<AllowNegativeScores>()
+            .init_resource::<QuestionLanguage>()
+            .init_resource::<StartingLives>()
+            .init_resource::<DifficultyRamp>()
+            .init_resource::<SuddenDeath>()
+            .register_type::<GameName>()
+            .register_type::<GameMode>()
+            .register_type::<ShuffleAnswers>()
+            .register_type::<AllowNegativeScores>()
+            .register_type::<QuestionLanguage>()
+            .register_type::<StartingLives>()
+            .register_type::<DifficultyRamp>()
+            .register_type::<SuddenDeath>();
     }
 }
 
-#[derive(Resource, Deref, DerefMut, Clone, Debug)]
-pub struct HostLobby(pub SystemId);
-
 #[derive(Default, Deref, DerefMut, Clone, Debug, Resource, Reflect)]
 #[reflect(Resource)]
 pub struct GameName(String);
 
+/// The question types a round allows, any of them if empty
+// TODO: Make this a set of Enums
+#[derive(Default, Deref, DerefMut, Clone, Debug, PartialEq, Reflect)]
+pub struct QuestionTypes(Vec<String>);
+
+impl QuestionTypes {
+    const MULTIPLE_CHOICE: &'static str = "Multiple Choice";
+    const TRUE_FALSE: &'static str = "True/False";
+
+    /// Whether questions of the type of `question` are allowed. True/false
+    /// questions are the ones with a single wrong answer.
+    pub fn allows(&self, question: &Question) -> bool {
+        let kind = if question.incorrect_answers.len() == 1 {
+            Self::TRUE_FALSE
+        } else {
+            Self::MULTIPLE_CHOICE
+        };
+        self.is_empty() || self.iter().any(|allowed| allowed == kind)
+    }
+
+    /// `Some(true)` if only multiple choice questions are allowed, `Some(false)`
+    /// if only true/false ones are, `None` if both are
+    pub fn multiple_choice(&self) -> Option<bool> {
+        match self.as_slice() {
+            [kind] => Some(kind == Self::MULTIPLE_CHOICE),
+            _ => None,
+        }
+    }
+}
+
+/// The categories to ask the question provider of a round for questions in,
+/// any of them if empty
+#[derive(Default, Deref, DerefMut, Clone, Debug, PartialEq, Reflect)]
+pub struct QuestionCategories(Vec<Category>);
+
+/// The difficulties a round allows, as listed in the host menu, any of them if empty
+// TODO: Make this a set of Enums
+#[derive(Default, Deref, DerefMut, Clone, Debug, PartialEq, Reflect)]
+pub struct QuestionDifficulty(Vec<String>);
+
+impl QuestionDifficulty {
+    /// How hard `difficulty` is, from 0 for the easiest. Questions without a
+    /// known difficulty count as middling.
+    pub fn rank(difficulty: &str) -> usize {
+        DIFFICULTIES
+            .iter()
+            .position(|known| known.eq_ignore_ascii_case(difficulty))
+            .unwrap_or(DIFFICULTIES.len() / 2)
+    }
+}
+
+/// How the game is played, in the order the host menu lists them
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Resource, Reflect)]
+#[reflect(Resource)]
+pub enum GameMode {
+    /// Everyone answers every question
+    #[default]
+    Classic,
+    /// The first player to buzz in gets to answer
+    Buzzer,
+    /// Players drop out as they answer wrong
+    Elimination,
+}
+
+impl GameMode {
+    pub const ALL: [Self; 3] = [Self::Classic, Self::Buzzer, Self::Elimination];
+
+    /// The key of the mode's option in the host menu, which is also its label
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Classic => "game_mode.classic",
+            Self::Buzzer => "game_mode.buzzer",
+            Self::Elimination => "game_mode.elimination",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.key() == key)
+    }
+}
+
 #[derive(Default, Deref, DerefMut, Clone, Debug, Resource, Reflect)]
 #[reflect(Resource)]
-pub struct NumQuestions(usize);
+pub struct ShuffleAnswers(bool);
 
-// TODO: Make this a set of Enums
+/// Wrong answers lose the points a right one would have won, even below zero
 #[derive(Default, Deref, DerefMut, Clone, Debug, Resource, Reflect)]
 #[reflect(Resource)]
-pub struct QuestionTypes(Vec<String>);
+pub struct AllowNegativeScores(bool);
 
-// TODO: Make this a set of Enums
+/// How many wrong or missed answers knock a player out of an elimination game
+#[derive(Deref, DerefMut, Clone, Copy, Debug, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct StartingLives(u32);
+
+impl Default for StartingLives {
+    fn default() -> Self {
+        Self(3)
+    }
+}
+
+/// The questions of each round go from easiest to hardest, in elimination games
 #[derive(Default, Deref, DerefMut, Clone, Debug, Resource, Reflect)]
 #[reflect(Resource)]
-pub struct QuestionDifficulty(Vec<String>);
+pub struct DifficultyRamp(bool);
+
+/// When a question would knock out everyone still in an elimination game, they
+/// all stay in instead, and questions keep coming past the last one until one
+/// of them outlasts the others
+#[derive(Default, Deref, DerefMut, Clone, Debug, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct SuddenDeath(bool);
+
+/// How many seconds players get to answer each question of a round, if it's timed
+#[derive(Default, Deref, DerefMut, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct TimeLimit(Option<usize>);
+
+/// The locales to ask sources for questions in, most preferred first.
+/// Sources with questions in several languages, like packs, use the first they
+/// have; the others only have English.
+#[derive(Default, Deref, DerefMut, Clone, Debug, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct QuestionLanguage(Vec<String>);
 
 #[derive(SystemParam)]
 struct HostLobbyParams<'w, 's> {
     texts: Query<'w, 's, (&'static TextInputValue, &'static Name)>,
-    dropdowns: Query<
+    toggles: Query<'w, 's, (&'static ToggleValue, &'static Name)>,
+    radio_groups: Query<
         'w,
         's,
         (
-            &'static TriviaSource,
-            &'static MultiDropdownSelected,
+            &'static RadioGroupSelected,
+            &'static RadioGroupKeys,
             &'static Name,
         ),
     >,
-    sliders: Query<'w, 's, (&'static SliderValue, &'static Name)>,
+    steppers: Query<'w, 's, (&'static StepperValue, &'static Name)>,
     game_name: ResMut<'w, GameName>,
-    num_questions: ResMut<'w, NumQuestions>,
-    question_types: ResMut<'w, QuestionTypes>,
-    question_difficulty: ResMut<'w, QuestionDifficulty>,
+    edited_format: Res<'w, EditedFormat>,
+    game_format: ResMut<'w, GameFormat>,
+    game_mode: ResMut<'w, GameMode>,
+    shuffle_answers: ResMut<'w, ShuffleAnswers>,
+    allow_negative_scores: ResMut<'w, AllowNegativeScores>,
+    starting_lives: ResMut<'w, StartingLives>,
+    difficulty_ramp: ResMut<'w, DifficultyRamp>,
+    sudden_death: ResMut<'w, SuddenDeath>,
+    question_language: ResMut<'w, QuestionLanguage>,
+    localization: Res<'w, Localization>,
 }
 
 impl<'w, 's> HostLobbyParams<'w, 's> {
@@ -82,30 +212,38 @@ impl<'w, 's> HostLobbyParams<'w, 's> {
                 .unwrap())
             .clone(),
         );
-        // TODO: Safely parse the values
-        *self.num_questions = NumQuestions(
-            *self
-                .sliders
-                .iter()
-                .find_map(|(v, n)| (n.as_str() == "num_questions").then_some(v.clone()))
-                .unwrap(),
-        );
-        *self.question_types = QuestionTypes({
-            let (source, selected) = self
-                .dropdowns
-                .iter()
-                .find_map(|(s, v, n)| (n.as_str() == "question_types").then_some((s, v)))
-                .unwrap();
-            selected.iter().map(|i| source[*i].clone()).collect()
-        });
-        *self.question_difficulty = QuestionDifficulty({
-            let (source, selected) = self
-                .dropdowns
-                .iter()
-                .find_map(|(s, v, n)| (n.as_str() == "difficulty").then_some((s, v)))
-                .unwrap();
-            selected.iter().map(|i| source[*i].clone()).collect()
-        });
+        // The rounds are kept up to date as they're edited, see `format`
+        *self.game_format = self.edited_format.format();
+        // By key rather than position, so the menu can list the modes in any order
+        *self.game_mode = self
+            .radio_groups
+            .iter()
+            .find_map(|(v, k, n)| (n.as_str() == "game_mode").then(|| k.key(**v)))
+            .flatten()
+            .and_then(GameMode::from_key)
+            .unwrap_or_default();
+        *self.shuffle_answers = ShuffleAnswers(self.toggle("shuffle_answers"));
+        *self.allow_negative_scores = AllowNegativeScores(self.toggle("allow_negative_scores"));
+        // The elimination settings are hidden, and so missing, in other modes
+        let elimination = *self.game_mode == GameMode::Elimination;
+        *self.starting_lives = self
+            .steppers
+            .iter()
+            .find_map(|(v, n)| (n.as_str() == "lives").then_some(**v))
+            .filter(|_| elimination)
+            .map(|lives| StartingLives(lives.max(1) as u32))
+            .unwrap_or_default();
+        *self.difficulty_ramp = DifficultyRamp(elimination && self.toggle("difficulty_ramp"));
+        *self.sudden_death = SuddenDeath(elimination && self.toggle("sudden_death"));
+        // Questions follow the language the host plays in
+        *self.question_language = QuestionLanguage(self.localization.chain().to_vec());
+    }
+
+    fn toggle(&self, name: &str) -> bool {
+        self.toggles
+            .iter()
+            .find_map(|(v, n)| (n.as_str() == name).then_some(**v))
+            .unwrap_or_default()
     }
 }
 
@@ -113,11 +251,16 @@ fn start_host_lobby(
     mut next_game_state: ResMut<NextState<GameState>>,
     mut params: HostLobbyParams,
     invalid_text_inputs: Query<&TextInputValueInvalid>,
+    mut toasts: EventWriter<ShowToast>,
 ) {
     if invalid_text_inputs.iter().len() > 0 {
         return;
     }
 
     params.fetch_form_values();
+    if params.game_format.rounds.is_empty() {
+        toasts.send(ShowToast(Toast::error("toast.no_rounds")));
+        return;
+    }
     next_game_state.set(GameState::Playing);
 }
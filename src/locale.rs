@@ -0,0 +1,306 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_quill::Cx;
+use serde::{Deserialize, Serialize};
+
+use crate::{loading::LocaleAssets, settings::Settings, GameState};
+
+/// The locale every lookup falls back to last, which should have every key
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// The locales that can be picked in the settings menu, in the order it lists them
+pub const SUPPORTED_LOCALES: [&str; 2] = ["en", "es"];
+
+/// Translates menu labels and other text shown to the player, following the
+/// locale picked in [`Settings`]
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Localization>()
+            .add_systems(OnExit(GameState::Loading), load_locales)
+            .add_systems(Update, select_locale.run_if(resource_changed::<Settings>));
+    }
+}
+
+/// The translations for one locale, loaded from `locales/<locale>.locale.ron`
+#[derive(Asset, Deserialize, TypePath, Clone, Debug, Default, PartialEq)]
+pub struct Locale {
+    /// The locale to look in next for keys this one is missing
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    fallback: Option<String>,
+
+    /// The text for each key
+    strings: HashMap<String, String>,
+}
+
+/// Every loaded [`Locale`], and the order to look through them in
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct Localization {
+    locales: HashMap<String, Locale>,
+    chain: Vec<String>,
+}
+
+impl Localization {
+    pub fn new(locales: HashMap<String, Locale>, locale: &str) -> Self {
+        let chain = fallback_chain(&locales, locale);
+        Self { locales, chain }
+    }
+
+    /// The locales looked through for each key, most preferred first
+    pub fn chain(&self) -> &[String] {
+        &self.chain
+    }
+
+    /// Switches to `locale`, keeping its fallbacks
+    pub fn select(&mut self, locale: &str) {
+        self.chain = fallback_chain(&self.locales, locale);
+    }
+
+    /// The text for `key` in the first locale of the chain that has it
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.chain
+            .iter()
+            .filter_map(|locale| self.locales.get(locale))
+            .find_map(|locale| locale.strings.get(key))
+            .map(String::as_str)
+    }
+
+    /// The text for `key`, or the key itself when no locale has it. Menus can
+    /// therefore use plain text where nothing needs translating.
+    pub fn text(&self, key: &str) -> String {
+        self.get(key).unwrap_or(key).to_string()
+    }
+
+    /// Like [`Localization::text`], with each `{name}` replaced by its argument
+    pub fn format(&self, key: &str, args: &[(&str, &dyn ToString)]) -> String {
+        args.iter().fold(self.text(key), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+    }
+}
+
+/// The locales to look through for `locale`: itself and its language without the
+/// region, the same for each fallback it names, and finally [`DEFAULT_LOCALE`]
+pub fn fallback_chain(locales: &HashMap<String, Locale>, locale: &str) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    let push = |chain: &mut Vec<String>, locale: &str| {
+        if !locale.is_empty() && !chain.iter().any(|l| l == locale) {
+            chain.push(locale.to_string());
+        }
+    };
+
+    let mut visited = Vec::new();
+    let mut next = Some(locale.to_string());
+    while let Some(locale) = next.take() {
+        // Stop at the first locale seen twice, in case the fallbacks loop
+        if visited.contains(&locale) {
+            break;
+        }
+        push(&mut chain, &locale);
+        if let Some((language, _region)) = locale.split_once('-') {
+            push(&mut chain, language);
+        }
+        next = locales.get(&locale).and_then(|l| l.fallback.clone());
+        visited.push(locale);
+    }
+    push(&mut chain, DEFAULT_LOCALE);
+    chain
+}
+
+/// The text for `key` in the current locale, re-rendering when the locale changes
+pub fn use_text(cx: &mut Cx, key: &str) -> String {
+    cx.use_resource::<Localization>().text(key)
+}
+
+/// Text in one or more languages, as found in question packs
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum LocalizedText {
+    /// The same text in every language
+    Plain(String),
+    /// The text in each language, by locale
+    Localized(HashMap<String, String>),
+}
+
+impl Default for LocalizedText {
+    fn default() -> Self {
+        Self::Plain(String::new())
+    }
+}
+
+impl LocalizedText {
+    /// The text in the first locale of `chain` that has it, if any
+    pub fn get(&self, chain: &[String]) -> Option<&str> {
+        match self {
+            Self::Plain(text) => Some(text),
+            Self::Localized(texts) => chain
+                .iter()
+                .find_map(|locale| texts.get(locale))
+                .map(String::as_str),
+        }
+    }
+
+    /// The text in every language it's given in
+    pub fn texts(&self) -> Vec<&str> {
+        match self {
+            Self::Plain(text) => vec![text],
+            Self::Localized(texts) => texts.values().map(String::as_str).collect(),
+        }
+    }
+
+    /// Whether there is text meant for `locale`
+    pub fn has(&self, locale: &str) -> bool {
+        match self {
+            Self::Plain(_) => true,
+            Self::Localized(texts) => texts.contains_key(locale),
+        }
+    }
+
+    /// Replaces the text meant for `locale`, keeping the other languages.
+    /// Text that's the same in every language stays that way.
+    pub fn set(&mut self, locale: &str, text: String) {
+        match self {
+            Self::Plain(plain) => *plain = text,
+            Self::Localized(texts) => {
+                texts.insert(locale.to_string(), text);
+            }
+        }
+    }
+}
+
+impl From<String> for LocalizedText {
+    fn from(text: String) -> Self {
+        Self::Plain(text)
+    }
+}
+
+fn load_locales(
+    mut commands: Commands,
+    assets: Res<LocaleAssets>,
+    locales: Res<Assets<Locale>>,
+    settings: Res<Settings>,
+) {
+    let locales = assets
+        .locales
+        .iter()
+        .filter_map(|(path, handle)| {
+            let name = path.rsplit('/').next()?.strip_suffix(".locale.ron")?;
+            Some((name.to_string(), locales.get(handle)?.clone()))
+        })
+        .collect();
+    commands.insert_resource(Localization::new(locales, &settings.locale));
+}
+
+fn select_locale(settings: Res<Settings>, mut localization: ResMut<Localization>) {
+    let mut next = localization.clone();
+    next.select(&settings.locale);
+    localization.set_if_neq(next);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(fallback: Option<&str>, strings: &[(&str, &str)]) -> Locale {
+        Locale {
+            fallback: fallback.map(str::to_string),
+            strings: strings
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    fn localization(locale_name: &str) -> Localization {
+        let locales = HashMap::from_iter([
+            (
+                "en".to_string(),
+                locale(None, &[("hello", "Hello"), ("quit", "Quit")]),
+            ),
+            ("es".to_string(), locale(None, &[("hello", "Hola")])),
+            ("es-MX".to_string(), locale(None, &[("hello", "Qué onda")])),
+            ("gl".to_string(), locale(Some("es"), &[])),
+            ("a".to_string(), locale(Some("b"), &[])),
+            ("b".to_string(), locale(Some("a"), &[])),
+        ]);
+        Localization::new(locales, locale_name)
+    }
+
+    #[test]
+    fn finds_keys_in_the_selected_locale() {
+        assert_eq!(localization("es").text("hello"), "Hola");
+        assert_eq!(localization("es-MX").text("hello"), "Qué onda");
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_the_default_locale() {
+        assert_eq!(localization("es").text("quit"), "Quit");
+    }
+
+    #[test]
+    fn regions_fall_back_to_their_language() {
+        assert_eq!(localization("es-AR").text("hello"), "Hola");
+        assert_eq!(
+            localization("es-MX").chain(),
+            ["es-MX", "es", "en"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn declared_fallbacks_come_before_the_default() {
+        assert_eq!(localization("gl").text("hello"), "Hola");
+        assert_eq!(localization("gl").text("quit"), "Quit");
+    }
+
+    #[test]
+    fn unknown_locales_use_the_default() {
+        assert_eq!(localization("xx").text("hello"), "Hello");
+        assert_eq!(localization("").chain(), ["en"].map(String::from));
+    }
+
+    #[test]
+    fn keys_missing_everywhere_are_shown_as_is() {
+        let localization = localization("es");
+        assert_eq!(localization.get("Play Again"), None);
+        assert_eq!(localization.text("Play Again"), "Play Again");
+    }
+
+    #[test]
+    fn looping_fallbacks_end() {
+        assert_eq!(
+            localization("a").chain(),
+            ["a", "b", "en"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn formats_arguments() {
+        let mut localization = localization("en");
+        localization
+            .locales
+            .get_mut("en")
+            .unwrap()
+            .strings
+            .insert("count".into(), "{n} of {total}".into());
+        assert_eq!(
+            localization.format("count", &[("n", &3), ("total", &10)]),
+            "3 of 10"
+        );
+    }
+
+    #[test]
+    fn localized_text_falls_back_along_the_chain() {
+        let text = LocalizedText::Localized(HashMap::from_iter([
+            ("en".to_string(), "Capital of France?".to_string()),
+            ("es".to_string(), "¿Capital de Francia?".to_string()),
+        ]));
+        let chain = |locale| localization(locale).chain().to_vec();
+        assert_eq!(text.get(&chain("es-MX")), Some("¿Capital de Francia?"));
+        assert_eq!(text.get(&chain("de")), Some("Capital of France?"));
+        assert_eq!(text.get(&["de".to_string()]), None);
+        assert_eq!(
+            LocalizedText::Plain("Paris".into()).get(&chain("es")),
+            Some("Paris")
+        );
+    }
+}
@@ -7,7 +7,7 @@ use bevy::window::PrimaryWindow;
 use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
 use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
-use bevy_trivia::GamePlugin;
+use bevy_trivia::{GamePlugin, UserAssetsPlugin};
 use std::io::Cursor;
 use winit::window::Icon;
 
@@ -16,6 +16,7 @@ fn main() {
         .insert_resource(Msaa::Off)
         .insert_resource(ClearColor(Color::linear_rgb(0.4, 0.4, 0.4)))
         .add_plugins((
+            UserAssetsPlugin,
             EmbeddedAssetPlugin {
                 mode: PluginMode::ReplaceAndFallback {
                     path: "assets".into(),
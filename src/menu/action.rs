@@ -0,0 +1,131 @@
+use bevy::{ecs::system::SystemId, prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{
+    dialog::{Dialog, ShowDialog, ShowToast, Toast},
+    menu::{
+        navigation::{focus_position, MenuRoot, PendingMenuFocus},
+        utils::open_url,
+        MenuStack, MenuStackEntry, WhichMenu,
+    },
+};
+
+/// The action to perform when a button is clicked
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, TypePath)]
+pub enum MenuAction {
+    /// Quit the game
+    Quit,
+
+    /// Go back to the previous menu in the stack
+    Back,
+
+    #[cfg(target_arch = "wasm32")]
+    /// Reload the game, web only
+    Reload,
+
+    /// Switch to another menu, remembering this one for [`MenuAction::Back`]
+    Goto(WhichMenu),
+
+    /// Open a url in the browser
+    OpenUrl(String),
+
+    /// Run the one-shot system registered under this name with
+    /// [`RegisterMenuAction::register_menu_action`]
+    Run(String),
+
+    /// Show a dialog with a message
+    Alert { title: String, message: String },
+
+    /// Show a toast notification
+    Toast(String),
+
+    /// Ask the player before performing another action
+    Confirm {
+        /// The question to ask
+        prompt: String,
+        /// The action to perform if the player agrees
+        then: Box<MenuAction>,
+    },
+}
+
+/// One-shot systems that menu buttons can run with [`MenuAction::Run`], by name
+#[derive(Resource, Debug, Default, Deref)]
+pub struct MenuActions(HashMap<String, SystemId>);
+
+pub trait RegisterMenuAction {
+    /// Registers `system` to run when a button with `MenuAction::Run(name)` is clicked
+    fn register_menu_action<M>(
+        &mut self,
+        name: impl Into<String>,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl RegisterMenuAction for App {
+    fn register_menu_action<M>(
+        &mut self,
+        name: impl Into<String>,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self {
+        let id = self.register_system(system);
+        self.world_mut()
+            .get_resource_or_insert_with(MenuActions::default)
+            .0
+            .insert(name.into(), id);
+        self
+    }
+}
+
+impl MenuAction {
+    /// Performs the action
+    pub fn run(&self, world: &mut World) {
+        debug!("Menu action: {:?}", self);
+        match self {
+            Self::Quit => {
+                world.send_event(AppExit::Success);
+            }
+            Self::Back => {
+                let entry = world.resource_mut::<MenuStack>().pop().unwrap_or_default();
+                world.resource_mut::<PendingMenuFocus>().0 = entry.focus;
+                world.resource_mut::<NextState<WhichMenu>>().set(entry.menu);
+            }
+            #[cfg(target_arch = "wasm32")]
+            Self::Reload => {
+                let location = gloo_utils::window().location();
+                location.reload().unwrap();
+            }
+            Self::Goto(menu) => {
+                let current = *world.resource::<State<WhichMenu>>().get();
+                let mut roots = world.query_filtered::<Entity, With<MenuRoot>>();
+                let focus = roots
+                    .iter(world)
+                    .next()
+                    .and_then(|root| focus_position(world, root));
+                world.resource_mut::<MenuStack>().push(MenuStackEntry {
+                    menu: current,
+                    focus,
+                });
+                world.resource_mut::<NextState<WhichMenu>>().set(*menu);
+            }
+            Self::OpenUrl(url) => open_url(url),
+            Self::Run(name) => {
+                let Some(id) = world.resource::<MenuActions>().get(name).copied() else {
+                    warn!("No menu action registered as {name:?}");
+                    return;
+                };
+                if let Err(error) = world.run_system(id) {
+                    warn!("Menu action {name:?} failed: {error}");
+                }
+            }
+            Self::Alert { title, message } => {
+                world.send_event(ShowDialog(Dialog::alert(title, message)));
+            }
+            Self::Toast(message) => {
+                world.send_event(ShowToast(Toast::info(message)));
+            }
+            Self::Confirm { prompt, then } => {
+                world.send_event(ShowDialog(Dialog::confirm(prompt, (**then).clone())));
+            }
+        }
+    }
+}
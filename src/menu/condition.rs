@@ -0,0 +1,338 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_quill::Cx;
+use serde::Deserialize;
+
+use crate::menu::{
+    serde::{SliderValue, ToggleValue},
+    widgets::{
+        dropdown::DropdownSelected,
+        multi_dropdown::MultiDropdownSelected,
+        radio_group::{RadioGroupKeys, RadioGroupSelected},
+        stepper::StepperValue,
+        text_input::{TextInputValue, TextInputValueInvalid},
+    },
+};
+
+/// The current value of a named menu widget, as seen by [`Condition`]s
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuValue {
+    /// A toggle
+    Bool(bool),
+    /// A slider or stepper
+    Number(f64),
+    /// A text input, and whether it passes its validators
+    Text { value: String, valid: bool },
+    /// A dropdown, multi-dropdown or radio group
+    Selection(HashSet<usize>),
+}
+
+/// The values of every named widget in the current menu, by name.
+///
+/// Kept in a resource so that views can react to other widgets changing.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Deref)]
+pub struct MenuValues {
+    #[deref]
+    values: HashMap<String, MenuValue>,
+    /// The key of the chosen option of each radio group with keys, see
+    /// [`RadioGroupKeys`]
+    keys: HashMap<String, String>,
+}
+
+impl MenuValues {
+    /// The key of the option chosen in the named radio group
+    pub fn selected_key(&self, name: &str) -> Option<&str> {
+        self.keys.get(name).map(String::as_str)
+    }
+}
+
+/// The platform the game is running on
+#[derive(Deserialize, TypePath, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    Desktop,
+    Mobile,
+    Web,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            Self::Web
+        } else if cfg!(any(target_os = "android", target_os = "ios")) {
+            Self::Mobile
+        } else {
+            Self::Desktop
+        }
+    }
+}
+
+/// A condition on the values of other widgets in the menu, or on the app,
+/// for the `visible_if` and `enabled_if` fields of menu items
+#[derive(Deserialize, TypePath, Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// The named toggle is on
+    On(String),
+
+    /// At least one option of the named dropdown, multi-dropdown or radio group is selected
+    AnySelected(String),
+
+    /// This option of the named dropdown, multi-dropdown or radio group is selected
+    Selected(String, usize),
+
+    /// The option of the named radio group with this key is selected. Unlike
+    /// [`Condition::Selected`], this keeps holding when options are reordered.
+    SelectedKey(String, String),
+
+    /// The named slider or stepper is at least this value
+    AtLeast(String, f64),
+
+    /// The named slider or stepper is at most this value
+    AtMost(String, f64),
+
+    /// The named text input has something other than whitespace in it
+    Filled(String),
+
+    /// The named text input passes all its validators
+    Valid(String),
+
+    /// The game is running on this platform
+    Platform(Platform),
+
+    /// The condition does not hold
+    Not(Box<Condition>),
+
+    /// All of the conditions hold
+    All(Vec<Condition>),
+
+    /// Any of the conditions holds
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    /// Whether the condition holds. Widgets that are missing from `values`,
+    /// because they are hidden or not in this menu, fail every check on them.
+    pub fn evaluate(&self, values: &MenuValues) -> bool {
+        match self {
+            Self::On(name) => matches!(values.get(name), Some(MenuValue::Bool(true))),
+            Self::AnySelected(name) => {
+                matches!(values.get(name), Some(MenuValue::Selection(s)) if !s.is_empty())
+            }
+            Self::Selected(name, index) => {
+                matches!(values.get(name), Some(MenuValue::Selection(s)) if s.contains(index))
+            }
+            Self::SelectedKey(name, key) => values.selected_key(name) == Some(key.as_str()),
+            Self::AtLeast(name, min) => {
+                matches!(values.get(name), Some(MenuValue::Number(n)) if n >= min)
+            }
+            Self::AtMost(name, max) => {
+                matches!(values.get(name), Some(MenuValue::Number(n)) if n <= max)
+            }
+            Self::Filled(name) => {
+                matches!(values.get(name), Some(MenuValue::Text { value, .. }) if !value.trim().is_empty())
+            }
+            Self::Valid(name) => {
+                matches!(values.get(name), Some(MenuValue::Text { valid: true, .. }))
+            }
+            Self::Platform(platform) => *platform == Platform::current(),
+            Self::Not(condition) => !condition.evaluate(values),
+            Self::All(conditions) => conditions.iter().all(|c| c.evaluate(values)),
+            Self::Any(conditions) => conditions.iter().any(|c| c.evaluate(values)),
+        }
+    }
+}
+
+/// Evaluates an optional condition inside a view, re-running the view when the
+/// menu values change. A missing condition always holds.
+pub fn use_condition(cx: &mut Cx, condition: &Option<Condition>) -> bool {
+    match condition {
+        Some(condition) => condition.evaluate(cx.use_resource::<MenuValues>()),
+        None => true,
+    }
+}
+
+/// Mirrors the values of the named widgets into [`MenuValues`]
+pub(crate) fn sync_menu_values(
+    mut values: ResMut<MenuValues>,
+    toggles: Query<(&Name, &ToggleValue)>,
+    sliders: Query<(&Name, &SliderValue)>,
+    steppers: Query<(&Name, &StepperValue)>,
+    texts: Query<(&Name, &TextInputValue, Has<TextInputValueInvalid>)>,
+    dropdowns: Query<(&Name, &DropdownSelected)>,
+    multi_dropdowns: Query<(&Name, &MultiDropdownSelected)>,
+    radio_groups: Query<(&Name, &RadioGroupSelected, Option<&RadioGroupKeys>)>,
+) {
+    let mut next = HashMap::new();
+    let mut keys = HashMap::new();
+    let mut add = |name: &Name, value| {
+        if !name.is_empty() {
+            next.insert(name.to_string(), value);
+        }
+    };
+    for (name, value) in toggles.iter() {
+        add(name, MenuValue::Bool(**value));
+    }
+    for (name, value) in sliders.iter() {
+        add(name, MenuValue::Number(**value as f64));
+    }
+    for (name, value) in steppers.iter() {
+        add(name, MenuValue::Number(**value as f64));
+    }
+    for (name, value, invalid) in texts.iter() {
+        add(
+            name,
+            MenuValue::Text {
+                value: (**value).clone(),
+                valid: !invalid,
+            },
+        );
+    }
+    for (name, value) in dropdowns.iter() {
+        add(name, MenuValue::Selection(value.iter().copied().collect()));
+    }
+    for (name, value) in multi_dropdowns.iter() {
+        add(name, MenuValue::Selection((**value).clone()));
+    }
+    for (name, value, option_keys) in radio_groups.iter() {
+        add(name, MenuValue::Selection(HashSet::from_iter([**value])));
+        if let Some(key) = option_keys.and_then(|k| k.key(**value)) {
+            keys.insert(name.to_string(), key.to_string());
+        }
+    }
+    values.set_if_neq(MenuValues { values: next, keys });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> MenuValues {
+        let values = [
+            ("toggle_on", MenuValue::Bool(true)),
+            ("toggle_off", MenuValue::Bool(false)),
+            ("stepper", MenuValue::Number(5.0)),
+            ("none_selected", MenuValue::Selection(HashSet::new())),
+            ("selected", MenuValue::Selection(HashSet::from_iter([1, 3]))),
+            (
+                "filled",
+                MenuValue::Text {
+                    value: "Name".to_string(),
+                    valid: true,
+                },
+            ),
+            (
+                "blank",
+                MenuValue::Text {
+                    value: "  ".to_string(),
+                    valid: false,
+                },
+            ),
+        ];
+        MenuValues {
+            values: values
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+            keys: HashMap::from_iter([("radio".to_string(), "easy".to_string())]),
+        }
+    }
+
+    fn on(name: &str) -> Condition {
+        Condition::On(name.to_string())
+    }
+
+    #[test]
+    fn toggles_are_on_or_off() {
+        let values = values();
+        assert!(on("toggle_on").evaluate(&values));
+        assert!(!on("toggle_off").evaluate(&values));
+        // Only toggles are on
+        assert!(!on("stepper").evaluate(&values));
+    }
+
+    #[test]
+    fn selections_hold_by_option() {
+        let values = values();
+        let any = |name: &str| Condition::AnySelected(name.to_string());
+        assert!(any("selected").evaluate(&values));
+        assert!(!any("none_selected").evaluate(&values));
+        let selected = |index| Condition::Selected("selected".to_string(), index);
+        assert!(selected(1).evaluate(&values));
+        assert!(selected(3).evaluate(&values));
+        assert!(!selected(2).evaluate(&values));
+    }
+
+    #[test]
+    fn keys_are_matched_exactly() {
+        let values = values();
+        let key = |name: &str, key: &str| Condition::SelectedKey(name.to_string(), key.to_string());
+        assert!(key("radio", "easy").evaluate(&values));
+        assert!(!key("radio", "hard").evaluate(&values));
+        assert!(!key("selected", "easy").evaluate(&values));
+    }
+
+    #[test]
+    fn numbers_are_compared_inclusively() {
+        let values = values();
+        let at_least = |min| Condition::AtLeast("stepper".to_string(), min);
+        let at_most = |max| Condition::AtMost("stepper".to_string(), max);
+        assert!(at_least(5.0).evaluate(&values));
+        assert!(!at_least(5.5).evaluate(&values));
+        assert!(at_most(5.0).evaluate(&values));
+        assert!(!at_most(4.5).evaluate(&values));
+    }
+
+    #[test]
+    fn text_is_filled_and_valid_apart() {
+        let values = values();
+        assert!(Condition::Filled("filled".to_string()).evaluate(&values));
+        assert!(!Condition::Filled("blank".to_string()).evaluate(&values));
+        assert!(Condition::Valid("filled".to_string()).evaluate(&values));
+        assert!(!Condition::Valid("blank".to_string()).evaluate(&values));
+    }
+
+    #[test]
+    fn only_the_current_platform_holds() {
+        let values = values();
+        for platform in [Platform::Desktop, Platform::Mobile, Platform::Web] {
+            assert_eq!(
+                Condition::Platform(platform).evaluate(&values),
+                platform == Platform::current()
+            );
+        }
+    }
+
+    #[test]
+    fn conditions_combine() {
+        let values = values();
+        assert!(Condition::Not(Box::new(on("toggle_off"))).evaluate(&values));
+        assert!(Condition::All(vec![on("toggle_on"), on("toggle_on")]).evaluate(&values));
+        assert!(!Condition::All(vec![on("toggle_on"), on("toggle_off")]).evaluate(&values));
+        assert!(Condition::Any(vec![on("toggle_off"), on("toggle_on")]).evaluate(&values));
+        assert!(!Condition::Any(vec![on("toggle_off")]).evaluate(&values));
+        // Nothing to fail, or nothing to hold
+        assert!(Condition::All(Vec::new()).evaluate(&values));
+        assert!(!Condition::Any(Vec::new()).evaluate(&values));
+    }
+
+    #[test]
+    fn missing_widgets_fail_every_check() {
+        let values = values();
+        let missing = || "missing".to_string();
+        for condition in [
+            Condition::On(missing()),
+            Condition::AnySelected(missing()),
+            Condition::Selected(missing(), 0),
+            Condition::SelectedKey(missing(), "easy".to_string()),
+            Condition::AtLeast(missing(), f64::MIN),
+            Condition::AtMost(missing(), f64::MAX),
+            Condition::Filled(missing()),
+            Condition::Valid(missing()),
+        ] {
+            assert!(!condition.evaluate(&values), "{condition:?}");
+        }
+        // So negating a check on a hidden widget holds
+        assert!(Condition::Not(Box::new(on("missing"))).evaluate(&values));
+    }
+}
@@ -1,13 +1,29 @@
 use ::serde::{Deserialize, Serialize};
-use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use bevy::{ecs::system::RunSystemOnce, input::InputSystem, prelude::*};
 use bevy_mod_stylebuilder::{StyleBuilder, StyleBuilderFont, StyleBuilderLayout};
 use bevy_quill::View;
-use bevy_quill_obsidian::colors;
 use serde::Menu;
 
-use crate::{loading::MenuAssets, trivia::source::SourcePlugin, GameState};
-
+use crate::{
+    layout::{Breakpoint, ScreenLayout},
+    loading::MenuAssets,
+    theme::Theme,
+    trivia::source::SourcePlugin,
+    GameState,
+};
+use action::MenuActions;
+use condition::{sync_menu_values, MenuValues};
+use navigation::{gamepad_menu_input, restore_menu_focus, PendingMenuFocus};
+use transition::{
+    animate_menus, cleanup_menu_fade, start_menu_enter, start_menu_exit, MenuTransitionState,
+};
+use widgets::WidgetsPlugin;
+
+pub mod action;
+pub mod condition;
+pub mod navigation;
 pub mod serde;
+pub mod transition;
 pub mod utils;
 pub mod widgets;
 
@@ -17,8 +33,12 @@ pub struct MenuPlugin;
 /// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(SourcePlugin)
+        app.add_plugins((SourcePlugin, WidgetsPlugin))
             .init_resource::<MenuStack>()
+            .init_resource::<MenuValues>()
+            .init_resource::<MenuActions>()
+            .init_resource::<PendingMenuFocus>()
+            .init_resource::<MenuTransitionState>()
             .add_sub_state::<WhichMenu>()
             .add_systems(
                 StateTransition,
@@ -26,12 +46,37 @@ impl Plugin for MenuPlugin {
                     .pipe(menu_transition)
                     .run_if(in_state(GameState::Menu)),
             )
-            .add_systems(OnExit(GameState::Menu), cleanup_menu);
+            // Only in menus, so in game the gamepad isn't also read as keys
+            .add_systems(
+                PreUpdate,
+                gamepad_menu_input
+                    .after(InputSystem)
+                    .run_if(in_state(GameState::Menu)),
+            )
+            .add_systems(
+                Update,
+                (
+                    sync_menu_values,
+                    restore_menu_focus,
+                    (start_menu_enter, animate_menus).chain(),
+                )
+                    .run_if(in_state(GameState::Menu)),
+            )
+            .add_systems(OnExit(GameState::Menu), (cleanup_menu, cleanup_menu_fade));
     }
 }
 
+/// The menus to go back to, most recent last
 #[derive(Default, Deref, DerefMut, Clone, Debug, Resource)]
-pub struct MenuStack(Vec<WhichMenu>);
+pub struct MenuStack(Vec<MenuStackEntry>);
+
+/// A menu to go back to, and where the focus was when it was left
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MenuStackEntry {
+    pub menu: WhichMenu,
+    /// The position of the focused item among the menu's focusable items
+    pub focus: Option<usize>,
+}
 
 #[derive(
     Clone, Copy, Debug, PartialEq, Eq, Hash, SubStates, Default, TypePath, Serialize, Deserialize,
@@ -44,6 +89,7 @@ pub enum WhichMenu {
     HostGame,
     Settings,
     Credits,
+    PackEditor,
 }
 
 impl WhichMenu {
@@ -55,6 +101,7 @@ impl WhichMenu {
                 Self::HostGame => &assets.host,
                 Self::Settings => &assets.settings,
                 Self::Credits => &assets.credits,
+                Self::PackEditor => &assets.editor,
             })
             .expect("main menu")
             .clone()
@@ -64,31 +111,70 @@ impl WhichMenu {
 #[derive(Component)]
 struct MenuMarker;
 
-fn menu_style(ss: &mut StyleBuilder) {
+// The styles taking a [`Theme`] or [`ScreenLayout`] are applied with `style_dyn`,
+// so they follow theme switches and window resizes
+
+pub(crate) fn menu_style((theme, layout): (Theme, ScreenLayout), ss: &mut StyleBuilder) {
+    // Phones can't spare the full padding
+    let padding = match layout.breakpoint {
+        Breakpoint::Compact => theme.spacing.gap,
+        _ => theme.spacing.padding,
+    };
+    let insets = layout.insets;
     ss.display(Display::Flex)
         .flex_direction(FlexDirection::Column)
         .position(PositionType::Absolute)
-        .padding(50)
+        .padding(UiRect {
+            left: Val::Px(padding + insets.left),
+            right: Val::Px(padding + insets.right),
+            top: Val::Px(padding + insets.top),
+            bottom: Val::Px(padding + insets.bottom),
+        })
         .left(0)
         .right(0)
         .bottom(0)
         .top(0)
-        .row_gap(10)
         .align_items(AlignItems::Center);
 }
 
-fn menu_row_style(ss: &mut StyleBuilder) {
+/// The column holding the title and items, as wide as the layout allows
+pub(crate) fn menu_content_style((theme, layout): (Theme, ScreenLayout), ss: &mut StyleBuilder) {
     ss.display(Display::Flex)
-        .flex_direction(FlexDirection::Row)
-        .width(Val::Percent(75.0))
+        .flex_direction(FlexDirection::Column)
+        .width(Val::Percent(layout.breakpoint.content_width()))
+        .flex_grow(1.0)
+        .row_gap(theme.spacing.gap)
+        .align_items(AlignItems::Center);
+}
+
+pub(crate) fn menu_title_style((theme, layout): (Theme, ScreenLayout), ss: &mut StyleBuilder) {
+    let size = match layout.breakpoint {
+        Breakpoint::Compact => theme.font_sizes.title * 0.75,
+        _ => theme.font_sizes.title,
+    };
+    ss.min_height(Val::Px(size * 1.5))
+        .font_size(size)
+        .color(theme.palette.primary);
+}
+
+/// Rows stack their items when there's only room for one across
+fn menu_row_style((theme, layout): (Theme, ScreenLayout), ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(if layout.is_narrow() {
+            FlexDirection::Column
+        } else {
+            FlexDirection::Row
+        })
+        .width(Val::Percent(100.0))
         .align_items(AlignItems::Center)
-        .column_gap(10);
+        .column_gap(theme.spacing.gap)
+        .row_gap(theme.spacing.gap);
 }
 
 fn menu_button_style(ss: &mut StyleBuilder) {
     ss.display(Display::Flex)
         .flex_direction(FlexDirection::Row)
-        .width(Val::Percent(75.0))
+        .width(Val::Percent(100.0))
         .align_items(AlignItems::Center)
         .column_gap(10);
 }
@@ -101,13 +187,19 @@ fn menu_text_input_style(ss: &mut StyleBuilder) {
         .column_gap(10);
 }
 
-fn menu_labeled_style(ss: &mut StyleBuilder) {
+fn menu_labeled_style(theme: Theme, ss: &mut StyleBuilder) {
     ss.display(Display::Flex)
         .flex_direction(FlexDirection::Column)
-        .width(Val::Percent(75.0))
+        .width(Val::Percent(100.0))
         .align_items(AlignItems::Start)
-        .row_gap(2.5)
-        .color(colors::FOREGROUND);
+        .row_gap(theme.spacing.label_gap)
+        .font_size(theme.font_sizes.body)
+        .color(theme.palette.foreground);
+}
+
+fn menu_error_style(theme: Theme, ss: &mut StyleBuilder) {
+    ss.font_size(theme.font_sizes.small)
+        .color(theme.palette.destructive);
 }
 
 fn setup_menu(
@@ -128,6 +220,7 @@ fn cleanup_menu(mut commands: Commands, menu: Query<Entity, With<MenuMarker>>) {
 
 /// This system is responsible for transitioning between the different menu states
 /// It is only active during the State `GameState::Menu`
+/// The old menu is kept until its exit animation is done, see [`transition`]
 fn menu_transition(transition: In<Option<StateTransitionEvent<WhichMenu>>>, world: &mut World) {
     let Some(transition) = transition.0 else {
         return;
@@ -137,6 +230,7 @@ fn menu_transition(transition: In<Option<StateTransitionEvent<WhichMenu>>>, worl
         return;
     }
 
-    world.run_system_once(cleanup_menu);
-    world.run_system_once(setup_menu);
+    // Going back already asked for the focus it remembered, anything else starts at the top
+    world.resource_mut::<PendingMenuFocus>().get_or_insert(0);
+    start_menu_exit(world);
 }
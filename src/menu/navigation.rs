@@ -0,0 +1,250 @@
+use bevy::{
+    a11y::Focus,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_quill_obsidian::{
+    controls::Disabled,
+    focus::{FocusVisible, TabIndex},
+};
+
+use crate::menu::transition::MenuExiting;
+
+/// How long a direction has to be held on the gamepad before it starts repeating
+const REPEAT_DELAY_SECS: f32 = 0.4;
+
+/// How often a held gamepad direction repeats
+const REPEAT_INTERVAL_SECS: f32 = 0.12;
+
+/// How far the left stick has to be pushed to count as a direction
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// Marks the element of the menu currently on screen
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct MenuRoot;
+
+/// The position, in [`focusables`] order, to focus once the menu being built has
+/// its items. Set when switching menus, so going back restores the old focus.
+#[derive(Resource, Debug, Default, Clone, Copy, Deref, DerefMut, PartialEq, Eq)]
+pub struct PendingMenuFocus(pub Option<usize>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn from_key(key_code: KeyCode) -> Option<Self> {
+        match key_code {
+            KeyCode::ArrowUp => Some(Self::Up),
+            KeyCode::ArrowDown => Some(Self::Down),
+            KeyCode::ArrowLeft => Some(Self::Left),
+            KeyCode::ArrowRight => Some(Self::Right),
+            _ => None,
+        }
+    }
+}
+
+/// The enabled, focusable descendants of `root`, in the order they appear
+pub fn focusables(world: &World, root: Entity) -> Vec<Entity> {
+    let mut found = Vec::new();
+    let mut stack = vec![root];
+    while let Some(entity) = stack.pop() {
+        if entity != root
+            && world.get::<TabIndex>(entity).is_some_and(|t| t.0 >= 0)
+            && world.get::<Disabled>(entity).is_none()
+        {
+            found.push(entity);
+        }
+        if let Some(children) = world.get::<Children>(entity) {
+            stack.extend(children.iter().rev());
+        }
+    }
+    found
+}
+
+/// Where the focused descendant of `root` is in [`focusables`] order
+pub fn focus_position(world: &World, root: Entity) -> Option<usize> {
+    let focused = world.resource::<Focus>().0?;
+    focusables(world, root).iter().position(|e| *e == focused)
+}
+
+/// Moves focus to the nearest focusable descendant of `root` in `direction`.
+///
+/// Up and Down wrap around in [`focusables`] order when nothing is in the way, so
+/// a column of items can always be walked. Returns whether the focus moved.
+pub fn navigate(world: &mut World, root: Entity, direction: Direction) -> bool {
+    let candidates = focusables(world, root);
+    if candidates.is_empty() {
+        return false;
+    }
+    let rect = |entity: Entity| -> Option<Rect> {
+        let node = world.get::<Node>(entity)?;
+        let transform = world.get::<GlobalTransform>(entity)?;
+        Some(node.logical_rect(transform))
+    };
+
+    let focused = world.resource::<Focus>().0;
+    let next = match focused.and_then(|f| candidates.iter().position(|e| *e == f)) {
+        None => Some(candidates[0]),
+        Some(current) => {
+            let from = rect(candidates[current]).unwrap_or_default().center();
+            let nearest = candidates
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != current)
+                .filter_map(|(_, entity)| {
+                    let delta = rect(*entity)?.center() - from;
+                    // UI coordinates grow downwards
+                    let (along, across) = match direction {
+                        Direction::Up => (-delta.y, delta.x.abs()),
+                        Direction::Down => (delta.y, delta.x.abs()),
+                        Direction::Left => (-delta.x, delta.y.abs()),
+                        Direction::Right => (delta.x, delta.y.abs()),
+                    };
+                    (along > 1.0).then_some((*entity, along + across * 2.0))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(entity, _)| entity);
+            let len = candidates.len();
+            nearest.or(match direction {
+                Direction::Up => Some(candidates[(current + len - 1) % len]),
+                Direction::Down => Some(candidates[(current + 1) % len]),
+                Direction::Left | Direction::Right => None,
+            })
+        }
+    };
+
+    match next {
+        Some(next) if Some(next) != focused => {
+            world.resource_mut::<Focus>().0 = Some(next);
+            world.resource_mut::<FocusVisible>().0 = true;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Focuses the item asked for by [`PendingMenuFocus`] once the menu has been built
+pub(crate) fn restore_menu_focus(world: &mut World) {
+    let Some(position) = world.resource::<PendingMenuFocus>().0 else {
+        return;
+    };
+    let mut roots = world.query_filtered::<Entity, (With<MenuRoot>, Without<MenuExiting>)>();
+    let Some(root) = roots.iter(world).next() else {
+        return;
+    };
+    let candidates = focusables(world, root);
+    let Some(last) = candidates.len().checked_sub(1) else {
+        return;
+    };
+    world.resource_mut::<Focus>().0 = Some(candidates[position.min(last)]);
+    world.resource_mut::<PendingMenuFocus>().0 = None;
+}
+
+/// The gamepad button or direction held down, and when it repeats
+#[derive(Default)]
+pub(crate) struct GamepadRepeat(Option<(KeyCode, Timer)>);
+
+/// Turns the D-pad, left stick, A and B into arrow keys, Enter and Escape, so
+/// every widget that handles the keyboard handles the gamepad too
+pub(crate) fn gamepad_menu_input(
+    time: Res<Time<Real>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+    mut repeat: Local<GamepadRepeat>,
+    mut keys: EventWriter<KeyboardInput>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let mut send = |key_code: KeyCode| {
+        let logical_key = match key_code {
+            KeyCode::ArrowUp => Key::ArrowUp,
+            KeyCode::ArrowDown => Key::ArrowDown,
+            KeyCode::ArrowLeft => Key::ArrowLeft,
+            KeyCode::ArrowRight => Key::ArrowRight,
+            KeyCode::Enter => Key::Enter,
+            _ => Key::Escape,
+        };
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            keys.send(KeyboardInput {
+                key_code,
+                logical_key: logical_key.clone(),
+                state,
+                window,
+            });
+        }
+    };
+
+    let mut held = None;
+    for gamepad in gamepads.iter() {
+        let button = |button_type| GamepadButton::new(gamepad, button_type);
+        if buttons.just_pressed(button(GamepadButtonType::South)) {
+            send(KeyCode::Enter);
+        }
+        if buttons.just_pressed(button(GamepadButtonType::East)) {
+            send(KeyCode::Escape);
+        }
+
+        let stick_x = axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+            .unwrap_or_default();
+        let stick_y = axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+            .unwrap_or_default();
+        let direction = [
+            (
+                GamepadButtonType::DPadUp,
+                stick_y > STICK_THRESHOLD,
+                KeyCode::ArrowUp,
+            ),
+            (
+                GamepadButtonType::DPadDown,
+                stick_y < -STICK_THRESHOLD,
+                KeyCode::ArrowDown,
+            ),
+            (
+                GamepadButtonType::DPadLeft,
+                stick_x < -STICK_THRESHOLD,
+                KeyCode::ArrowLeft,
+            ),
+            (
+                GamepadButtonType::DPadRight,
+                stick_x > STICK_THRESHOLD,
+                KeyCode::ArrowRight,
+            ),
+        ]
+        .into_iter()
+        .find(|(dpad, stick, _)| buttons.pressed(button(*dpad)) || *stick)
+        .map(|(_, _, key_code)| key_code);
+        held = held.or(direction);
+    }
+
+    match (held, &mut repeat.0) {
+        (None, _) => repeat.0 = None,
+        (Some(key_code), Some((repeating, timer))) if *repeating == key_code => {
+            timer.tick(time.delta());
+            if timer.finished() {
+                *timer = Timer::from_seconds(REPEAT_INTERVAL_SECS, TimerMode::Once);
+                send(key_code);
+            }
+        }
+        (Some(key_code), _) => {
+            send(key_code);
+            repeat.0 = Some((
+                key_code,
+                Timer::from_seconds(REPEAT_DELAY_SECS, TimerMode::Once),
+            ));
+        }
+    }
+}
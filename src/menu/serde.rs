@@ -1,40 +1,52 @@
-use bevy::{a11y::Focus, prelude::*};
-use bevy_mod_picking::prelude::{Listener, On};
-use bevy_mod_stylebuilder::{StyleBuilder, StyleBuilderFont, StyleBuilderLayout};
+use bevy::{a11y::accesskit::Role, ecs::system::SystemState, prelude::*};
+use bevy_mod_picking::prelude::{ListenerInput, On};
+use bevy_mod_stylebuilder::{StyleBuilderFont, StyleBuilderLayout};
 use bevy_quill::*;
 use bevy_quill_obsidian::{
-    colors,
     controls::{
-        Button as QuillButton, ButtonVariant, Icon, MenuButton, MenuItem as QuillMenuItem,
-        MenuPopup, Slider as QuillSlider, Spacer,
+        Button as QuillButton, ButtonVariant, Checkbox, Icon, Slider as QuillSlider, Spacer,
     },
-    focus::{AutoFocus, DefaultKeyListener, KeyPressEvent, TabGroup},
+    focus::{DefaultKeyListener, KeyPressEvent, TabGroup},
     size::Size,
     typography,
 };
 use serde::Deserialize;
 
 use crate::{
+    accessibility::MenuItemAccess,
+    editor::EditedPack,
+    layout::use_layout,
     loading::TextureAssets,
-    lobby::HostLobby,
+    locale::{use_text, Localization},
     menu::{
-        menu_button_style, menu_labeled_style, menu_row_style, menu_style, menu_text_input_style,
-        utils::open_link,
+        action::MenuAction,
+        condition::{use_condition, Condition, MenuValue},
+        menu_button_style, menu_content_style, menu_error_style, menu_labeled_style,
+        menu_row_style, menu_style, menu_text_input_style, menu_title_style,
+        navigation::{navigate, Direction, MenuRoot},
+        transition::MenuTransition,
+        utils::{is_false, open_link},
         widgets::{
+            dropdown::Dropdown as QuillDropdown,
             multi_dropdown::MultiDropdown as QuillMultiDropdown,
+            radio_group::RadioGroup as QuillRadioGroup,
+            stepper::Stepper as QuillStepper,
             text_input::{TextInput as QuillTextInput, TextInputType},
+            validator::Validator,
             UseComponentOrDefault,
         },
-        MenuStack, WhichMenu,
+        WhichMenu,
     },
-    trivia::source::TriviaSource,
+    settings::Settings,
+    theme::use_theme,
+    trivia::{card::QuestionCard, source::TriviaSource},
     ShowInspectorUi,
 };
 
 /// A menu for the game
 #[derive(Asset, Deserialize, TypePath, Clone, Debug, PartialEq)]
 pub struct Menu {
-    /// The title of the menu
+    /// The localization key of the title, which also names the menu's element
     title: String,
 
     /// Whether this is the main menu, or not
@@ -43,6 +55,21 @@ pub struct Menu {
 
     /// The contents of the menu
     children: Vec<MenuItem>,
+
+    /// How the menu animates in and out
+    #[serde(default)]
+    transition: MenuTransition,
+
+    /// The music to play while the menu is shown, by track name. Menus without
+    /// any keep playing the music of the menu before.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    music: Option<String>,
+}
+
+impl Menu {
+    pub fn music(&self) -> Option<&str> {
+        self.music.as_deref()
+    }
 }
 
 impl ViewTemplate for Menu {
@@ -50,51 +77,86 @@ impl ViewTemplate for Menu {
 
     fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
         let id = cx.create_entity();
-        cx.world_mut().get_resource_mut::<Focus>().unwrap().0 = Some(id);
+        let main_menu = self.main_menu;
+        let transition = self.transition;
+        let theme = use_theme(cx);
+        let layout = use_layout(cx);
+        // Focus is given to the items by `restore_menu_focus` once they exist
         Element::<NodeBundle>::for_entity(id)
             .named(&self.title)
-            .style(menu_style)
-            .insert_dyn(move |_| (TabGroup::default(), AutoFocus), ())
-            .insert_if(cfg!(debug_assertions), || DefaultKeyListener)
-            .insert_if(cfg!(debug_assertions), move || {
-                On::<KeyPressEvent>::run(
-                    move |event: Listener<KeyPressEvent>, mut show: ResMut<ShowInspectorUi>| {
-                        if event.key_code == KeyCode::F12 {
-                            show.0 = !show.0;
-                        };
-                    },
-                )
-            })
-            .children((
+            .style_dyn(menu_style, (theme, layout))
+            .insert_dyn(
+                move |_| {
+                    (
+                        MenuRoot,
+                        transition,
+                        TabGroup::default(),
+                        DefaultKeyListener,
+                        On::<KeyPressEvent>::run(move |world: &mut World| {
+                            let mut state =
+                                SystemState::<ResMut<ListenerInput<KeyPressEvent>>>::new(world);
+                            let key_code = state.get_mut(world).key_code;
+                            let handled = match key_code {
+                                KeyCode::Escape if !main_menu => {
+                                    MenuAction::Back.run(world);
+                                    true
+                                }
+                                KeyCode::F12 if cfg!(debug_assertions) => {
+                                    let mut show = world.resource_mut::<ShowInspectorUi>();
+                                    show.0 = !show.0;
+                                    true
+                                }
+                                key_code => Direction::from_key(key_code)
+                                    .is_some_and(|direction| navigate(world, id, direction)),
+                            };
+                            if handled {
+                                state.get_mut(world).stop_propagation();
+                            }
+                        }),
+                    )
+                },
+                (),
+            )
+            .children(
                 Element::<NodeBundle>::new()
-                    .style((typography::text_strong, move |ss: &mut StyleBuilder| {
-                        ss.min_height(Val::Px(70.0))
-                            .font_size(48.0)
-                            .color(colors::PRIMARY);
-                    }))
-                    .children(self.title.clone()),
-                self.children
-                    .iter()
-                    .map(|item| item.clone().into_view_child())
-                    .collect::<Vec<_>>(),
-                Cond::new(
-                    self.main_menu,
-                    #[cfg(not(target_arch = "wasm32"))]
-                    MenuItem::Button(Button {
-                        label: "Quit".to_string(),
-                        action: MenuAction::Quit,
-                    }),
-                    #[cfg(target_arch = "wasm32")]
-                    MenuItem::Button(Button {
-                        label: "Quit".to_string(),
-                        action: MenuAction::Reload,
-                    }),
-                    MenuItem::Button(Button {
-                        label: "Back".to_string(),
-                        action: MenuAction::Back,
-                    }),
-                ),
-            ))
+                    .style_dyn(menu_content_style, (theme, layout))
+                    .children((
+                        Element::<NodeBundle>::new()
+                            .style(typography::text_strong)
+                            .style_dyn(menu_title_style, (theme, layout))
+                            .children(use_text(cx, &self.title)),
+                        self.children
+                            .iter()
+                            .map(|item| item.clone().into_view_child())
+                            .collect::<Vec<_>>(),
+                        Cond::new(
+                            self.main_menu,
+                            #[cfg(not(target_arch = "wasm32"))]
+                            MenuItem::Button(Button {
+                                label: "menu.quit".to_string(),
+                                action: MenuAction::Confirm {
+                                    prompt: "menu.quit_prompt".to_string(),
+                                    then: Box::new(MenuAction::Quit),
+                                },
+                                visible_if: None,
+                                enabled_if: None,
+                            }),
+                            #[cfg(target_arch = "wasm32")]
+                            MenuItem::Button(Button {
+                                label: "menu.quit".to_string(),
+                                action: MenuAction::Reload,
+                                visible_if: None,
+                                enabled_if: None,
+                            }),
+                            MenuItem::Button(Button {
+                                label: "menu.back".to_string(),
+                                action: MenuAction::Back,
+                                visible_if: None,
+                                enabled_if: None,
+                            }),
+                        ),
+                    )),
+            )
     }
 }
 
@@ -122,6 +184,15 @@ pub enum MenuItem {
     /// A slider to select a value
     Slider(Slider),
 
+    /// A number with -/+ buttons to step through a range
+    Stepper(Stepper),
+
+    /// A checkbox to switch something on or off
+    Toggle(Toggle),
+
+    /// A set of options of which exactly one is chosen
+    RadioGroup(RadioGroup),
+
     /// A row of several items
     Row(Row),
 
@@ -130,14 +201,18 @@ pub enum MenuItem {
 
     /// A multi-dropdown to select multiple options
     MultiDropdown(MultiDropdown),
+
+    /// The question open in the pack editor, as players would see it
+    QuestionPreview(QuestionPreview),
 }
 
 impl ViewTemplate for MenuItem {
     type View = impl View;
 
-    fn create(&self, _cx: &mut bevy_quill::Cx) -> Self::View {
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
         let item: Self = self.to_owned();
-        match item {
+        let visible = use_condition(cx, &item.visible_if());
+        let view = match item {
             MenuItem::Label(label) => label.into_view_child(),
             MenuItem::Spacer => Spacer.into_view_child(),
             MenuItem::SubMenu(sub_menu) => sub_menu.into_view_child(),
@@ -145,32 +220,40 @@ impl ViewTemplate for MenuItem {
             MenuItem::Link(link) => link.into_view_child(),
             MenuItem::TextInput(text_input) => text_input.into_view_child(),
             MenuItem::Slider(slider) => slider.into_view_child(),
+            MenuItem::Stepper(stepper) => stepper.into_view_child(),
+            MenuItem::Toggle(toggle) => toggle.into_view_child(),
+            MenuItem::RadioGroup(radio_group) => radio_group.into_view_child(),
             MenuItem::Row(row) => row.into_view_child(),
             MenuItem::Dropdown(dropdown) => dropdown.into_view_child(),
             MenuItem::MultiDropdown(multi_dropdown) => multi_dropdown.into_view_child(),
-        }
+            MenuItem::QuestionPreview(preview) => preview.into_view_child(),
+        };
+        Cond::new(visible, view, ())
     }
 }
 
-/// The action to perform when a button is clicked
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, TypePath)]
-pub enum MenuAction {
-    /// Quit the game
-    Quit,
-
-    /// Go back to the previous menu in the stack
-    Back,
-
-    #[cfg(target_arch = "wasm32")]
-    /// Reload the game, web only
-    Reload,
-
-    /// Open the Lobby from the [`WhichMenu::HostGame`] Menu
-    HostLobby,
+impl MenuItem {
+    /// The condition for showing the item, if it has one
+    fn visible_if(&self) -> Option<Condition> {
+        match self {
+            MenuItem::Label(Label { visible_if, .. })
+            | MenuItem::SubMenu(SubMenu { visible_if, .. })
+            | MenuItem::Button(Button { visible_if, .. })
+            | MenuItem::Link(Link { visible_if, .. })
+            | MenuItem::TextInput(TextInput { visible_if, .. })
+            | MenuItem::Slider(Slider { visible_if, .. })
+            | MenuItem::Stepper(Stepper { visible_if, .. })
+            | MenuItem::Toggle(Toggle { visible_if, .. })
+            | MenuItem::RadioGroup(RadioGroup { visible_if, .. })
+            | MenuItem::Dropdown(Dropdown { visible_if, .. })
+            | MenuItem::MultiDropdown(MultiDropdown { visible_if, .. })
+            | MenuItem::QuestionPreview(QuestionPreview { visible_if }) => visible_if.clone(),
+            MenuItem::Spacer | MenuItem::Row(_) => None,
+        }
+    }
 }
 
 /// A button to click
-/// Currently only supports the `Quit` and `Back` actions
 #[derive(Deserialize, TypePath, Clone, Debug, PartialEq)]
 pub struct Button {
     /// The label to display
@@ -178,45 +261,27 @@ pub struct Button {
 
     /// The action to perform when the button is clicked
     action: MenuAction,
+
+    /// Only show the button while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+
+    /// Only let the button be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
 }
 
 impl ViewTemplate for Button {
     type View = impl View;
 
     fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
-        let label = self.label.clone();
-        let action = self.action;
+        let label = use_text(cx, &self.label);
+        let action = self.action.clone();
+        let enabled = use_condition(cx, &self.enabled_if);
 
         QuillButton::new()
-            .on_click(cx.create_callback(
-                move |mut commands: Commands,
-                      mut next_state: ResMut<NextState<WhichMenu>>,
-                      mut menu_stack: ResMut<MenuStack>,
-                      mut app_exit: EventWriter<AppExit>,
-                      start_host_lobby: Res<HostLobby>| {
-                    debug!("Menu Stack: {:?}", menu_stack);
-                    match action {
-                        MenuAction::Quit => {
-                            app_exit.send(AppExit::Success);
-                        }
-                        MenuAction::Back => {
-                            if let Some(menu) = menu_stack.pop() {
-                                next_state.set(menu);
-                            } else {
-                                next_state.set(WhichMenu::Main);
-                            }
-                        }
-                        #[cfg(target_arch = "wasm32")]
-                        MenuAction::Reload => {
-                            let location = gloo_utils::window().location();
-                            location.reload().unwrap();
-                        }
-                        MenuAction::HostLobby => {
-                            commands.run_system(**start_host_lobby);
-                        }
-                    }
-                },
-            ))
+            .disabled(!enabled)
+            .on_click(cx.create_callback(move |world: &mut World| action.run(world)))
             .style(menu_button_style)
             .size(Size::Xl)
             .children(label)
@@ -240,29 +305,34 @@ pub struct Label {
     /// The color of the label
     #[serde(skip_serializing_if = "Option::is_none", default)]
     color: Option<Color>,
+
+    /// Only show the label while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
 }
 
 impl ViewTemplate for Label {
     type View = impl View;
 
-    fn create(&self, _cx: &mut bevy_quill::Cx) -> Self::View {
-        let label = self.label.clone();
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let label = use_text(cx, &self.label);
         let height = self.height;
         let font_size = self.font_size;
         let color = self.color;
+        let theme = use_theme(cx);
 
         Element::<NodeBundle>::new()
-            .style((typography::text_default, move |ss: &mut StyleBuilder| {
-                if let Some(height) = height {
-                    ss.min_height(Val::Px(height));
-                }
-                if let Some(font_size) = font_size {
-                    ss.font_size(font_size);
-                }
-                if let Some(color) = color {
-                    ss.color(color);
-                }
-            }))
+            .style(typography::text_default)
+            .style_dyn(
+                |(theme, height, font_size, color), ss| {
+                    if let Some(height) = height {
+                        ss.min_height(Val::Px(height));
+                    }
+                    ss.font_size(font_size.unwrap_or(theme.font_sizes.body))
+                        .color(color.unwrap_or(theme.palette.foreground));
+                },
+                (theme, height, font_size, color),
+            )
             .children(label)
     }
 }
@@ -279,6 +349,14 @@ pub struct Link {
     /// The icon to display next to the label
     #[serde(skip_serializing_if = "Option::is_none", default)]
     icon: Option<String>,
+
+    /// Only show the link while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+
+    /// Only let the link be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
 }
 
 impl ViewTemplate for Link {
@@ -286,11 +364,13 @@ impl ViewTemplate for Link {
 
     fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
         let url = self.url.clone();
-        let label = self.label.clone();
+        let label = use_text(cx, &self.label);
         let icon = self.icon.clone();
+        let enabled = use_condition(cx, &self.enabled_if);
 
         info!("TextureAssets: {:?}", cx.use_resource::<TextureAssets>());
         QuillButton::new()
+            .disabled(!enabled)
             .on_click(open_link(cx, url))
             .style(menu_button_style)
             .size(Size::Xl)
@@ -342,6 +422,14 @@ pub struct SubMenu {
     /// The variant of the button: Primary or Default
     #[serde(skip_serializing_if = "MenuButtonVariant::is_default", default)]
     variant: MenuButtonVariant,
+
+    /// Only show the button while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+
+    /// Only let the button be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
 }
 
 impl ViewTemplate for SubMenu {
@@ -349,20 +437,16 @@ impl ViewTemplate for SubMenu {
 
     fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
         let menu = self.menu;
+        let enabled = use_condition(cx, &self.enabled_if);
 
         QuillButton::new()
-            .on_click(cx.create_callback(
-                move |mut next_state: ResMut<NextState<WhichMenu>>,
-                      current_state: Res<State<WhichMenu>>,
-                      mut menu_stack: ResMut<MenuStack>| {
-                    menu_stack.push(current_state.to_owned());
-                    next_state.set(menu);
-                    debug!("Menu Stack: {:?}", menu_stack);
-                },
-            ))
+            .disabled(!enabled)
+            .on_click(
+                cx.create_callback(move |world: &mut World| MenuAction::Goto(menu).run(world)),
+            )
             .style(menu_button_style)
             .size(Size::Xl)
-            .children(self.label.clone())
+            .children(use_text(cx, &self.label))
             .variant(self.variant.into())
     }
 }
@@ -390,26 +474,60 @@ pub struct TextInput {
         rename(deserialize = "type", serialize = "type")
     )]
     type_: TextInputType,
+
+    /// Checks the value must pass, in order, before the form can be submitted
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    validators: Vec<Validator>,
+
+    /// Only show the text input while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+
+    /// Only let the text input be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
 }
 
 impl ViewTemplate for TextInput {
     type View = impl View;
 
-    fn create(&self, _cx: &mut bevy_quill::Cx) -> Self::View {
-        let label = self.label.clone();
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let label = use_text(cx, &self.label);
         let default_value = self.default_value.clone();
+        let error = cx.create_mutable::<Option<String>>(None);
+        let message = error.get_clone(cx);
+        let enabled = use_condition(cx, &self.enabled_if);
+        let theme = use_theme(cx);
 
         Element::<NodeBundle>::new()
-            .style((menu_labeled_style, typography::text_strong))
+            .style(typography::text_strong)
+            .style_dyn(menu_labeled_style, theme)
+            .insert_dyn(
+                |label| MenuItemAccess::new(Role::TextInput, label),
+                label.clone(),
+            )
             .children((
                 label,
                 QuillTextInput::new()
                     .named(&self.name)
                     .default_value(default_value)
                     .max_length(self.max_length)
+                    .disabled(!enabled)
                     .style(menu_text_input_style)
                     .size(Size::Xl)
-                    .type_(self.type_),
+                    .type_(self.type_)
+                    .validators(self.validators.clone())
+                    .on_validate(cx.create_callback(
+                        move |message: In<Option<String>>, world: &mut World| {
+                            error.set_clone(world, message.0);
+                        },
+                    )),
+                message.map(|message| {
+                    Element::<NodeBundle>::new()
+                        .style(typography::text_default)
+                        .style_dyn(menu_error_style, theme)
+                        .children(message)
+                }),
             ))
     }
 }
@@ -431,6 +549,14 @@ pub struct Slider {
 
     /// The name of the slider, for fetching the value from components
     name: String,
+
+    /// Only show the slider while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+
+    /// Only let the slider be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
 }
 
 #[derive(Component, Debug, Default, Clone, Reflect, Deref, DerefMut, PartialEq, Eq)]
@@ -441,14 +567,28 @@ impl ViewTemplate for Slider {
 
     fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
         let id = cx.create_entity();
-        let label = self.label.clone();
+        // Sliders named after a setting start out with its current value
+        let initial = match cx.world().resource::<Settings>().value(&self.name) {
+            Some(MenuValue::Number(value)) => value as usize,
+            _ => self.value,
+        };
+        let label = use_text(cx, &self.label);
         let value = cx
-            .use_component_or::<SliderValue>(id, SliderValue(self.value))
+            .use_component_or::<SliderValue>(id, SliderValue(initial))
             .clone();
         let value = cx.create_mutable(*value);
+        let enabled = use_condition(cx, &self.enabled_if);
+        let theme = use_theme(cx);
 
         Element::<NodeBundle>::for_entity(id)
-            .style((menu_labeled_style, typography::text_strong))
+            .style(typography::text_strong)
+            .style_dyn(menu_labeled_style, theme)
+            .insert_dyn(
+                |(label, min, max)| {
+                    MenuItemAccess::new(Role::Slider, label).range(min as f64, max as f64)
+                },
+                (label.clone(), self.min, self.max),
+            )
             .insert_dyn(Name::new, self.name.clone())
             .insert_dyn(SliderValue, value.get(cx))
             .children((
@@ -456,6 +596,7 @@ impl ViewTemplate for Slider {
                 QuillSlider::new()
                     .value(value.get(cx) as f32)
                     .precision(0)
+                    .disabled(!enabled)
                     .step(1.0)
                     .range(self.min as f32..=self.max as f32)
                     .style(menu_text_input_style)
@@ -471,6 +612,203 @@ impl ViewTemplate for Slider {
     }
 }
 
+/// A number with -/+ buttons to step through a range
+#[derive(Deserialize, TypePath, Clone, Debug, PartialEq)]
+pub struct Stepper {
+    /// The label to display
+    label: String,
+
+    /// The default value of the stepper
+    value: i64,
+
+    /// The minimum value of the stepper
+    min: i64,
+
+    /// The maximum value of the stepper
+    max: i64,
+
+    /// How much each press of -/+ changes the value
+    #[serde(default = "default_step")]
+    step: i64,
+
+    /// The name of the stepper, for fetching the value from components
+    name: String,
+
+    /// Only show the stepper while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+
+    /// Only let the stepper be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
+}
+
+fn default_step() -> i64 {
+    1
+}
+
+impl ViewTemplate for Stepper {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        // Steppers named after a setting start out with its current value
+        let initial = match cx.world().resource::<Settings>().value(&self.name) {
+            Some(MenuValue::Number(value)) => value as i64,
+            _ => self.value,
+        };
+        let label = use_text(cx, &self.label);
+        let enabled = use_condition(cx, &self.enabled_if);
+        let theme = use_theme(cx);
+
+        Element::<NodeBundle>::new()
+            .style(typography::text_strong)
+            .style_dyn(menu_labeled_style, theme)
+            .insert_dyn(
+                |(label, min, max)| {
+                    MenuItemAccess::new(Role::SpinButton, label).range(min as f64, max as f64)
+                },
+                (label.clone(), self.min, self.max),
+            )
+            .children((
+                label,
+                QuillStepper::new()
+                    .disabled(!enabled)
+                    .value(initial)
+                    .range(self.min, self.max)
+                    .step(self.step)
+                    .size(Size::Md)
+                    .named(&self.name),
+            ))
+    }
+}
+
+/// A checkbox to switch something on or off
+#[derive(Deserialize, TypePath, Clone, Debug, PartialEq)]
+pub struct Toggle {
+    /// The label to display
+    label: String,
+
+    /// Whether the toggle starts out on
+    #[serde(skip_serializing_if = "is_false", default)]
+    value: bool,
+
+    /// The name of the toggle, for fetching the value from components
+    name: String,
+
+    /// Only show the toggle while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+
+    /// Only let the toggle be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
+}
+
+#[derive(Component, Debug, Default, Clone, Copy, Reflect, Deref, DerefMut, PartialEq, Eq)]
+pub struct ToggleValue(bool);
+
+impl ViewTemplate for Toggle {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let id = cx.create_entity();
+        // Toggles named after a setting start out with its current value
+        let initial = match cx.world().resource::<Settings>().value(&self.name) {
+            Some(MenuValue::Bool(value)) => value,
+            _ => self.value,
+        };
+        let value = **cx.use_component_or::<ToggleValue>(id, ToggleValue(initial));
+        let label = use_text(cx, &self.label);
+        let enabled = use_condition(cx, &self.enabled_if);
+        let theme = use_theme(cx);
+
+        Element::<NodeBundle>::for_entity(id)
+            .style(typography::text_strong)
+            .style_dyn(menu_labeled_style, theme)
+            .insert_dyn(
+                |label| MenuItemAccess::new(Role::CheckBox, label),
+                label.clone(),
+            )
+            .insert_dyn(Name::new, self.name.clone())
+            .children(
+                Checkbox::new()
+                    .label(label)
+                    .checked(value)
+                    .disabled(!enabled)
+                    .on_change(cx.create_callback(move |v: In<bool>, world: &mut World| {
+                        world.entity_mut(id).insert(ToggleValue(*v));
+                    })),
+            )
+    }
+}
+
+/// A set of options of which exactly one is chosen, shown as a row of buttons
+#[derive(Deserialize, TypePath, Clone, Debug, PartialEq)]
+pub struct RadioGroup {
+    /// The label to display
+    label: String,
+
+    /// The options to choose from, as locale keys. These also name the options
+    /// for [`Condition::SelectedKey`] and for reading the choice back.
+    options: Vec<String>,
+
+    /// The index of the option chosen at first
+    #[serde(default)]
+    selected: usize,
+
+    /// The name of the radio group, for fetching the value from components
+    name: String,
+
+    /// Only show the radio group while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+
+    /// Only let the radio group be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
+}
+
+impl ViewTemplate for RadioGroup {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let enabled = use_condition(cx, &self.enabled_if);
+        let theme = use_theme(cx);
+        // Radio groups named after a setting start out with its current value
+        let selected = match cx.world().resource::<Settings>().value(&self.name) {
+            Some(MenuValue::Selection(selected)) => {
+                selected.iter().next().copied().unwrap_or(self.selected)
+            }
+            _ => self.selected,
+        };
+        let localization = cx.use_resource::<Localization>();
+        let label = localization.text(&self.label);
+        let options = self
+            .options
+            .iter()
+            .map(|option| localization.text(option))
+            .collect::<Vec<_>>();
+
+        Element::<NodeBundle>::new()
+            .style(typography::text_strong)
+            .style_dyn(menu_labeled_style, theme)
+            .insert_dyn(
+                |label| MenuItemAccess::new(Role::RadioGroup, label),
+                label.clone(),
+            )
+            .children((
+                label,
+                QuillRadioGroup::new()
+                    .disabled(!enabled)
+                    .options(options)
+                    .keys(self.options.clone())
+                    .selected(selected)
+                    .size(Size::Md)
+                    .named(&self.name),
+            ))
+    }
+}
+
 /// A row of several items
 #[derive(Deserialize, TypePath, Clone, Debug, PartialEq)]
 pub struct Row(Vec<MenuItem>);
@@ -478,43 +816,57 @@ pub struct Row(Vec<MenuItem>);
 impl ViewTemplate for Row {
     type View = impl View;
 
-    fn create(&self, _cx: &mut bevy_quill::Cx) -> Self::View {
-        Element::<NodeBundle>::new().style(menu_row_style).children(
-            self.0
-                .iter()
-                .map(|item| item.clone().into_view_child())
-                .collect::<Vec<_>>(),
-        )
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let theme = use_theme(cx);
+        let layout = use_layout(cx);
+
+        Element::<NodeBundle>::new()
+            .style_dyn(menu_row_style, (theme, layout))
+            .children(
+                self.0
+                    .iter()
+                    .map(|item| item.clone().into_view_child())
+                    .collect::<Vec<_>>(),
+            )
     }
 }
 
+/// A dropdown to select a single option
 #[derive(Deserialize, TypePath, Clone, Debug, PartialEq)]
 pub struct Dropdown {
+    /// The label to display
     label: String,
-    source: TriviaSource,
+    /// The options to choose from
+    #[serde(alias = "source")]
+    options: TriviaSource,
+    /// The selected option
     #[serde(skip_serializing_if = "Option::is_none", default)]
     selected: Option<usize>,
+    /// The name of the dropdown, for fetching the value from components
+    name: String,
+    /// Only show the dropdown while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+    /// Only let the dropdown be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
 }
 
 impl ViewTemplate for Dropdown {
     type View = impl View;
 
-    fn create(&self, _cx: &mut bevy_quill::Cx) -> Self::View {
-        let label = self.label.clone();
-        let source = self.source.clone();
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let label = use_text(cx, &self.label);
+        let options = self.options.clone();
+        let enabled = use_condition(cx, &self.enabled_if);
 
-        Element::<NodeBundle>::new()
-            .style((menu_labeled_style, typography::text_strong))
-            .children(
-                MenuButton::new().children(label).popup(
-                    MenuPopup::new().children(
-                        source
-                            .iter()
-                            .map(|s| QuillMenuItem::new().label(s).into_view_child())
-                            .collect::<Vec<_>>(),
-                    ),
-                ),
-            )
+        QuillDropdown::new()
+            .disabled(!enabled)
+            .label(label)
+            .source(options)
+            .selected(self.selected)
+            .named(&self.name)
+            .into_view_child()
     }
 }
 
@@ -528,17 +880,25 @@ pub struct MultiDropdown {
     selected: Vec<usize>,
     /// The name of the dropdown, for fetching the value from components
     name: String,
+    /// Only show the multi-dropdown while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+    /// Only let the multi-dropdown be used while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    enabled_if: Option<Condition>,
 }
 
 impl ViewTemplate for MultiDropdown {
     type View = impl View;
 
-    fn create(&self, _cx: &mut bevy_quill::Cx) -> Self::View {
-        let label = self.label.clone();
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let label = use_text(cx, &self.label);
         let options = self.options.clone();
         let selected = &self.selected;
+        let enabled = use_condition(cx, &self.enabled_if);
 
         QuillMultiDropdown::new()
+            .disabled(!enabled)
             .label(label)
             .source(options)
             .selected(selected)
@@ -546,3 +906,32 @@ impl ViewTemplate for MultiDropdown {
             .into_view_child()
     }
 }
+
+/// The question open in the pack editor, as players would see it, following
+/// edits as they're made
+#[derive(Deserialize, TypePath, Clone, Debug, PartialEq)]
+pub struct QuestionPreview {
+    /// Only show the preview while this holds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visible_if: Option<Condition>,
+}
+
+impl ViewTemplate for QuestionPreview {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let chain = cx.use_resource::<Localization>().chain().to_vec();
+        // Questions without all their text in any language shown can't be asked yet
+        let question = cx
+            .use_resource::<EditedPack>()
+            .question()
+            .localize(&chain)
+            .unwrap_or_default();
+
+        QuestionCard {
+            question,
+            ..default()
+        }
+        .into_view_child()
+    }
+}
@@ -0,0 +1,301 @@
+use bevy::{a11y::Focus, ecs::system::RunSystemOnce, prelude::*};
+use bevy_mod_picking::prelude::Pickable;
+use bevy_quill_obsidian::focus::TabIndex;
+use serde::Deserialize;
+
+use crate::{
+    menu::{cleanup_menu, navigation::MenuRoot, setup_menu, MenuStack},
+    settings::Settings,
+};
+
+/// How long each half of a transition takes: the old menu leaving, then the new one arriving
+const TRANSITION_SECS: f32 = 0.15;
+
+/// How much the menus grow or shrink in a [`MenuTransition::Scale`]
+const SCALE_AMOUNT: f32 = 0.1;
+
+/// How a menu animates when it appears and disappears
+#[derive(Component, Deserialize, TypePath, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MenuTransition {
+    /// Switch instantly
+    None,
+    /// Fade through the background color
+    Fade,
+    /// Slide in from the side, the way the player is going
+    #[default]
+    Slide,
+    /// Zoom in when going forward, and out when going back
+    Scale,
+}
+
+impl MenuTransition {
+    /// The transition to use, given the player's settings
+    fn effective(self, settings: &Settings) -> Self {
+        match self {
+            Self::Slide | Self::Scale if settings.reduce_motion => Self::Fade,
+            transition => transition,
+        }
+    }
+}
+
+/// Whether the player went into a submenu, or back out of one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransitionDirection {
+    #[default]
+    Forward,
+    Backward,
+}
+
+/// The menu is animating out, and will be despawned when the timer finishes
+#[derive(Component, Debug, Clone)]
+pub struct MenuExiting {
+    timer: Timer,
+    direction: TransitionDirection,
+}
+
+/// The menu is animating in
+#[derive(Component, Debug, Clone)]
+pub struct MenuEntering {
+    timer: Timer,
+    direction: TransitionDirection,
+}
+
+/// The direction of the transition in progress, worked out from the [`MenuStack`]
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub(crate) struct MenuTransitionState {
+    direction: TransitionDirection,
+    stack_len: usize,
+}
+
+/// Covers the menus while they fade
+#[derive(Component, Debug, Default, Clone, Copy)]
+struct MenuFade;
+
+/// Starts animating the current menu out, or switches instantly when it doesn't animate
+pub(crate) fn start_menu_exit(world: &mut World) {
+    let stack_len = world.resource::<MenuStack>().len();
+    let mut state = *world.resource::<MenuTransitionState>();
+    state.direction = if stack_len < state.stack_len {
+        TransitionDirection::Backward
+    } else {
+        TransitionDirection::Forward
+    };
+    state.stack_len = stack_len;
+    *world.resource_mut::<MenuTransitionState>() = state;
+
+    let settings = world.resource::<Settings>().clone();
+    let mut roots =
+        world.query_filtered::<(Entity, &MenuTransition, Has<MenuExiting>), With<MenuRoot>>();
+    let roots = roots
+        .iter(world)
+        .map(|(entity, transition, exiting)| (entity, transition.effective(&settings), exiting))
+        .collect::<Vec<_>>();
+    // Still leaving from an earlier switch, whatever menu is current then will be built
+    if roots.iter().any(|(_, _, exiting)| *exiting) {
+        return;
+    }
+    if roots
+        .iter()
+        .all(|(_, transition, _)| *transition == MenuTransition::None)
+    {
+        world.run_system_once(cleanup_menu);
+        world.run_system_once(setup_menu);
+        return;
+    }
+    for (entity, _, _) in roots {
+        world
+            .entity_mut(entity)
+            .remove::<MenuEntering>()
+            .insert(MenuExiting {
+                timer: Timer::from_seconds(TRANSITION_SECS, TimerMode::Once),
+                direction: state.direction,
+            });
+        make_inert(world, entity);
+    }
+}
+
+/// Keeps the keyboard, gamepad and pointer off a menu on its way out, so nothing
+/// in it can be used while it animates
+fn make_inert(world: &mut World, root: Entity) {
+    let mut menu = Vec::new();
+    let mut stack = vec![root];
+    while let Some(entity) = stack.pop() {
+        menu.push(entity);
+        if let Some(children) = world.get::<Children>(entity) {
+            stack.extend(children.iter());
+        }
+    }
+    if world
+        .resource::<Focus>()
+        .0
+        .is_some_and(|focused| menu.contains(&focused))
+    {
+        world.resource_mut::<Focus>().0 = None;
+    }
+    for entity in menu {
+        world
+            .entity_mut(entity)
+            .remove::<TabIndex>()
+            .insert(Pickable::IGNORE);
+    }
+}
+
+/// Removes the fade when leaving the menus, which a transition cut short by
+/// starting a game would otherwise leave over the game
+pub(crate) fn cleanup_menu_fade(mut commands: Commands, fades: Query<Entity, With<MenuFade>>) {
+    for entity in fades.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Starts animating each newly built menu in
+pub(crate) fn start_menu_enter(
+    mut commands: Commands,
+    state: Res<MenuTransitionState>,
+    settings: Res<Settings>,
+    roots: Query<(Entity, &MenuTransition), Added<MenuRoot>>,
+) {
+    for (entity, transition) in roots.iter() {
+        if transition.effective(&settings) == MenuTransition::None {
+            continue;
+        }
+        commands.entity(entity).insert(MenuEntering {
+            timer: Timer::from_seconds(TRANSITION_SECS, TimerMode::Once),
+            direction: state.direction,
+        });
+    }
+}
+
+/// Moves the menus along their transitions, and swaps in the new menu once the old one is gone
+pub(crate) fn animate_menus(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    clear_color: Res<ClearColor>,
+    mut exiting: Query<
+        (
+            Entity,
+            &MenuTransition,
+            &mut MenuExiting,
+            &mut Style,
+            &mut Transform,
+        ),
+        Without<MenuEntering>,
+    >,
+    mut entering: Query<
+        (
+            Entity,
+            &MenuTransition,
+            &mut MenuEntering,
+            &mut Style,
+            &mut Transform,
+        ),
+        Without<MenuExiting>,
+    >,
+    mut fades: Query<(Entity, &mut BackgroundColor), With<MenuFade>>,
+) {
+    let mut fade = 0.0_f32;
+
+    let mut exited = Vec::new();
+    for (entity, transition, mut exit, mut style, mut transform) in exiting.iter_mut() {
+        exit.timer.tick(time.delta());
+        let sign = match exit.direction {
+            TransitionDirection::Forward => -1.0,
+            TransitionDirection::Backward => 1.0,
+        };
+        let offset = ease(exit.timer.fraction());
+        fade = fade.max(apply(
+            transition.effective(&settings),
+            offset,
+            sign,
+            &mut style,
+            &mut transform,
+        ));
+        if exit.timer.finished() {
+            exited.push(entity);
+        }
+    }
+    if !exited.is_empty() {
+        commands.add(move |world: &mut World| {
+            world.run_system_once(cleanup_menu);
+            for entity in exited {
+                if let Some(entity) = world.get_entity_mut(entity) {
+                    entity.despawn_recursive();
+                }
+            }
+            world.run_system_once(setup_menu);
+        });
+    }
+
+    for (entity, transition, mut enter, mut style, mut transform) in entering.iter_mut() {
+        enter.timer.tick(time.delta());
+        let sign = match enter.direction {
+            TransitionDirection::Forward => 1.0,
+            TransitionDirection::Backward => -1.0,
+        };
+        let offset = 1.0 - ease(enter.timer.fraction());
+        fade = fade.max(apply(
+            transition.effective(&settings),
+            offset,
+            sign,
+            &mut style,
+            &mut transform,
+        ));
+        if enter.timer.finished() {
+            commands.entity(entity).remove::<MenuEntering>();
+        }
+    }
+
+    match (fades.get_single_mut(), fade > 0.0) {
+        (Ok((_, mut color)), true) => color.0 = clear_color.0.with_alpha(fade),
+        (Ok((entity, _)), false) => commands.entity(entity).despawn(),
+        (Err(_), true) => {
+            commands.spawn((
+                MenuFade,
+                Name::new("MenuFade"),
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(0.0),
+                        right: Val::Px(0.0),
+                        top: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                        ..default()
+                    },
+                    background_color: clear_color.0.with_alpha(fade).into(),
+                    z_index: ZIndex::Global(50),
+                    ..default()
+                },
+            ));
+        }
+        (Err(_), false) => {}
+    }
+}
+
+/// Places a menu `offset` of the way out of view, towards `sign`.
+/// Returns how opaque the fade over the menus should be.
+fn apply(
+    transition: MenuTransition,
+    offset: f32,
+    sign: f32,
+    style: &mut Style,
+    transform: &mut Transform,
+) -> f32 {
+    match transition {
+        MenuTransition::None => 0.0,
+        MenuTransition::Fade => offset,
+        MenuTransition::Slide => {
+            style.left = Val::Percent(sign * offset * 100.0);
+            style.right = Val::Percent(-sign * offset * 100.0);
+            0.0
+        }
+        MenuTransition::Scale => {
+            transform.scale = Vec3::splat(1.0 + sign * SCALE_AMOUNT * offset);
+            offset
+        }
+    }
+}
+
+fn ease(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
@@ -1,12 +1,15 @@
 use bevy::log::warn;
 use bevy_quill::{Callback, Cx};
 
+/// Opens `link` in the browser, logging when that fails
+pub fn open_url(link: &str) {
+    if let Err(error) = webbrowser::open(link) {
+        warn!("Failed to open link {error:?}");
+    }
+}
+
 pub fn open_link(cx: &mut Cx, link: impl AsRef<str> + Send + Sync + 'static) -> Callback {
-    cx.create_callback(move || {
-        if let Err(error) = webbrowser::open(link.as_ref()) {
-            warn!("Failed to open link {error:?}");
-        }
-    })
+    cx.create_callback(move || open_url(link.as_ref()))
 }
 
 pub fn is_false(value: &bool) -> bool {
@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+
+/// Access to the system clipboard, or the browser clipboard on wasm.
+///
+/// Reading the browser clipboard is asynchronous, so pasting is always a request:
+/// the text is queued for the target entity and applied by [`Clipboard::drain_pastes`]
+/// in a later frame.
+#[derive(Resource, Default, Clone)]
+pub struct Clipboard {
+    pending: Arc<Mutex<Vec<(Entity, String)>>>,
+
+    /// Fallback for platforms without a system clipboard we can reach
+    #[cfg(not(any(
+        target_arch = "wasm32",
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux"
+    )))]
+    local: Arc<Mutex<String>>,
+}
+
+impl Clipboard {
+    /// Copies `text` to the clipboard
+    pub fn set_text(&self, text: String) {
+        #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+        {
+            if let Err(error) = arboard::Clipboard::new().and_then(|mut c| c.set_text(text)) {
+                warn!("Failed to copy to the clipboard: {error}");
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let Some(clipboard) = web_sys::window().and_then(|w| w.navigator().clipboard()) else {
+                warn!("The browser does not expose a clipboard");
+                return;
+            };
+            let _ = clipboard.write_text(&text);
+        }
+
+        #[cfg(not(any(
+            target_arch = "wasm32",
+            target_os = "windows",
+            target_os = "macos",
+            target_os = "linux"
+        )))]
+        {
+            *self.local.lock().unwrap() = text;
+        }
+    }
+
+    /// Reads the clipboard and queues its contents to be pasted into `target`
+    pub fn request_paste(&self, target: Entity) {
+        #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+        {
+            match arboard::Clipboard::new().and_then(|mut c| c.get_text()) {
+                Ok(text) => self.pending.lock().unwrap().push((target, text)),
+                Err(error) => warn!("Failed to read the clipboard: {error}"),
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let Some(clipboard) = web_sys::window().and_then(|w| w.navigator().clipboard()) else {
+                warn!("The browser does not expose a clipboard");
+                return;
+            };
+            let pending = self.pending.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match wasm_bindgen_futures::JsFuture::from(clipboard.read_text()).await {
+                    Ok(text) => {
+                        if let Some(text) = text.as_string() {
+                            pending.lock().unwrap().push((target, text));
+                        }
+                    }
+                    Err(error) => warn!("Failed to read the clipboard: {error:?}"),
+                }
+            });
+        }
+
+        #[cfg(not(any(
+            target_arch = "wasm32",
+            target_os = "windows",
+            target_os = "macos",
+            target_os = "linux"
+        )))]
+        {
+            let text = self.local.lock().unwrap().clone();
+            self.pending.lock().unwrap().push((target, text));
+        }
+    }
+
+    /// Takes every paste that has arrived since the last call
+    pub fn drain_pastes(&self) -> Vec<(Entity, String)> {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
@@ -1 +1,385 @@
+use bevy::{a11y::accesskit::Role, ecs::system::SystemState, prelude::*};
+use bevy_mod_picking::prelude::{ListenerInput, On};
+use bevy_mod_stylebuilder::{StyleBuilder, StyleBuilderFont, StyleBuilderLayout, StyleHandle};
+use bevy_quill::*;
+use bevy_quill_obsidian::{
+    controls::{Button, MenuButton, MenuItem, MenuPopup},
+    focus::{AutoFocus, KeyCharEvent, KeyPressEvent},
+    size::Size,
+    typography, RoundedCorners,
+};
 
+use crate::{
+    accessibility::MenuItemAccess,
+    locale::Localization,
+    menu::{menu_text_input_style, widgets::UseComponentOrDefault},
+    theme::use_theme,
+    trivia::source::{
+        cache::cached_note, DoneFetching, FetchError, Fetching, FromCache, TriviaSource,
+    },
+};
+
+/// How long to wait between key presses before the type-to-search query starts over
+const SEARCH_RESET_SECS: f32 = 1.0;
+
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut, PartialEq, Eq)]
+pub struct DropdownSelected(Option<usize>);
+
+/// The characters typed so far while the dropdown is focused
+#[derive(Component, Debug, Default, Clone)]
+struct DropdownSearch {
+    query: String,
+    last_key: f32,
+}
+
+#[derive(Default, Clone, PartialEq)]
+pub struct Dropdown {
+    pub label: String,
+    pub source: TriviaSource,
+    pub selected: Option<usize>,
+    pub placeholder: String,
+
+    pub size: Size,
+    pub disabled: bool,
+    pub style: StyleHandle,
+    pub tab_index: i32,
+    pub corners: RoundedCorners,
+    pub auto_focus: bool,
+    pub on_change: Option<Callback<usize>>,
+
+    pub name: String,
+}
+
+impl Dropdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn label(mut self, label: String) -> Self {
+        self.label = label;
+        self
+    }
+
+    pub fn source(mut self, source: TriviaSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn placeholder(mut self, placeholder: String) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    pub fn size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    pub fn tab_index(mut self, tab_index: i32) -> Self {
+        self.tab_index = tab_index;
+        self
+    }
+
+    pub fn corners(mut self, corners: RoundedCorners) -> Self {
+        self.corners = corners;
+        self
+    }
+
+    pub fn auto_focus(mut self, auto_focus: bool) -> Self {
+        self.auto_focus = auto_focus;
+        self
+    }
+
+    pub fn on_change(mut self, callback: Callback<usize>) -> Self {
+        self.on_change = Some(callback);
+        self
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+/// Selects `index` in the dropdown `id`, notifying `on_change` if the selection changed
+fn select_option(world: &mut World, id: Entity, index: usize, on_change: Option<Callback<usize>>) {
+    let mut selected = world
+        .get_mut::<DropdownSelected>(id)
+        .expect("DropdownSelected set by `create()`");
+    if **selected == Some(index) {
+        return;
+    }
+    **selected = Some(index);
+    if let Some(on_change) = on_change {
+        world.run_callback(on_change, index);
+    }
+}
+
+/// Finds the next option starting with `query`, beginning the search just after `current`
+fn search_options(options: &[String], query: &str, current: Option<usize>) -> Option<usize> {
+    if options.is_empty() || query.is_empty() {
+        return None;
+    }
+    let query = query.to_lowercase();
+    // A single repeated character cycles through the options starting with it
+    let start = match current {
+        Some(current) if query.chars().count() == 1 => current + 1,
+        Some(current) => current,
+        None => 0,
+    };
+    (0..options.len())
+        .map(|offset| (start + offset) % options.len())
+        .find(|&i| options[i].to_lowercase().starts_with(&query))
+}
+
+impl ViewTemplate for Dropdown {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let id = cx.create_entity();
+
+        let label = self.label.clone();
+        let size = self.size;
+        let tab_index = self.tab_index;
+        let corners = self.corners;
+        let on_change = self.on_change;
+
+        let source = cx
+            .use_component_or::<TriviaSource>(id, self.source.clone())
+            .clone();
+        let selected =
+            **cx.use_component_or::<DropdownSelected>(id, DropdownSelected(self.selected));
+        let mut ent = cx.world_mut().entity_mut(id);
+        if !ent.contains::<DropdownSearch>() {
+            ent.insert(DropdownSearch::default());
+        }
+        let is_string_source = matches!(source, TriviaSource::String(_));
+        let loading = !is_string_source
+            && cx.use_component::<DoneFetching>(id).is_none()
+            && cx.use_component::<Fetching>(id).is_some();
+        let error = cx.use_component::<FetchError>(id).cloned();
+        let cached = cx.use_component::<FromCache>(id).copied();
+
+        let localization = cx.use_resource::<Localization>().clone();
+        let palette = use_theme(cx).palette;
+        let options = source
+            .iter()
+            .map(|option| localization.text(&option))
+            .collect::<Vec<_>>();
+        let current = selected
+            .and_then(|i| options.get(i).cloned())
+            .unwrap_or_else(|| self.placeholder.clone());
+        let summary = if loading {
+            localization.text("dropdown.loading")
+        } else if error.is_some() {
+            localization.text("dropdown.unavailable")
+        } else {
+            current
+        };
+        let disabled = self.disabled || loading || error.is_some() || options.is_empty();
+
+        let retry = cx.create_callback(move |world: &mut World| {
+            let mut entity = world.entity_mut(id);
+            if let Some(mut source) = entity.get_mut::<TriviaSource>() {
+                source.reset();
+            }
+            entity.remove::<(Fetching, FetchError)>();
+        });
+
+        Element::<NodeBundle>::for_entity(id)
+            .insert_dyn(Name::new, self.name.clone())
+            .insert_dyn(
+                |(label, summary)| MenuItemAccess::new(Role::ComboBox, label).value(summary),
+                (label.clone(), summary.clone()),
+            )
+            .style((
+                style_dropdown,
+                typography::text_default,
+                move |ss: &mut StyleBuilder| {
+                    ss.min_height(size.height()).font_size(size.font_size());
+                    ss.padding(0);
+                },
+                self.style.clone(),
+            ))
+            .insert_if(self.auto_focus, || AutoFocus)
+            // Don't run systems on String sources
+            .insert_if(is_string_source, || DoneFetching)
+            .insert_dyn(
+                move |_| {
+                    (
+                        On::<KeyPressEvent>::run(move |world: &mut World| {
+                            let mut state = SystemState::<(
+                                ResMut<ListenerInput<KeyPressEvent>>,
+                                Query<(&TriviaSource, &DropdownSelected)>,
+                            )>::new(world);
+                            let (mut event, sources) = state.get_mut(world);
+                            let Ok((source, selected)) = sources.get(id) else {
+                                return;
+                            };
+                            let len = source.len();
+                            if len == 0 {
+                                return;
+                            }
+                            let next = match (event.key_code, **selected) {
+                                (KeyCode::ArrowLeft, Some(i)) => Some(i.saturating_sub(1)),
+                                (KeyCode::ArrowRight, Some(i)) => Some((i + 1).min(len - 1)),
+                                (KeyCode::ArrowLeft | KeyCode::ArrowRight, None) => Some(0),
+                                (KeyCode::Home, _) => Some(0),
+                                (KeyCode::End, _) => Some(len - 1),
+                                _ => None,
+                            };
+                            let Some(next) = next else {
+                                return;
+                            };
+                            event.stop_propagation();
+                            select_option(world, id, next, on_change);
+                        }),
+                        On::<KeyCharEvent>::run(move |world: &mut World| {
+                            let now = world.resource::<Time>().elapsed_seconds();
+                            let mut state = SystemState::<(
+                                ResMut<ListenerInput<KeyCharEvent>>,
+                                Res<Localization>,
+                                Query<(&TriviaSource, &DropdownSelected, &mut DropdownSearch)>,
+                            )>::new(world);
+                            let (mut event, localization, mut sources) = state.get_mut(world);
+                            let key = event.key;
+                            if key.is_control() {
+                                return;
+                            }
+                            let Ok((source, selected, mut search)) = sources.get_mut(id) else {
+                                return;
+                            };
+                            event.stop_propagation();
+                            if now - search.last_key > SEARCH_RESET_SECS {
+                                search.query.clear();
+                            }
+                            search.last_key = now;
+                            search.query.push(key);
+                            // Search what the player sees
+                            let options = source
+                                .iter()
+                                .map(|option| localization.text(&option))
+                                .collect::<Vec<_>>();
+                            let Some(found) = search_options(&options, &search.query, **selected)
+                            else {
+                                return;
+                            };
+                            select_option(world, id, found, on_change);
+                        }),
+                    )
+                },
+                (),
+            )
+            .children((
+                MenuButton::new()
+                    .style(menu_text_input_style)
+                    .children(format!("{label}: {summary}"))
+                    .disabled(disabled)
+                    .tab_index(tab_index)
+                    .corners(corners)
+                    .popup(
+                        MenuPopup::new().children(
+                            options
+                                .iter()
+                                .enumerate()
+                                .map(|(i, option)| {
+                                    MenuItem::new()
+                                        .label(option.clone())
+                                        .checked(selected == Some(i))
+                                        .on_click(cx.create_callback(move |world: &mut World| {
+                                            select_option(world, id, i, on_change);
+                                        }))
+                                        .into_view_child()
+                                })
+                                .collect::<Vec<_>>(),
+                        ),
+                    ),
+                Cond::new(
+                    error.is_some(),
+                    (
+                        Element::<NodeBundle>::new()
+                            .style_dyn(
+                                |destructive, ss| {
+                                    ss.color(destructive);
+                                },
+                                palette.destructive,
+                            )
+                            .children(
+                                error
+                                    .map(|error| error.localize(&localization))
+                                    .unwrap_or_default(),
+                            ),
+                        Button::new()
+                            .on_click(retry)
+                            .children(localization.text("dropdown.retry")),
+                    ),
+                    (),
+                ),
+                Cond::new(
+                    cached.is_some(),
+                    Element::<NodeBundle>::new().children(
+                        cached
+                            .map(|cached| cached_note(&localization, cached.0))
+                            .unwrap_or_default(),
+                    ),
+                    (),
+                ),
+            ))
+    }
+}
+
+fn style_dropdown(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Row)
+        .align_items(AlignItems::Center)
+        .column_gap(10);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> Vec<String> {
+        ["Art", "Books", "Board Games", "Film", "History"]
+            .iter()
+            .map(|option| option.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn finds_options_by_prefix_ignoring_case() {
+        assert_eq!(search_options(&options(), "bo", None), Some(1));
+        assert_eq!(search_options(&options(), "BOA", None), Some(2));
+        assert_eq!(search_options(&options(), "hist", Some(0)), Some(4));
+    }
+
+    #[test]
+    fn a_single_character_cycles_through_matches() {
+        assert_eq!(search_options(&options(), "b", None), Some(1));
+        assert_eq!(search_options(&options(), "b", Some(1)), Some(2));
+        assert_eq!(search_options(&options(), "b", Some(2)), Some(1));
+    }
+
+    #[test]
+    fn a_longer_query_keeps_the_current_match() {
+        assert_eq!(search_options(&options(), "bo", Some(1)), Some(1));
+        assert_eq!(search_options(&options(), "boa", Some(1)), Some(2));
+    }
+
+    #[test]
+    fn finds_nothing_without_a_match() {
+        assert_eq!(search_options(&options(), "z", None), None);
+        assert_eq!(search_options(&options(), "", Some(0)), None);
+        assert_eq!(search_options(&[], "a", None), None);
+    }
+}
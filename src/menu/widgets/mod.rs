@@ -1,9 +1,40 @@
 use bevy::prelude::*;
 use bevy_quill::Cx;
 
+use self::{
+    clipboard::Clipboard,
+    stepper::{commit_unfocused_steppers, repeat_stepper},
+    text_input::{apply_clipboard_pastes, clear_text_input_frame_text, text_input_ime},
+    validator::CustomValidators,
+};
+
+pub mod clipboard;
 pub mod dropdown;
 pub mod multi_dropdown;
+pub mod radio_group;
+pub mod stepper;
 pub mod text_input;
+pub mod validator;
+
+pub struct WidgetsPlugin;
+
+/// Systems backing the menu widgets that can't live in their event handlers
+impl Plugin for WidgetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Clipboard>()
+            .init_resource::<CustomValidators>()
+            .add_systems(
+                Update,
+                (
+                    apply_clipboard_pastes,
+                    text_input_ime,
+                    repeat_stepper,
+                    commit_unfocused_steppers,
+                ),
+            )
+            .add_systems(Last, clear_text_input_frame_text);
+    }
+}
 
 pub trait UseComponentOrDefault {
     fn use_component_or_default<T: Component + Default>(&mut self, target: Entity) -> &T;
@@ -1,21 +1,103 @@
-use bevy::{prelude::*, utils::HashSet};
+use bevy::{a11y::accesskit::Role, prelude::*, utils::HashSet};
 use bevy_mod_stylebuilder::{StyleBuilder, StyleBuilderFont, StyleBuilderLayout, StyleHandle};
 use bevy_quill::*;
 use bevy_quill_obsidian::{
-    controls::{Checkbox, MenuButton, MenuPopup},
+    controls::{Button, Checkbox, MenuButton, MenuPopup, ScrollView},
     focus::AutoFocus,
     size::Size,
     typography, RoundedCorners,
 };
 
 use crate::{
-    menu::{menu_text_input_style, widgets::UseComponentOrDefault},
-    trivia::source::{DoneFetching, TriviaSource},
+    accessibility::MenuItemAccess,
+    locale::Localization,
+    menu::{
+        menu_text_input_style,
+        widgets::{text_input::TextInput, UseComponentOrDefault},
+    },
+    theme::use_theme,
+    trivia::source::{
+        cache::cached_note, DoneFetching, FetchError, Fetching, FromCache, TriviaSource,
+    },
 };
 
+/// Separates a group name from the option name, as in `"Entertainment: Film"`
+const GROUP_SEPARATOR: &str = ": ";
+
 #[derive(Component, Debug, Default, Clone, Deref, DerefMut, PartialEq, Eq)]
 pub struct MultiDropdownSelected(HashSet<usize>);
 
+/// The text typed into the filter field of the dropdown
+#[derive(Component, Debug, Default, Clone, Deref, DerefMut, PartialEq, Eq)]
+pub struct MultiDropdownFilter(String);
+
+/// A set of options sharing the same prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OptionGroup {
+    /// The shared prefix, `None` for options that don't belong to a group
+    name: Option<String>,
+    /// The index of each option in the source, and the label to show for it
+    options: Vec<(usize, String)>,
+}
+
+/// Splits `options` into groups by the text before [`GROUP_SEPARATOR`], keeping
+/// only the options that contain `filter`, case insensitively.
+fn group_options(options: &[String], filter: &str) -> Vec<OptionGroup> {
+    let filter = filter.trim().to_lowercase();
+    let mut groups: Vec<OptionGroup> = Vec::new();
+    for (i, option) in options.iter().enumerate() {
+        if !filter.is_empty() && !option.to_lowercase().contains(&filter) {
+            continue;
+        }
+        let (name, label) = match option.split_once(GROUP_SEPARATOR) {
+            Some((group, label)) => (Some(group.to_string()), label.to_string()),
+            None => (None, option.clone()),
+        };
+        match groups
+            .iter_mut()
+            .find(|g| g.name.is_some() && g.name == name)
+        {
+            Some(group) => group.options.push((i, label)),
+            None => groups.push(OptionGroup {
+                name,
+                options: vec![(i, label)],
+            }),
+        }
+    }
+    groups
+}
+
+/// Updates the selection of the dropdown `id` and mirrors it into its [`TriviaSource`]
+fn update_selected(world: &mut World, id: Entity, f: impl FnOnce(&mut HashSet<usize>)) {
+    let entity = &mut world.entity_mut(id);
+    let mut selected = entity
+        .get_mut::<MultiDropdownSelected>()
+        .expect("MultiDropdownValues set by `create()`");
+    f(&mut selected);
+    let selected = (**selected).clone();
+    let mut source = entity
+        .get_mut::<TriviaSource>()
+        .expect("MultiDropdownValues set by `create()`");
+    source.set_selected(selected);
+}
+
+/// The indices of the options that pass the current filter of the dropdown `id`
+fn visible_options(world: &World, id: Entity) -> Vec<usize> {
+    let filter = world
+        .get::<MultiDropdownFilter>(id)
+        .map(|f| f.0.clone())
+        .unwrap_or_default();
+    let localization = world.resource::<Localization>();
+    let options = world
+        .get::<TriviaSource>(id)
+        .map(|s| s.iter().map(|o| localization.text(&o)).collect::<Vec<_>>())
+        .unwrap_or_default();
+    group_options(&options, &filter)
+        .into_iter()
+        .flat_map(|g| g.options.into_iter().map(|(i, _)| i))
+        .collect()
+}
+
 #[derive(Default, Clone, PartialEq)]
 pub struct MultiDropdown {
     pub label: String,
@@ -124,9 +206,78 @@ impl ViewTemplate for MultiDropdown {
                 ),
             )
             .clone();
+        let filter = cx
+            .use_component_or_default::<MultiDropdownFilter>(id)
+            .clone();
+        let loading = !matches!(source, TriviaSource::String(_))
+            && cx.use_component::<DoneFetching>(id).is_none()
+            && cx.use_component::<Fetching>(id).is_some();
+        let error = cx.use_component::<FetchError>(id).cloned();
+        let cached = cx.use_component::<FromCache>(id).copied();
+
+        let localization = cx.use_resource::<Localization>().clone();
+        let palette = use_theme(cx).palette;
+        let options = source
+            .iter()
+            .map(|option| localization.text(&option))
+            .collect::<Vec<_>>();
+        let groups = group_options(&options, &filter);
+        let summary = if loading {
+            localization.format("multi_dropdown.loading", &[("label", &label)])
+        } else if error.is_some() {
+            localization.format("multi_dropdown.unavailable", &[("label", &label)])
+        } else {
+            localization.format(
+                "multi_dropdown.summary",
+                &[
+                    ("label", &label),
+                    ("selected", &selected.len()),
+                    ("total", &options.len()),
+                ],
+            )
+        };
+
+        let retry = cx.create_callback(move |world: &mut World| {
+            let mut entity = world.entity_mut(id);
+            if let Some(mut source) = entity.get_mut::<TriviaSource>() {
+                source.reset();
+            }
+            entity.remove::<(Fetching, FetchError)>();
+        });
+        let on_filter = cx.create_callback(move |value: In<String>, world: &mut World| {
+            world
+                .entity_mut(id)
+                .insert(MultiDropdownFilter(value.0.clone()));
+        });
+        let select_all = cx.create_callback(move |world: &mut World| {
+            let visible = visible_options(world, id);
+            update_selected(world, id, |selected| selected.extend(visible));
+        });
+        let select_none = cx.create_callback(move |world: &mut World| {
+            let visible = visible_options(world, id);
+            update_selected(world, id, |selected| {
+                visible.iter().for_each(|i| {
+                    selected.remove(i);
+                })
+            });
+        });
+        let invert = cx.create_callback(move |world: &mut World| {
+            let visible = visible_options(world, id);
+            update_selected(world, id, |selected| {
+                for i in visible {
+                    if !selected.remove(&i) {
+                        selected.insert(i);
+                    }
+                }
+            });
+        });
 
         Element::<NodeBundle>::for_entity(id)
             .insert_dyn(Name::new, name.clone())
+            .insert_dyn(
+                |(label, summary)| MenuItemAccess::new(Role::ComboBox, label).value(summary),
+                (label.clone(), summary.clone()),
+            )
             .style((
                 style_multi_dropdown,
                 typography::text_default,
@@ -141,61 +292,240 @@ impl ViewTemplate for MultiDropdown {
             .insert_if(matches!(self.source, TriviaSource::String(_)), || {
                 DoneFetching
             })
-            .children(
+            .children((
                 MenuButton::new()
                     .style(menu_text_input_style)
-                    .children(label)
-                    .disabled(disabled)
+                    .children(summary)
+                    .disabled(disabled || loading || error.is_some())
                     .tab_index(tab_index)
                     .corners(corners)
                     .popup(
-                        MenuPopup::new().children(
-                            source
-                                .iter()
-                                .enumerate()
-                                .map(|(i, s)| {
-                                    let is_selected = selected.contains(&i);
-                                    Checkbox::new()
-                                        .checked(is_selected)
-                                        .label(s.clone())
-                                        .style(|sb: &mut StyleBuilder| {
-                                            sb.align_self(AlignSelf::Start)
-                                                .width(Val::Percent(100.0));
-                                        })
-                                        .on_change(cx.create_callback(
-                                            move |value: In<bool>, world: &mut World| {
-                                                let entity = &mut world.entity_mut(id);
-                                                let mut selected = entity
-                                                    .get_mut::<MultiDropdownSelected>()
-                                                    .expect(
-                                                        "MultiDropdownValues set by `create()`",
-                                                    );
-                                                if value.0 {
-                                                    selected.insert(i);
-                                                } else {
-                                                    selected.remove(&i);
-                                                }
-                                                let selected = (**selected).clone();
-                                                let mut source =
-                                                    entity.get_mut::<TriviaSource>().expect(
-                                                        "MultiDropdownValues set by `create()`",
-                                                    );
-                                                source.set_selected(selected);
-                                            },
-                                        ))
+                        MenuPopup::new().children((
+                            // Unnamed, so the filter isn't taken for a value of the form
+                            TextInput::new()
+                                .default_value(filter.0.clone())
+                                .on_change(on_filter)
+                                .style(style_multi_dropdown_filter)
+                                .size(Size::Md),
+                            Element::<NodeBundle>::new()
+                                .style(style_multi_dropdown_actions)
+                                .children((
+                                    Button::new()
+                                        .size(Size::Sm)
+                                        .on_click(select_all)
+                                        .children(localization.text("multi_dropdown.all")),
+                                    Button::new()
+                                        .size(Size::Sm)
+                                        .on_click(select_none)
+                                        .children(localization.text("multi_dropdown.none")),
+                                    Button::new()
+                                        .size(Size::Sm)
+                                        .on_click(invert)
+                                        .children(localization.text("multi_dropdown.invert")),
+                                )),
+                            ScrollView::new().style(style_multi_dropdown_list).children(
+                                groups
+                                    .into_iter()
+                                    .map(|group| {
+                                        OptionGroupView {
+                                            id,
+                                            group,
+                                            selected: selected.clone(),
+                                        }
                                         .into_view_child()
-                                })
-                                .collect::<Vec<_>>(),
-                        ),
+                                    })
+                                    .collect::<Vec<_>>(),
+                            ),
+                        )),
                     ),
-            )
+                Cond::new(
+                    error.is_some(),
+                    (
+                        Element::<NodeBundle>::new()
+                            .style_dyn(
+                                |destructive, ss| {
+                                    ss.color(destructive);
+                                },
+                                palette.destructive,
+                            )
+                            .children(
+                                error
+                                    .map(|error| error.localize(&localization))
+                                    .unwrap_or_default(),
+                            ),
+                        Button::new()
+                            .on_click(retry)
+                            .children(localization.text("dropdown.retry")),
+                    ),
+                    (),
+                ),
+                Cond::new(
+                    cached.is_some(),
+                    Element::<NodeBundle>::new().children(
+                        cached
+                            .map(|cached| cached_note(&localization, cached.0))
+                            .unwrap_or_default(),
+                    ),
+                    (),
+                ),
+            ))
+    }
+}
+
+/// The checkboxes of one [`OptionGroup`], with a header that toggles the whole group
+#[derive(Clone, PartialEq)]
+struct OptionGroupView {
+    id: Entity,
+    group: OptionGroup,
+    selected: MultiDropdownSelected,
+}
+
+impl ViewTemplate for OptionGroupView {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let id = self.id;
+        let indices = self
+            .group
+            .options
+            .iter()
+            .map(|(i, _)| *i)
+            .collect::<Vec<_>>();
+        let all_selected = indices.iter().all(|i| self.selected.contains(i));
+        let grouped = self.group.name.is_some();
+
+        Element::<NodeBundle>::new()
+            .style(style_multi_dropdown_group)
+            .children((
+                self.group.name.clone().map(|name| {
+                    Checkbox::new()
+                        .checked(all_selected)
+                        .label(name)
+                        .style(typography::text_strong)
+                        .on_change(
+                            cx.create_callback(move |value: In<bool>, world: &mut World| {
+                                update_selected(world, id, |selected| {
+                                    for i in indices.iter() {
+                                        if value.0 {
+                                            selected.insert(*i);
+                                        } else {
+                                            selected.remove(i);
+                                        }
+                                    }
+                                });
+                            }),
+                        )
+                }),
+                self.group
+                    .options
+                    .iter()
+                    .map(|(i, label)| {
+                        let i = *i;
+                        Checkbox::new()
+                            .checked(self.selected.contains(&i))
+                            .label(label.clone())
+                            .style(move |sb: &mut StyleBuilder| {
+                                sb.align_self(AlignSelf::Start).width(Val::Percent(100.0));
+                                if grouped {
+                                    sb.margin_left(16);
+                                }
+                            })
+                            .on_change(cx.create_callback(
+                                move |value: In<bool>, world: &mut World| {
+                                    update_selected(world, id, |selected| {
+                                        if value.0 {
+                                            selected.insert(i);
+                                        } else {
+                                            selected.remove(&i);
+                                        }
+                                    });
+                                },
+                            ))
+                            .into_view_child()
+                    })
+                    .collect::<Vec<_>>(),
+            ))
     }
 }
 
 fn style_multi_dropdown(ss: &mut StyleBuilder) {
     ss.display(Display::Flex)
         .flex_direction(FlexDirection::Row)
-        .width(Val::Percent(75.0))
         .align_items(AlignItems::Center)
         .column_gap(10);
 }
+
+fn style_multi_dropdown_filter(ss: &mut StyleBuilder) {
+    ss.width(Val::Percent(100.0)).margin_bottom(4);
+}
+
+fn style_multi_dropdown_actions(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Row)
+        .column_gap(4)
+        .margin_bottom(4);
+}
+
+fn style_multi_dropdown_list(ss: &mut StyleBuilder) {
+    ss.max_height(Val::Vh(60.0)).min_width(Val::Px(250.0));
+}
+
+fn style_multi_dropdown_group(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .align_items(AlignItems::Stretch);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(options: &[&str]) -> Vec<String> {
+        options.iter().map(|option| option.to_string()).collect()
+    }
+
+    fn labels(group: &OptionGroup) -> Vec<(usize, &str)> {
+        group
+            .options
+            .iter()
+            .map(|(i, label)| (*i, label.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_options_by_their_prefix() {
+        let options = options(&[
+            "Entertainment: Film",
+            "History",
+            "Entertainment: Music",
+            "Science: Computers",
+        ]);
+        let groups = group_options(&options, "");
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].name.as_deref(), Some("Entertainment"));
+        assert_eq!(labels(&groups[0]), [(0, "Film"), (2, "Music")]);
+        assert_eq!(groups[1].name, None);
+        assert_eq!(labels(&groups[1]), [(1, "History")]);
+        assert_eq!(groups[2].name.as_deref(), Some("Science"));
+        assert_eq!(labels(&groups[2]), [(3, "Computers")]);
+    }
+
+    #[test]
+    fn ungrouped_options_each_get_a_group() {
+        let groups = group_options(&options(&["Art", "History"]), "");
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.name.is_none()));
+    }
+
+    #[test]
+    fn filters_options_ignoring_case() {
+        let options = options(&["Entertainment: Film", "History", "Entertainment: Music"]);
+        let groups = group_options(&options, " MUS ");
+        assert_eq!(groups.len(), 1);
+        assert_eq!(labels(&groups[0]), [(2, "Music")]);
+        // The group name is searched too
+        let groups = group_options(&options, "entertain");
+        assert_eq!(labels(&groups[0]), [(0, "Film"), (2, "Music")]);
+        assert!(group_options(&options, "sport").is_empty());
+    }
+}
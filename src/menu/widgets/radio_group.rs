@@ -0,0 +1,201 @@
+use bevy::{ecs::system::SystemState, prelude::*};
+use bevy_mod_picking::prelude::{ListenerInput, On};
+use bevy_mod_stylebuilder::{StyleBuilder, StyleBuilderLayout, StyleHandle, StyleTuple};
+use bevy_quill::*;
+use bevy_quill_obsidian::{
+    controls::{Button, ButtonVariant, Disabled, IsDisabled},
+    focus::KeyPressEvent,
+    size::Size,
+    typography, RoundedCorners,
+};
+
+use crate::menu::widgets::UseComponentOrDefault;
+
+/// The index of the chosen option of a radio group
+#[derive(Component, Debug, Default, Clone, Copy, Reflect, Deref, DerefMut, PartialEq, Eq)]
+pub struct RadioGroupSelected(usize);
+
+/// Names for the options of a radio group that stay the same when options are
+/// reordered or relabelled, one for each option in order
+#[derive(Component, Debug, Default, Clone, Deref, PartialEq, Eq)]
+pub struct RadioGroupKeys(pub Vec<String>);
+
+impl RadioGroupKeys {
+    /// The key of the option at `index`
+    pub fn key(&self, index: usize) -> Option<&str> {
+        self.0.get(index).map(String::as_str)
+    }
+}
+
+/// Chooses option `index` of the radio group `id`, notifying `on_change`
+fn select_option(world: &mut World, id: Entity, index: usize, on_change: Option<Callback<usize>>) {
+    let mut selected = world
+        .get_mut::<RadioGroupSelected>(id)
+        .expect("RadioGroupSelected set by `create()`");
+    if **selected == index {
+        return;
+    }
+    **selected = index;
+    if let Some(on_change) = on_change {
+        world.run_callback(on_change, index);
+    }
+}
+
+/// A row of buttons of which exactly one is chosen.
+///
+/// Each button is its own tab stop, and ArrowLeft/ArrowRight move the choice
+/// while any of them has focus.
+#[derive(Default, Clone, PartialEq)]
+pub struct RadioGroup {
+    pub options: Vec<String>,
+    /// See [`RadioGroupKeys`], none if empty
+    pub keys: Vec<String>,
+    pub selected: usize,
+
+    pub size: Size,
+    pub disabled: bool,
+    pub style: StyleHandle,
+    pub tab_index: i32,
+    pub on_change: Option<Callback<usize>>,
+
+    pub name: String,
+}
+
+impl RadioGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn options(mut self, options: Vec<String>) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn keys(mut self, keys: Vec<String>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    pub fn selected(mut self, selected: usize) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    pub fn style<S: StyleTuple + 'static>(mut self, style: S) -> Self {
+        self.style = style.into_handle();
+        self
+    }
+
+    pub fn tab_index(mut self, tab_index: i32) -> Self {
+        self.tab_index = tab_index;
+        self
+    }
+
+    pub fn on_change(mut self, callback: Callback<usize>) -> Self {
+        self.on_change = Some(callback);
+        self
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+impl ViewTemplate for RadioGroup {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let id = cx.create_entity();
+        let count = self.options.len();
+        let selected = **cx.use_component_or(
+            id,
+            RadioGroupSelected(self.selected.min(count.saturating_sub(1))),
+        );
+        let on_change = self.on_change;
+        let size = self.size;
+        let disabled = self.disabled;
+        let tab_index = self.tab_index;
+        let last = self.options.len().saturating_sub(1);
+
+        Element::<NodeBundle>::for_entity(id)
+            .style((
+                typography::text_default,
+                style_radio_group,
+                self.style.clone(),
+            ))
+            .insert_dyn(Name::new, self.name.clone())
+            .insert_dyn(RadioGroupKeys, self.keys.clone())
+            .insert_if(disabled, || Disabled)
+            .insert_dyn(
+                move |_| {
+                    On::<KeyPressEvent>::run(move |world: &mut World| {
+                        if world.is_disabled(id) || count == 0 {
+                            return;
+                        }
+                        let mut state =
+                            SystemState::<ResMut<ListenerInput<KeyPressEvent>>>::new(world);
+                        let key_code = state.get_mut(world).key_code;
+                        let current = world
+                            .get::<RadioGroupSelected>(id)
+                            .map(|s| **s)
+                            .unwrap_or_default();
+                        let index = match key_code {
+                            KeyCode::ArrowLeft => current.checked_sub(1).unwrap_or(count - 1),
+                            KeyCode::ArrowRight => (current + 1) % count,
+                            _ => return,
+                        };
+                        state.get_mut(world).stop_propagation();
+                        select_option(world, id, index, on_change);
+                    })
+                },
+                (),
+            )
+            .children(
+                self.options
+                    .iter()
+                    .enumerate()
+                    .map(|(i, option)| {
+                        let corners = match i {
+                            _ if last == 0 => RoundedCorners::All,
+                            0 => RoundedCorners::Left,
+                            i if i == last => RoundedCorners::Right,
+                            _ => RoundedCorners::None,
+                        };
+                        Button::new()
+                            .size(size)
+                            .disabled(disabled)
+                            .tab_index(tab_index)
+                            .corners(corners)
+                            .variant(if i == selected {
+                                ButtonVariant::Selected
+                            } else {
+                                ButtonVariant::Default
+                            })
+                            .on_click(cx.create_callback(move |world: &mut World| {
+                                select_option(world, id, i, on_change);
+                            }))
+                            .children(option.clone())
+                            .into_view_child()
+                    })
+                    .collect::<Vec<_>>(),
+            )
+    }
+}
+
+fn style_radio_group(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Row)
+        .align_items(AlignItems::Center)
+        .column_gap(1);
+}
@@ -0,0 +1,506 @@
+use bevy::{a11y::Focus, ecs::system::SystemState, prelude::*};
+use bevy_mod_picking::{
+    events::{Down, Out, Pointer, Up},
+    prelude::{ListenerInput, On},
+};
+use bevy_mod_stylebuilder::{
+    StyleBuilder, StyleBuilderFont, StyleBuilderLayout, StyleBuilderOutline, StyleHandle,
+    StyleTuple,
+};
+use bevy_quill::*;
+use bevy_quill_obsidian::{
+    controls::{Button, Disabled, IsDisabled},
+    focus::{AutoFocus, KeyCharEvent, KeyPressEvent, TabIndex},
+    hooks::UseIsFocus,
+    size::Size,
+    typography,
+};
+
+use crate::{menu::widgets::UseComponentOrDefault, theme::use_theme};
+
+/// How long a -/+ button has to be held before it starts repeating
+const REPEAT_DELAY_SECS: f32 = 0.4;
+
+/// How often a held -/+ button repeats
+const REPEAT_INTERVAL_SECS: f32 = 0.08;
+
+/// How many steps PageUp/PageDown move at once
+const PAGE_STEPS: i64 = 10;
+
+#[derive(Component, Debug, Default, Clone, Copy, Reflect, Deref, DerefMut, PartialEq, Eq)]
+pub struct StepperValue(i64);
+
+/// Digits typed while the stepper is focused, applied on Enter, the next step,
+/// or when the stepper loses focus
+#[derive(Component, Debug, Default, Clone, Deref, DerefMut, PartialEq, Eq)]
+struct StepperEntry(String);
+
+/// A -/+ button is being held down
+#[derive(Component, Debug, Clone)]
+pub(crate) struct StepperRepeat {
+    steps: i64,
+    timer: Timer,
+}
+
+/// The limits of a stepper, kept on its entity for the event handlers and systems
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+struct StepperRange {
+    min: i64,
+    max: i64,
+    step: i64,
+    on_change: Option<Callback<i64>>,
+}
+
+impl StepperRange {
+    fn clamp(&self, value: i64) -> i64 {
+        value.clamp(self.min, self.max)
+    }
+
+    /// `value` moved by `steps` steps, without overflowing
+    fn step(&self, value: i64, steps: i64) -> i64 {
+        value.saturating_add(steps.saturating_mul(self.step))
+    }
+
+    /// `value` clamped, then rounded to the nearest step up from `min` that's
+    /// still in range, for values that were typed rather than stepped to
+    fn snap(&self, value: i64) -> i64 {
+        let value = self.clamp(value);
+        let (min, step) = (i128::from(self.min), i128::from(self.step.max(1)));
+        let mut snapped = min + (i128::from(value) - min + step / 2) / step * step;
+        if snapped > i128::from(self.max) {
+            snapped -= step;
+        }
+        snapped as i64
+    }
+}
+
+/// Sets the value of the stepper `id`, clamped to its range, notifying `on_change`
+fn set_value(world: &mut World, id: Entity, value: impl FnOnce(i64, &StepperRange) -> i64) {
+    let Some(range) = world.get::<StepperRange>(id).copied() else {
+        return;
+    };
+    let mut current = world
+        .get_mut::<StepperValue>(id)
+        .expect("StepperValue set by `create()`");
+    let next = range.clamp(value(**current, &range));
+    if **current == next {
+        return;
+    }
+    **current = next;
+    if let Some(on_change) = range.on_change {
+        world.run_callback(on_change, next);
+    }
+}
+
+/// Whether the stepper `id` can move by `steps` right now: it isn't disabled and
+/// isn't already at the limit in that direction
+fn can_step(world: &World, id: Entity, steps: i64) -> bool {
+    if world.is_disabled(id) {
+        return false;
+    }
+    match (world.get::<StepperRange>(id), world.get::<StepperValue>(id)) {
+        (Some(range), Some(value)) if steps < 0 => **value > range.min,
+        (Some(range), Some(value)) => **value < range.max,
+        _ => false,
+    }
+}
+
+/// Applies the digits typed into the stepper `id`, if any
+fn commit_entry(world: &mut World, id: Entity) {
+    let Some(mut entry) = world.get_mut::<StepperEntry>(id) else {
+        return;
+    };
+    if entry.is_empty() {
+        return;
+    }
+    let typed = std::mem::take(&mut **entry).parse::<i64>();
+    if let Ok(typed) = typed {
+        set_value(world, id, |_, range| range.snap(typed));
+    }
+}
+
+#[derive(Default, Clone, PartialEq)]
+pub struct Stepper {
+    pub value: i64,
+    pub min: i64,
+    pub max: i64,
+    pub step: i64,
+
+    pub size: Size,
+    pub disabled: bool,
+    pub style: StyleHandle,
+    pub tab_index: i32,
+    pub auto_focus: bool,
+    pub on_change: Option<Callback<i64>>,
+
+    pub name: String,
+}
+
+impl Stepper {
+    pub fn new() -> Self {
+        Self {
+            max: i64::MAX,
+            min: i64::MIN,
+            step: 1,
+            ..default()
+        }
+    }
+
+    pub fn value(mut self, value: i64) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn range(mut self, min: i64, max: i64) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn step(mut self, step: i64) -> Self {
+        self.step = step.max(1);
+        self
+    }
+
+    pub fn size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    pub fn style<S: StyleTuple + 'static>(mut self, style: S) -> Self {
+        self.style = style.into_handle();
+        self
+    }
+
+    pub fn tab_index(mut self, tab_index: i32) -> Self {
+        self.tab_index = tab_index;
+        self
+    }
+
+    pub fn auto_focus(mut self, auto_focus: bool) -> Self {
+        self.auto_focus = auto_focus;
+        self
+    }
+
+    pub fn on_change(mut self, callback: Callback<i64>) -> Self {
+        self.on_change = Some(callback);
+        self
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+impl ViewTemplate for Stepper {
+    type View = impl View;
+
+    fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
+        let id = cx.create_entity();
+        let range = StepperRange {
+            min: self.min,
+            max: self.max,
+            step: self.step,
+            on_change: self.on_change,
+        };
+        let value = **cx.use_component_or(id, StepperValue(range.clamp(self.value)));
+        let entry = cx.use_component_or_default::<StepperEntry>(id).0.clone();
+        let focused = cx.is_focus_visible(id);
+        let focus = use_theme(cx).palette.focus;
+        let size = self.size;
+        let disabled = self.disabled;
+
+        let display = if entry.is_empty() {
+            value.to_string()
+        } else {
+            entry
+        };
+
+        Element::<NodeBundle>::for_entity(id)
+            .style((
+                typography::text_default,
+                style_stepper,
+                move |ss: &mut StyleBuilder| {
+                    ss.min_height(size.height()).font_size(size.font_size());
+                },
+                self.style.clone(),
+            ))
+            .insert_dyn(Name::new, self.name.clone())
+            .insert_dyn(TabIndex, self.tab_index)
+            .insert_dyn(move |range| range, range)
+            .insert_if(disabled, || Disabled)
+            .insert_if(self.auto_focus, || AutoFocus)
+            .style_dyn(
+                |(focused, focus), sb| {
+                    if focused {
+                        sb.outline_color(focus).outline_width(2).outline_offset(2);
+                    } else {
+                        sb.outline_color(Option::<Color>::None);
+                    }
+                },
+                (focused, focus),
+            )
+            .insert_dyn(
+                move |_| {
+                    (
+                        On::<KeyPressEvent>::run(move |world: &mut World| {
+                            if world.is_disabled(id) {
+                                return;
+                            }
+                            let mut state =
+                                SystemState::<ResMut<ListenerInput<KeyPressEvent>>>::new(world);
+                            let key_code = state.get_mut(world).key_code;
+                            let typing = world
+                                .get::<StepperEntry>(id)
+                                .is_some_and(|entry| !entry.is_empty());
+                            let steps = match key_code {
+                                KeyCode::ArrowLeft | KeyCode::Minus | KeyCode::NumpadSubtract => -1,
+                                KeyCode::ArrowRight | KeyCode::Equal | KeyCode::NumpadAdd => 1,
+                                KeyCode::PageDown => -PAGE_STEPS,
+                                KeyCode::PageUp => PAGE_STEPS,
+                                KeyCode::Home => i64::MIN,
+                                KeyCode::End => i64::MAX,
+                                KeyCode::Enter if typing => 0,
+                                KeyCode::Backspace | KeyCode::Escape if typing => {
+                                    state.get_mut(world).stop_propagation();
+                                    let mut entry = world
+                                        .get_mut::<StepperEntry>(id)
+                                        .expect("StepperEntry set by `create()`");
+                                    if key_code == KeyCode::Escape {
+                                        entry.clear();
+                                    } else {
+                                        entry.pop();
+                                    }
+                                    return;
+                                }
+                                _ => return,
+                            };
+                            state.get_mut(world).stop_propagation();
+                            commit_entry(world, id);
+                            set_value(world, id, |value, range| match steps {
+                                i64::MIN => range.min,
+                                i64::MAX => range.max,
+                                steps => range.step(value, steps),
+                            });
+                        }),
+                        // Typing digits replaces the value once committed, `-` is taken by stepping down
+                        On::<KeyCharEvent>::run(move |world: &mut World| {
+                            if world.is_disabled(id) {
+                                return;
+                            }
+                            let mut state = SystemState::<(
+                                ResMut<ListenerInput<KeyCharEvent>>,
+                                Query<&mut StepperEntry>,
+                            )>::new(world);
+                            let (mut event, mut entries) = state.get_mut(world);
+                            let key = event.key;
+                            let Ok(mut entry) = entries.get_mut(id) else {
+                                return;
+                            };
+                            if key.is_ascii_digit() {
+                                entry.push(key);
+                                event.stop_propagation();
+                            }
+                        }),
+                    )
+                },
+                (),
+            )
+            .children((
+                StepperButton {
+                    stepper: id,
+                    steps: -1,
+                    disabled: disabled || value <= self.min,
+                    size,
+                },
+                Element::<NodeBundle>::new()
+                    .style(style_stepper_value)
+                    .children(display),
+                StepperButton {
+                    stepper: id,
+                    steps: 1,
+                    disabled: disabled || value >= self.max,
+                    size,
+                },
+            ))
+    }
+}
+
+/// A -/+ button that steps once when pressed and repeats while held
+#[derive(Clone, PartialEq)]
+struct StepperButton {
+    stepper: Entity,
+    steps: i64,
+    disabled: bool,
+    size: Size,
+}
+
+impl ViewTemplate for StepperButton {
+    type View = impl View;
+
+    fn create(&self, _cx: &mut bevy_quill::Cx) -> Self::View {
+        let stepper = self.stepper;
+        let steps = self.steps;
+
+        Element::<NodeBundle>::new()
+            .insert_dyn(
+                move |_| {
+                    (
+                        On::<Pointer<Down>>::run(move |world: &mut World| {
+                            // Checked now rather than when the button was built, which
+                            // may be a frame behind the stepper's value
+                            if !can_step(world, stepper, steps) {
+                                return;
+                            }
+                            commit_entry(world, stepper);
+                            set_value(world, stepper, |value, range| range.step(value, steps));
+                            world.entity_mut(stepper).insert(StepperRepeat {
+                                steps,
+                                timer: Timer::from_seconds(REPEAT_DELAY_SECS, TimerMode::Once),
+                            });
+                        }),
+                        On::<Pointer<Up>>::run(move |world: &mut World| {
+                            world.entity_mut(stepper).remove::<StepperRepeat>();
+                        }),
+                        On::<Pointer<Out>>::run(move |world: &mut World| {
+                            world.entity_mut(stepper).remove::<StepperRepeat>();
+                        }),
+                    )
+                },
+                (),
+            )
+            .children(
+                Button::new()
+                    .size(self.size)
+                    .disabled(self.disabled)
+                    .tab_index(-1)
+                    .children(if steps < 0 { "-" } else { "+" }),
+            )
+    }
+}
+
+/// Repeats the step of any held -/+ button
+pub(crate) fn repeat_stepper(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut steppers: Query<(Entity, &mut StepperRepeat)>,
+) {
+    for (entity, mut repeat) in steppers.iter_mut() {
+        repeat.timer.tick(time.delta());
+        if !repeat.timer.finished() {
+            continue;
+        }
+        repeat.timer = Timer::from_seconds(REPEAT_INTERVAL_SECS, TimerMode::Once);
+        let steps = repeat.steps;
+        commands.add(move |world: &mut World| {
+            if !can_step(world, entity, steps) {
+                world.entity_mut(entity).remove::<StepperRepeat>();
+                return;
+            }
+            set_value(world, entity, |value, range| range.step(value, steps));
+        });
+    }
+}
+
+/// Applies the digits typed into steppers that lost focus, so their value
+/// doesn't fall behind what they show
+pub(crate) fn commit_unfocused_steppers(
+    mut commands: Commands,
+    focus: Res<Focus>,
+    entries: Query<(Entity, &StepperEntry)>,
+) {
+    for (entity, entry) in entries.iter() {
+        if !entry.is_empty() && focus.0 != Some(entity) {
+            commands.add(move |world: &mut World| commit_entry(world, entity));
+        }
+    }
+}
+
+fn style_stepper(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Row)
+        .align_items(AlignItems::Center)
+        .column_gap(10);
+}
+
+fn style_stepper_value(ss: &mut StyleBuilder) {
+    ss.min_width(Val::Px(60.0))
+        .justify_content(JustifyContent::Center)
+        .display(Display::Flex);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(min: i64, max: i64, step: i64) -> StepperRange {
+        StepperRange {
+            min,
+            max,
+            step,
+            on_change: None,
+        }
+    }
+
+    #[test]
+    fn typed_values_are_clamped() {
+        let range = range(1, 50, 1);
+        assert_eq!(range.snap(0), 1);
+        assert_eq!(range.snap(20), 20);
+        assert_eq!(range.snap(500), 50);
+    }
+
+    #[test]
+    fn typed_values_are_snapped_to_a_step() {
+        let range = range(5, 50, 5);
+        assert_eq!(range.snap(12), 10);
+        assert_eq!(range.snap(13), 15);
+        assert_eq!(range.snap(7), 5);
+        assert_eq!(range.snap(49), 50);
+    }
+
+    #[test]
+    fn snapping_stays_in_range() {
+        assert_eq!(range(0, 9, 5).snap(9), 5);
+        let unbounded = range(i64::MIN, i64::MAX, 1);
+        assert_eq!(unbounded.snap(i64::MAX), i64::MAX);
+        assert_eq!(unbounded.snap(i64::MIN), i64::MIN);
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Focus>()
+            .add_systems(Update, commit_unfocused_steppers);
+        app
+    }
+
+    fn stepper(app: &mut App, typed: &str) -> Entity {
+        app.world_mut()
+            .spawn((
+                range(1, 50, 1),
+                StepperValue(10),
+                StepperEntry(typed.to_string()),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn commits_what_was_typed_when_focus_moves_away() {
+        let mut app = app();
+        let stepper = stepper(&mut app, "70");
+        app.world_mut().resource_mut::<Focus>().0 = Some(stepper);
+        app.update();
+        assert_eq!(**app.world().get::<StepperValue>(stepper).unwrap(), 10);
+
+        app.world_mut().resource_mut::<Focus>().0 = None;
+        app.update();
+        assert_eq!(**app.world().get::<StepperValue>(stepper).unwrap(), 50);
+        assert!(app.world().get::<StepperEntry>(stepper).unwrap().is_empty());
+    }
+}
@@ -1,15 +1,18 @@
+use std::ops::Range;
+
 use bevy::{
     a11y::{
         accesskit::{NodeBuilder, Role},
         AccessibilityNode, Focus,
     },
-    ecs::system::SystemState,
+    ecs::{query::QueryData, system::SystemState},
     prelude::*,
-    text::BreakLineOn,
+    text::{BreakLineOn, TextLayoutInfo},
     ui,
+    window::{Ime, PrimaryWindow},
 };
 use bevy_mod_picking::{
-    events::{Click, Pointer},
+    events::{Click, Down, Drag, Pointer},
     prelude::{ListenerInput, On},
 };
 use bevy_mod_stylebuilder::{
@@ -29,14 +32,126 @@ use bevy_quill_obsidian::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{loading::MenuAssets, menu::widgets::UseComponentOrDefault};
+use crate::{
+    loading::MenuAssets,
+    locale::Localization,
+    menu::widgets::{
+        clipboard::Clipboard,
+        validator::{CustomValidators, ValidationError, Validator},
+        UseComponentOrDefault,
+    },
+    theme::use_theme,
+};
+
+/// How many edits can be undone
+const MAX_UNDO: usize = 100;
 
 #[derive(Component, Debug, Default, Clone, Deref, DerefMut, PartialEq, Eq, Reflect)]
 pub struct TextInputValue(String);
 
+/// The cursor position, counted in `char`s rather than bytes
 #[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut, PartialEq, Eq, Reflect)]
 pub struct TextInputCursorPos(usize);
 
+/// The other end of the selection, the cursor being the end that moves.
+/// There is no selection when this is `None` or equal to the cursor
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut, PartialEq, Eq, Reflect)]
+pub struct TextInputSelection(Option<usize>);
+
+/// Text being composed by an input method, shown at the cursor until it is committed
+#[derive(Component, Debug, Default, Clone, Deref, DerefMut, PartialEq, Eq, Reflect)]
+pub struct TextInputPreedit(String);
+
+/// Text that went into the input this frame, from key presses and from the input
+/// method. Some platforms send what an input method commits as key presses too,
+/// so each skips what the other already inserted.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq, Reflect)]
+pub struct TextInputFrameText {
+    typed: String,
+    committed: String,
+}
+
+impl TextInputFrameText {
+    /// Whether a typed `key` should be inserted, rather than being the next
+    /// character of text the input method already committed
+    fn type_char(&mut self, key: char) -> bool {
+        if let Some(rest) = self.committed.strip_prefix(key) {
+            self.committed = rest.to_string();
+            return false;
+        }
+        self.typed.push(key);
+        true
+    }
+
+    /// Whether `text` committed by the input method should be inserted, rather
+    /// than having been typed already
+    fn commit(&mut self, text: &str) -> bool {
+        if let Some(rest) = self.typed.strip_prefix(text).filter(|_| !text.is_empty()) {
+            self.typed = rest.to_string();
+            return false;
+        }
+        self.committed.push_str(text);
+        true
+    }
+}
+
+#[derive(Component, Debug, Default, Clone, Copy, Deref, PartialEq, Eq, Reflect)]
+pub struct TextInputMaxLength(Option<usize>);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+enum EditKind {
+    #[default]
+    None,
+    Typing,
+    Deleting,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+struct TextInputSnapshot {
+    value: String,
+    cursor: usize,
+}
+
+#[derive(Component, Debug, Default, Clone, Reflect)]
+pub struct TextInputHistory {
+    undo: Vec<TextInputSnapshot>,
+    redo: Vec<TextInputSnapshot>,
+    last_edit: EditKind,
+}
+
+impl TextInputHistory {
+    /// Remembers `value` before an edit of the given kind.
+    /// Runs of typing or deleting are undone as a single step
+    fn record(&mut self, value: &str, cursor: usize, kind: EditKind) {
+        self.redo.clear();
+        if kind != EditKind::Other && kind == self.last_edit {
+            return;
+        }
+        self.undo.push(TextInputSnapshot {
+            value: value.to_string(),
+            cursor,
+        });
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.last_edit = kind;
+    }
+}
+
+/// The text entity rendering an input, used to map pointer positions to characters
+#[derive(Component, Debug, Clone, Copy, Deref, PartialEq, Eq)]
+struct TextInputText(Entity);
+
+/// For each section of the rendered text, the position of its first character in the
+/// value, or `None` for sections that aren't part of the value, like the cursor
+#[derive(Component, Debug, Default, Clone, Copy, Deref, PartialEq, Eq)]
+struct TextInputSectionOffsets([Option<usize>; 6]);
+
+/// The value last passed to `on_change`
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+struct TextInputReported(String);
+
 #[derive(Component, Debug, Reflect)]
 pub struct TextInputCursorTimer {
     pub timer: Timer,
@@ -44,8 +159,9 @@ pub struct TextInputCursorTimer {
     should_reset: bool,
 }
 
-#[derive(Component, Debug, Default, Clone, Reflect)]
-pub struct TextInputValueInvalid;
+/// The value failed validation, holds the message to show the player
+#[derive(Component, Debug, Default, Clone, Deref, PartialEq, Eq, Reflect)]
+pub struct TextInputValueInvalid(pub String);
 
 impl Default for TextInputCursorTimer {
     fn default() -> Self {
@@ -71,10 +187,20 @@ impl TextInputType {
     }
 
     pub fn is_valid(&self, v: &str) -> bool {
+        self.validate(v).is_ok()
+    }
+
+    pub fn validate(&self, v: &str) -> Result<(), ValidationError> {
         match self {
-            Self::Text => true,
-            Self::Integer => v.parse::<i32>().is_ok(),
-            Self::Float => v.parse::<f32>().is_ok(),
+            Self::Text => Ok(()),
+            Self::Integer => v
+                .parse::<i32>()
+                .map(|_| ())
+                .map_err(|_| ValidationError::new("validator.whole_number")),
+            Self::Float => v
+                .parse::<f32>()
+                .map(|_| ())
+                .map_err(|_| ValidationError::new("validator.number")),
         }
     }
 }
@@ -82,6 +208,277 @@ impl TextInputType {
 #[derive(Component, Debug, Default, Clone, Reflect)]
 pub struct Placeholder(String);
 
+/// Everything an edit touches on a text input entity
+#[derive(QueryData)]
+#[query_data(mutable)]
+pub struct TextInputEditor {
+    value: &'static mut TextInputValue,
+    cursor: &'static mut TextInputCursorPos,
+    selection: &'static mut TextInputSelection,
+    history: &'static mut TextInputHistory,
+    timer: &'static mut TextInputCursorTimer,
+    max_length: &'static TextInputMaxLength,
+}
+
+impl TextInputEditorItem<'_> {
+    fn editor(&mut self) -> Editor<'_> {
+        self.timer.should_reset = true;
+        Editor {
+            value: &mut self.value.0,
+            cursor: &mut self.cursor.0,
+            anchor: &mut self.selection.0,
+            history: &mut *self.history,
+            max_length: self.max_length.0,
+        }
+    }
+
+    /// Replaces the value from outside the input, as when a form is filled in.
+    /// The cursor goes to the end, and the old value can't be undone back to.
+    pub fn set_value(&mut self, value: &str) {
+        let value: String = match self.max_length.0 {
+            Some(length) => value.chars().take(length).collect(),
+            None => value.to_string(),
+        };
+        self.cursor.0 = value.chars().count();
+        self.selection.0 = None;
+        *self.history = TextInputHistory::default();
+        self.value.0 = value;
+    }
+}
+
+/// Editing operations on a single line of text, with positions counted in `char`s
+struct Editor<'a> {
+    value: &'a mut String,
+    cursor: &'a mut usize,
+    anchor: &'a mut Option<usize>,
+    history: &'a mut TextInputHistory,
+    max_length: Option<usize>,
+}
+
+impl Editor<'_> {
+    fn chars(&self) -> Vec<char> {
+        self.value.chars().collect()
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    fn selection(&self) -> Option<Range<usize>> {
+        let anchor = (*self.anchor)?;
+        let cursor = *self.cursor;
+        (anchor != cursor).then(|| anchor.min(cursor)..anchor.max(cursor))
+    }
+
+    fn selected_text(&self) -> Option<String> {
+        let range = self.selection()?;
+        Some(
+            self.value
+                .chars()
+                .skip(range.start)
+                .take(range.len())
+                .collect(),
+        )
+    }
+
+    /// Moves the cursor, extending the selection instead of clearing it if `extend` is set
+    fn move_to(&mut self, pos: usize, extend: bool) {
+        if !extend {
+            *self.anchor = None;
+        } else if self.anchor.is_none() {
+            *self.anchor = Some(*self.cursor);
+        }
+        *self.cursor = pos.min(self.len());
+        self.history.last_edit = EditKind::None;
+    }
+
+    fn move_left(&mut self, word: bool, extend: bool) {
+        let pos = match self.selection() {
+            Some(range) if !extend && !word => range.start,
+            _ if word => prev_word_boundary(&self.chars(), *self.cursor),
+            _ => self.cursor.saturating_sub(1),
+        };
+        self.move_to(pos, extend);
+    }
+
+    fn move_right(&mut self, word: bool, extend: bool) {
+        let pos = match self.selection() {
+            Some(range) if !extend && !word => range.end,
+            _ if word => next_word_boundary(&self.chars(), *self.cursor),
+            _ => *self.cursor + 1,
+        };
+        self.move_to(pos, extend);
+    }
+
+    fn select_all(&mut self) {
+        *self.anchor = Some(0);
+        *self.cursor = self.len();
+    }
+
+    /// Replaces `range` with `text`, truncating it to fit `max_length`
+    fn replace(&mut self, range: Range<usize>, text: &str, kind: EditKind) -> bool {
+        let room = self.max_length.map_or(usize::MAX, |max| {
+            max.saturating_sub(self.len() - range.len())
+        });
+        let text = text
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(room)
+            .collect::<String>();
+        if range.is_empty() && text.is_empty() {
+            return false;
+        }
+        self.history.record(self.value, *self.cursor, kind);
+        let start = byte_index(self.value, range.start);
+        let end = byte_index(self.value, range.end);
+        self.value.replace_range(start..end, &text);
+        *self.cursor = range.start + text.chars().count();
+        *self.anchor = None;
+        true
+    }
+
+    /// Inserts `text` at the cursor, replacing the selection if there is one
+    fn insert(&mut self, text: &str, kind: EditKind) -> bool {
+        let range = self.selection().unwrap_or(*self.cursor..*self.cursor);
+        self.replace(range, text, kind)
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some(range) => self.replace(range, "", EditKind::Other),
+            None => false,
+        }
+    }
+
+    fn delete_backward(&mut self, word: bool) -> bool {
+        let range = self.selection().unwrap_or_else(|| {
+            let start = if word {
+                prev_word_boundary(&self.chars(), *self.cursor)
+            } else {
+                self.cursor.saturating_sub(1)
+            };
+            start..*self.cursor
+        });
+        self.replace(range, "", EditKind::Deleting)
+    }
+
+    fn delete_forward(&mut self, word: bool) -> bool {
+        let range = self.selection().unwrap_or_else(|| {
+            let end = if word {
+                next_word_boundary(&self.chars(), *self.cursor)
+            } else {
+                (*self.cursor + 1).min(self.len())
+            };
+            *self.cursor..end
+        });
+        self.replace(range, "", EditKind::Deleting)
+    }
+
+    fn restore(&mut self, snapshot: TextInputSnapshot) {
+        *self.value = snapshot.value;
+        *self.cursor = snapshot.cursor;
+        *self.anchor = None;
+        self.history.last_edit = EditKind::None;
+    }
+
+    fn snapshot(&self) -> TextInputSnapshot {
+        TextInputSnapshot {
+            value: self.value.clone(),
+            cursor: *self.cursor,
+        }
+    }
+
+    fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.history.undo.pop() else {
+            return false;
+        };
+        let current = self.snapshot();
+        self.history.redo.push(current);
+        self.restore(snapshot);
+        true
+    }
+
+    fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.history.redo.pop() else {
+            return false;
+        };
+        let current = self.snapshot();
+        self.history.undo.push(current);
+        self.restore(snapshot);
+        true
+    }
+}
+
+fn byte_index(value: &str, char_pos: usize) -> usize {
+    value
+        .char_indices()
+        .nth(char_pos)
+        .map_or(value.len(), |(i, _)| i)
+}
+
+fn prev_word_boundary(chars: &[char], pos: usize) -> usize {
+    let mut i = pos.min(chars.len());
+    while i > 0 && !chars[i - 1].is_alphanumeric() {
+        i -= 1;
+    }
+    while i > 0 && chars[i - 1].is_alphanumeric() {
+        i -= 1;
+    }
+    i
+}
+
+fn next_word_boundary(chars: &[char], pos: usize) -> usize {
+    let mut i = pos.min(chars.len());
+    while i < chars.len() && !chars[i].is_alphanumeric() {
+        i += 1;
+    }
+    while i < chars.len() && chars[i].is_alphanumeric() {
+        i += 1;
+    }
+    i
+}
+
+/// Control on most platforms, Command on macOS. Not while Alt is held too,
+/// since AltGr comes through as Ctrl+Alt on Windows and is used for typing.
+fn shortcut_modifier(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]) && !keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+}
+
+/// Finds the character under `position`, in logical window coordinates
+fn char_index_at(world: &mut World, text: Entity, position: Vec2) -> Option<usize> {
+    let scale_factor = world
+        .query_filtered::<&Window, With<PrimaryWindow>>()
+        .get_single(world)
+        .map_or(1.0, |window| window.scale_factor());
+    let entity = world.get_entity(text)?;
+    let node = entity.get::<Node>()?;
+    let transform = entity.get::<GlobalTransform>()?;
+    let layout = entity.get::<TextLayoutInfo>()?;
+    let text = entity.get::<Text>()?;
+    let offsets = entity.get::<TextInputSectionOffsets>()?;
+
+    // Glyphs are laid out in physical pixels, relative to the top left of the node
+    let x = (position.x - node.logical_rect(transform).min.x) * scale_factor;
+    let mut last = None;
+    for glyph in layout.glyphs.iter() {
+        let Some(offset) = offsets[glyph.section_index] else {
+            continue;
+        };
+        let section = &text.sections[glyph.section_index].value;
+        let index = offset + section[..glyph.byte_index].chars().count();
+        if glyph.position.x >= x {
+            return Some(index);
+        }
+        last = Some(index + 1);
+    }
+    last
+}
+
 #[derive(Default, Clone, PartialEq)]
 pub struct TextInput {
     pub default_value: String,
@@ -93,9 +490,12 @@ pub struct TextInput {
     pub auto_focus: bool,
     pub minimal: bool,
     pub on_submit: Option<Callback>,
+    pub on_change: Option<Callback<String>>,
     pub max_length: Option<usize>,
     pub name: String,
     pub type_: TextInputType,
+    pub validators: Vec<Validator>,
+    pub on_validate: Option<Callback<Option<String>>>,
 }
 
 impl TextInput {
@@ -128,6 +528,11 @@ impl TextInput {
         self
     }
 
+    pub fn on_change(mut self, callback: Callback<String>) -> Self {
+        self.on_change = Some(callback);
+        self
+    }
+
     pub fn tab_index(mut self, tab_index: i32) -> Self {
         self.tab_index = tab_index;
         self
@@ -162,6 +567,17 @@ impl TextInput {
         self.type_ = type_;
         self
     }
+
+    pub fn validators(mut self, validators: Vec<Validator>) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    /// Called with the validation message whenever it changes, `None` once the value is valid
+    pub fn on_validate(mut self, callback: Callback<Option<String>>) -> Self {
+        self.on_validate = Some(callback);
+        self
+    }
 }
 
 fn style_text_input(ss: &mut StyleBuilder) {
@@ -184,6 +600,7 @@ fn fetch_value_and_cursor_pos<'c>(
     cx: &'c mut Cx,
     id: Entity,
     default: String,
+    max_length: Option<usize>,
 ) -> (&'c TextInputValue, usize) {
     let mut ent = cx.world_mut().entity_mut(id);
     if !ent.contains::<TextInputValue>() {
@@ -191,8 +608,17 @@ fn fetch_value_and_cursor_pos<'c>(
     }
     if !ent.contains::<TextInputCursorPos>() {
         let value = ent.get::<TextInputValue>().unwrap();
-        ent.insert(TextInputCursorPos(value.len()));
+        ent.insert(TextInputCursorPos(value.chars().count()));
     }
+    if !ent.contains::<TextInputHistory>() {
+        ent.insert((
+            TextInputSelection::default(),
+            TextInputHistory::default(),
+            TextInputPreedit::default(),
+            TextInputFrameText::default(),
+        ));
+    }
+    ent.insert(TextInputMaxLength(max_length));
 
     let cursor_pos = **cx.use_component::<TextInputCursorPos>(id).unwrap();
     let value = cx.use_component::<TextInputValue>(id).unwrap();
@@ -204,6 +630,8 @@ impl ViewTemplate for TextInput {
 
     fn create(&self, cx: &mut bevy_quill::Cx) -> Self::View {
         let id = cx.create_entity();
+        let text_id = cx.create_entity();
+        let palette = use_theme(cx).palette;
 
         let max_length = self.max_length;
         let default_value = max_length
@@ -213,23 +641,84 @@ impl ViewTemplate for TextInput {
         let _invalid = cx.world_mut().init_component::<TextInputValueInvalid>();
         let _is_invalid: Option<TextInputValueInvalid> =
             cx.use_component::<TextInputValueInvalid>(id).cloned();
-        let (value, cursor_pos) = fetch_value_and_cursor_pos(cx, id, default_value);
+        let (value, cursor_pos) = fetch_value_and_cursor_pos(cx, id, default_value, max_length);
+        let value = value.clone();
+        let anchor = **cx.use_component::<TextInputSelection>(id).unwrap();
+        let preedit = cx.use_component::<TextInputPreedit>(id).unwrap().0.clone();
 
-        let before = value.chars().take(cursor_pos).collect::<String>();
-        let after = value
-            .chars()
-            .skip(cursor_pos)
-            .chain(std::iter::once(' '))
-            .collect::<String>();
+        // Split the value around the cursor and the selection, see `TextInputSectionOffsets`
+        let chars = value.chars().collect::<Vec<_>>();
+        let cursor_pos = cursor_pos.min(chars.len());
+        let anchor = anchor.unwrap_or(cursor_pos).min(chars.len());
+        let selection_start = anchor.min(cursor_pos);
+        let selection_end = anchor.max(cursor_pos);
+        let sections = [
+            chars[..selection_start].iter().collect::<String>(),
+            chars[selection_start..cursor_pos]
+                .iter()
+                .collect::<String>(),
+            "|".to_string(),
+            preedit,
+            chars[cursor_pos..selection_end].iter().collect::<String>(),
+            chars[selection_end..]
+                .iter()
+                .chain(std::iter::once(&' '))
+                .collect::<String>(),
+        ];
+        let offsets = TextInputSectionOffsets([
+            Some(0),
+            Some(selection_start),
+            None,
+            None,
+            Some(cursor_pos),
+            Some(selection_end),
+        ]);
 
-        let my_value = value.clone();
-        let is_valid = self.type_.is_valid(&my_value);
+        let my_value = (*value).clone();
+        let type_ = self.type_;
+        let localization = cx.use_resource::<Localization>().clone();
+        let custom = cx.use_resource::<CustomValidators>();
+        let error = type_.validate(&my_value).and_then(|_| {
+            self.validators
+                .iter()
+                .try_for_each(|validator| validator.validate(&my_value, custom))
+        });
+        let error = error.err().map(|error| error.localize(&localization));
+        let is_valid = error.is_none();
+        let on_validate = self.on_validate;
+        cx.create_effect_ext(
+            move |world: &mut World, (error,)| {
+                match &error {
+                    None => {
+                        world.entity_mut(id).remove::<TextInputValueInvalid>();
+                    }
+                    Some(message) => {
+                        world
+                            .entity_mut(id)
+                            .insert(TextInputValueInvalid(message.clone()));
+                    }
+                }
+                if let Some(on_validate) = on_validate {
+                    world.run_callback(on_validate, error);
+                }
+            },
+            (error,),
+            EffectOptions {
+                run_immediately: true,
+            },
+        );
+        // Report every change, whether it came from typing, the clipboard, an IME or undo
+        let on_change = self.on_change;
         cx.create_effect_ext(
-            |world, (value,)| {
-                if self.type_.is_valid(&value) {
-                    world.entity_mut(id).remove::<TextInputValueInvalid>();
-                } else {
-                    world.entity_mut(id).insert(TextInputValueInvalid);
+            move |world: &mut World, (value,)| {
+                let Some(on_change) = on_change else {
+                    return;
+                };
+                let mut ent = world.entity_mut(id);
+                let unchanged = ent.get::<TextInputReported>().map(|r| r.0 == value);
+                ent.insert(TextInputReported(value.clone()));
+                if unchanged == Some(false) {
+                    world.run_callback(on_change, value);
                 }
             },
             (my_value,),
@@ -272,6 +761,7 @@ impl ViewTemplate for TextInput {
             .insert_dyn(
                 move |_| {
                     (
+                        TextInputText(text_id),
                         AccessibilityNode::from(NodeBuilder::new(Role::TextInput)),
                         On::<Pointer<Click>>::run(move |world: &mut World| {
                             let mut focus = world.get_resource_mut::<Focus>().unwrap();
@@ -286,58 +776,90 @@ impl ViewTemplate for TextInput {
                                 event.stop_propagation();
                             }
                         }),
-                        // Not going to worry about drag events for now
+                        // Place the cursor where the pointer went down, shift extends the selection
+                        On::<Pointer<Down>>::run(move |world: &mut World| {
+                            if world.is_disabled(id) {
+                                return;
+                            }
+                            let position = {
+                                let mut event = world
+                                    .get_resource_mut::<ListenerInput<Pointer<Down>>>()
+                                    .unwrap();
+                                event.stop_propagation();
+                                event.pointer_location.position
+                            };
+                            world.resource_mut::<Focus>().0 = Some(id);
+                            let shift = world
+                                .resource::<ButtonInput<KeyCode>>()
+                                .any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+                            let Some(index) = char_index_at(world, text_id, position) else {
+                                return;
+                            };
+                            let mut qs = world.query::<TextInputEditor>();
+                            if let Ok(mut input) = qs.get_mut(world, id) {
+                                input.editor().move_to(index, shift);
+                            }
+                        }),
+                        // Dragging selects from where the pointer went down
+                        On::<Pointer<Drag>>::run(move |world: &mut World| {
+                            if world.is_disabled(id) {
+                                return;
+                            }
+                            let position = world
+                                .resource::<ListenerInput<Pointer<Drag>>>()
+                                .pointer_location
+                                .position;
+                            let Some(index) = char_index_at(world, text_id, position) else {
+                                return;
+                            };
+                            let mut qs = world.query::<TextInputEditor>();
+                            if let Ok(mut input) = qs.get_mut(world, id) {
+                                input.editor().move_to(index, true);
+                            }
+                        }),
                         On::<KeyPressEvent>::run(move |world: &mut World| {
                             if world.is_disabled(id) {
                                 return;
                             }
                             let mut state = SystemState::<(
                                 ResMut<ListenerInput<KeyPressEvent>>,
-                                Query<(
-                                    &mut TextInputValue,
-                                    &mut TextInputCursorPos,
-                                    &mut TextInputCursorTimer,
-                                )>,
+                                Res<ButtonInput<KeyCode>>,
+                                Res<Clipboard>,
+                                Query<TextInputEditor>,
                                 ResMut<Focus>,
                                 ResMut<FocusVisible>,
                                 TabNavigation,
                             )>::new(world);
-                            let (mut event, mut qs, mut focus, mut focus_visible, nav) =
-                                state.get_mut(world);
+                            let (
+                                mut event,
+                                keys,
+                                clipboard,
+                                mut qs,
+                                mut focus,
+                                mut focus_visible,
+                                nav,
+                            ) = state.get_mut(world);
                             if event.target != id {
                                 return;
                             }
                             let key_code = event.key_code;
                             let shift = event.shift;
-                            let (mut value, mut cursor_pos, mut cursor_timer) = qs
+                            let ctrl = shortcut_modifier(&keys);
+                            let mut input = qs
                                 .get_mut(id)
                                 .expect("this entity should have provided components entity");
+                            let mut editor = input.editor();
                             let mut run_callback = false;
                             match key_code {
                                 KeyCode::Enter => {
                                     run_callback = true;
-                                    event.stop_propagation();
-                                }
-                                code @ (KeyCode::ArrowLeft | KeyCode::ArrowRight) => {
-                                    if code == KeyCode::ArrowLeft {
-                                        if **cursor_pos > 0 {
-                                            **cursor_pos -= 1;
-                                        }
-                                    } else if **cursor_pos < value.len() {
-                                        **cursor_pos += 1;
-                                    }
-                                    cursor_timer.should_reset = true;
-                                    event.stop_propagation();
-                                }
-                                KeyCode::Home => {
-                                    **cursor_pos = 0;
-                                    cursor_timer.should_reset = true;
-                                    event.stop_propagation();
                                 }
+                                KeyCode::ArrowLeft => editor.move_left(ctrl, shift),
+                                KeyCode::ArrowRight => editor.move_right(ctrl, shift),
+                                KeyCode::Home => editor.move_to(0, shift),
                                 KeyCode::End => {
-                                    **cursor_pos = value.len();
-                                    cursor_timer.should_reset = true;
-                                    event.stop_propagation();
+                                    let len = editor.len();
+                                    editor.move_to(len, shift);
                                 }
                                 code @ (KeyCode::ArrowUp | KeyCode::ArrowDown | KeyCode::Tab) => {
                                     let next = nav.navigate(
@@ -354,33 +876,41 @@ impl ViewTemplate for TextInput {
                                         focus.0 = next;
                                         focus_visible.0 = true;
                                     }
-                                    event.stop_propagation();
                                 }
                                 KeyCode::Backspace => {
-                                    if **cursor_pos > 0 {
-                                        value.remove(**cursor_pos - 1);
-                                        **cursor_pos -= 1;
-                                        cursor_timer.should_reset = true;
-                                    }
-                                    event.stop_propagation();
+                                    editor.delete_backward(ctrl);
                                 }
                                 KeyCode::Delete => {
-                                    if **cursor_pos < (**value).len() {
-                                        value.remove(**cursor_pos);
-                                        cursor_timer.should_reset = true;
-                                    }
-                                    event.stop_propagation();
+                                    editor.delete_forward(ctrl);
                                 }
                                 KeyCode::Space => {
-                                    if max_length.map_or(true, |l| value.len() < l) {
-                                        value.insert(**cursor_pos, ' ');
-                                        **cursor_pos += 1;
-                                        cursor_timer.should_reset = true;
+                                    editor.insert(" ", EditKind::Other);
+                                }
+                                KeyCode::KeyA if ctrl => editor.select_all(),
+                                KeyCode::KeyC if ctrl => {
+                                    if let Some(text) = editor.selected_text() {
+                                        clipboard.set_text(text);
                                     }
-                                    event.stop_propagation();
                                 }
-                                _ => {}
+                                KeyCode::KeyX if ctrl => {
+                                    if let Some(text) = editor.selected_text() {
+                                        clipboard.set_text(text);
+                                        editor.delete_selection();
+                                    }
+                                }
+                                KeyCode::KeyV if ctrl => clipboard.request_paste(id),
+                                KeyCode::KeyZ if ctrl && shift => {
+                                    editor.redo();
+                                }
+                                KeyCode::KeyZ if ctrl => {
+                                    editor.undo();
+                                }
+                                KeyCode::KeyY if ctrl => {
+                                    editor.redo();
+                                }
+                                _ => return,
                             }
+                            event.stop_propagation();
                             if run_callback {
                                 if let Some(on_submit) = on_submit {
                                     world.run_callback(on_submit, ());
@@ -391,34 +921,35 @@ impl ViewTemplate for TextInput {
                             if world.is_disabled(id) {
                                 return;
                             }
-                            let mut event = world
-                                .get_resource_mut::<ListenerInput<KeyCharEvent>>()
-                                .unwrap();
+                            let mut state = SystemState::<(
+                                ResMut<ListenerInput<KeyCharEvent>>,
+                                Res<ButtonInput<KeyCode>>,
+                                Query<(TextInputEditor, &mut TextInputFrameText)>,
+                            )>::new(world);
+                            let (mut event, keys, mut qs) = state.get_mut(world);
                             if event.target != id {
                                 return;
                             }
                             event.stop_propagation();
                             let key = event.key;
                             // Just a single line, ignore newline characters, especially because we
-                            // should have already on_submit callback
-                            if key == '\n' {
+                            // should have already on_submit callback. Shortcuts are handled as
+                            // key presses
+                            if key == '\n' || shortcut_modifier(&keys) {
                                 return;
                             }
-                            let mut qs = world.query::<(
-                                &mut TextInputValue,
-                                &mut TextInputCursorPos,
-                                &mut TextInputCursorTimer,
-                            )>();
-                            let (mut value, mut cursor_pos, mut cursor_timer) = qs
-                                .get_mut(world, id)
+                            let (mut input, mut frame_text) = qs
+                                .get_mut(id)
                                 .expect("this entity should have provided components entity");
-                            if max_length.map_or(false, |l| value.len() >= l) {
+                            if !frame_text.type_char(key) {
                                 return;
                             }
-
-                            value.insert(**cursor_pos, key);
-                            **cursor_pos += 1;
-                            cursor_timer.should_reset = true;
+                            let kind = if key.is_whitespace() {
+                                EditKind::Other
+                            } else {
+                                EditKind::Typing
+                            };
+                            input.editor().insert(&key.to_string(), kind);
                         }),
                     )
                 },
@@ -433,81 +964,340 @@ impl ViewTemplate for TextInput {
                         self.size,
                     )
                     .style_dyn(
-                        |(minimal, disabled, hovering, is_valid), sb| {
+                        |(minimal, disabled, hovering, is_valid, palette), sb| {
                             let color = if !is_valid {
-                                colors::DESTRUCTIVE
+                                palette.destructive
                             } else if minimal {
-                                colors::TRANSPARENT
+                                colors::TRANSPARENT.into()
                             } else {
-                                text_input_bg_color(disabled, hovering)
+                                text_input_bg_color(palette.foreground, disabled, hovering)
                             };
                             sb.background_color(color);
                         },
-                        (minimal, self.disabled, hovering, is_valid),
+                        (minimal, self.disabled, hovering, is_valid, palette),
                     )
                     .style_dyn(
-                        move |focused, sb| {
+                        move |(focused, focus), sb| {
                             match focused {
                                 true => {
-                                    sb.outline_color(colors::FOCUS)
-                                        .outline_width(2)
-                                        .outline_offset(2);
+                                    sb.outline_color(focus).outline_width(2).outline_offset(2);
                                 }
                                 false => {
                                     sb.outline_color(Option::<Color>::None);
                                 }
                             };
                         },
-                        focused,
+                        (focused, palette.focus),
                     ),
-                Element::<TextBundle>::new()
+                Element::<TextBundle>::for_entity(text_id)
                     .named("TextInput::Text")
                     .style((typography::text_default,))
                     .insert_dyn(
-                        move |(visible, focused, before, after)| {
+                        move |(visible, focused, sections, palette)| {
                             let default_style = TextStyle {
-                                color: colors::BACKGROUND.into(),
+                                color: palette.background,
                                 ..Default::default()
                             };
-                            let sections = vec![
-                                TextSection {
-                                    value: before,
-                                    style: default_style.clone(),
-                                },
-                                TextSection {
-                                    value: "|".into(),
-                                    style: TextStyle {
-                                        font: cursor_font.clone(),
-                                        color: if visible && focused {
-                                            colors::BACKGROUND.into()
-                                        } else {
-                                            colors::TRANSPARENT.into()
-                                        },
-                                        ..default_style.clone()
-                                    },
-                                },
-                                TextSection {
-                                    value: after,
-                                    style: default_style,
+                            let selected_style = TextStyle {
+                                color: palette.primary,
+                                ..default_style.clone()
+                            };
+                            let cursor_style = TextStyle {
+                                font: cursor_font.clone(),
+                                color: if visible && focused {
+                                    palette.background
+                                } else {
+                                    colors::TRANSPARENT.into()
                                 },
+                                ..default_style.clone()
+                            };
+                            let preedit_style = TextStyle {
+                                color: palette.primary.with_alpha(0.7),
+                                ..default_style.clone()
+                            };
+                            let styles = [
+                                default_style.clone(),
+                                selected_style.clone(),
+                                cursor_style,
+                                preedit_style,
+                                selected_style,
+                                default_style,
                             ];
-                            Text {
-                                sections,
-                                justify: JustifyText::Left,
-                                linebreak_behavior: BreakLineOn::NoWrap,
-                            }
+                            let sections = sections
+                                .into_iter()
+                                .zip(styles)
+                                .map(|(value, style)| TextSection { value, style })
+                                .collect();
+                            (
+                                Text {
+                                    sections,
+                                    justify: JustifyText::Left,
+                                    linebreak_behavior: BreakLineOn::NoWrap,
+                                },
+                                offsets,
+                            )
                         },
-                        (cursor_visible, focused, before.clone(), after.clone()),
+                        (cursor_visible, focused, sections, palette),
                     ),
             ))
     }
 }
 
-pub(crate) fn text_input_bg_color(is_disabled: bool, is_hovering: bool) -> Srgba {
-    let base_color = colors::FOREGROUND;
+/// Applies text read from the clipboard to the input that asked for it
+pub(crate) fn apply_clipboard_pastes(
+    clipboard: Res<Clipboard>,
+    mut inputs: Query<TextInputEditor>,
+) {
+    for (entity, text) in clipboard.drain_pastes() {
+        let Ok(mut input) = inputs.get_mut(entity) else {
+            continue;
+        };
+        // This is a single line input, so flatten any line breaks
+        let text = text.replace(['\r', '\n'], " ");
+        input.editor().insert(&text, EditKind::Other);
+    }
+}
+
+/// Enables the input method while a text input is focused, and feeds it composed text
+pub(crate) fn text_input_ime(
+    focus: Res<Focus>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut events: EventReader<Ime>,
+    mut inputs: Query<(
+        TextInputEditor,
+        &mut TextInputPreedit,
+        &mut TextInputFrameText,
+    )>,
+    nodes: Query<(&Node, &GlobalTransform)>,
+) {
+    let focused = focus.0.filter(|entity| inputs.contains(*entity));
+    if let Ok(mut window) = windows.get_single_mut() {
+        let enabled = focused.is_some();
+        if window.ime_enabled != enabled {
+            window.ime_enabled = enabled;
+        }
+        // Candidate windows open just below the input
+        if let Some((node, transform)) = focused.and_then(|entity| nodes.get(entity).ok()) {
+            let rect = node.logical_rect(transform);
+            let position = Vec2::new(rect.min.x, rect.max.y);
+            if window.ime_position != position {
+                window.ime_position = position;
+            }
+        }
+    }
+
+    for event in events.read() {
+        let Some((mut input, mut preedit, mut frame_text)) =
+            focused.and_then(|entity| inputs.get_mut(entity).ok())
+        else {
+            continue;
+        };
+        match event {
+            Ime::Preedit { value, .. } => {
+                if **preedit != *value {
+                    **preedit = value.clone();
+                }
+            }
+            Ime::Commit { value, .. } => {
+                preedit.clear();
+                if frame_text.commit(value) {
+                    input.editor().insert(value, EditKind::Other);
+                }
+            }
+            Ime::Disabled { .. } => {
+                preedit.clear();
+            }
+            Ime::Enabled { .. } => {}
+        }
+    }
+}
+
+/// Forgets the text that went into inputs this frame, see [`TextInputFrameText`]
+pub(crate) fn clear_text_input_frame_text(mut inputs: Query<&mut TextInputFrameText>) {
+    for mut frame_text in inputs.iter_mut() {
+        frame_text.set_if_neq(TextInputFrameText::default());
+    }
+}
+
+/// The background of a text input, its text drawn in the theme's background
+/// color over `base_color`
+pub(crate) fn text_input_bg_color(
+    base_color: Color,
+    is_disabled: bool,
+    is_hovering: bool,
+) -> Color {
     match (is_disabled, is_hovering) {
         (true, _) => base_color.with_alpha(0.2),
         (_, true) => base_color.lighter(0.02),
         (_, false) => base_color,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What an [`Editor`] edits, as a text input entity would hold it
+    #[derive(Default)]
+    struct Input {
+        value: String,
+        cursor: usize,
+        anchor: Option<usize>,
+        history: TextInputHistory,
+        max_length: Option<usize>,
+    }
+
+    impl Input {
+        /// `value`, with the cursor at its end
+        fn new(value: &str) -> Self {
+            Self {
+                value: value.to_string(),
+                cursor: value.chars().count(),
+                ..default()
+            }
+        }
+
+        fn editor(&mut self) -> Editor<'_> {
+            Editor {
+                value: &mut self.value,
+                cursor: &mut self.cursor,
+                anchor: &mut self.anchor,
+                history: &mut self.history,
+                max_length: self.max_length,
+            }
+        }
+
+        fn type_text(&mut self, text: &str) {
+            for c in text.chars() {
+                self.editor().insert(&c.to_string(), EditKind::Typing);
+            }
+        }
+    }
+
+    #[test]
+    fn shift_moves_extend_the_selection() {
+        let mut input = Input::new("hello world");
+        input.editor().move_left(true, true);
+        assert_eq!(input.editor().selection(), Some(6..11));
+        assert_eq!(input.editor().selected_text().as_deref(), Some("world"));
+        input.editor().move_left(false, true);
+        assert_eq!(input.editor().selected_text().as_deref(), Some(" world"));
+        // Moving without shift collapses the selection to its start
+        input.editor().move_left(false, false);
+        assert_eq!(input.editor().selection(), None);
+        assert_eq!(input.cursor, 5);
+    }
+
+    #[test]
+    fn typing_replaces_the_selection() {
+        let mut input = Input::new("hello world");
+        input.editor().select_all();
+        assert_eq!(
+            input.editor().selected_text().as_deref(),
+            Some("hello world")
+        );
+        input.type_text("hi");
+        assert_eq!(input.value, "hi");
+        assert_eq!(input.cursor, 2);
+        assert_eq!(input.editor().selection(), None);
+    }
+
+    #[test]
+    fn positions_are_counted_in_chars() {
+        let mut input = Input::new("añb");
+        input.editor().move_left(false, true);
+        input.editor().move_left(false, true);
+        assert_eq!(input.editor().selected_text().as_deref(), Some("ñb"));
+        input.editor().delete_backward(false);
+        assert_eq!(input.value, "a");
+    }
+
+    #[test]
+    fn inserts_are_truncated_to_the_max_length() {
+        let mut input = Input {
+            max_length: Some(5),
+            ..Input::new("abc")
+        };
+        input.editor().insert("defgh", EditKind::Other);
+        assert_eq!(input.value, "abcde");
+        assert!(!input.editor().insert("x", EditKind::Typing));
+        // Replacing a selection makes room
+        input.editor().move_left(false, true);
+        input.editor().insert("xyz", EditKind::Other);
+        assert_eq!(input.value, "abcdx");
+    }
+
+    #[test]
+    fn runs_of_typing_and_deleting_are_undone_together() {
+        let mut input = Input::new("");
+        input.type_text("hello");
+        input.editor().delete_backward(false);
+        input.editor().delete_backward(false);
+        assert_eq!(input.value, "hel");
+        assert!(input.editor().undo());
+        assert_eq!(input.value, "hello");
+        assert!(input.editor().undo());
+        assert_eq!(input.value, "");
+        assert!(!input.editor().undo());
+    }
+
+    #[test]
+    fn moving_the_cursor_starts_a_new_undo_step() {
+        let mut input = Input::new("");
+        input.type_text("ab");
+        input.editor().move_left(false, false);
+        input.type_text("c");
+        assert_eq!(input.value, "acb");
+        input.editor().undo();
+        assert_eq!(input.value, "ab");
+        assert_eq!(input.cursor, 1);
+    }
+
+    #[test]
+    fn redo_restores_what_was_undone_until_the_next_edit() {
+        let mut input = Input::new("");
+        input.type_text("abc");
+        input.editor().undo();
+        assert!(input.editor().redo());
+        assert_eq!(input.value, "abc");
+        assert_eq!(input.cursor, 3);
+        input.editor().undo();
+        input.type_text("x");
+        assert!(!input.editor().redo());
+        assert_eq!(input.value, "x");
+    }
+
+    #[test]
+    fn cut_and_paste_are_single_undo_steps() {
+        let mut input = Input::new("hello world");
+        input.editor().move_left(true, true);
+        // As Ctrl+X does
+        let cut = input.editor().selected_text().unwrap();
+        input.editor().delete_selection();
+        assert_eq!(input.value, "hello ");
+        input.editor().move_to(0, false);
+        // As a paste does, dropping line breaks and other control characters
+        input.editor().insert(&format!("{cut}\t"), EditKind::Other);
+        assert_eq!(input.value, "worldhello ");
+        input.editor().undo();
+        assert_eq!(input.value, "hello ");
+        input.editor().undo();
+        assert_eq!(input.value, "hello world");
+    }
+
+    #[test]
+    fn text_committed_by_the_input_method_is_inserted_once() {
+        // Committed, then sent as key presses
+        let mut frame_text = TextInputFrameText::default();
+        assert!(frame_text.commit("日本"));
+        assert!(!frame_text.type_char('日'));
+        assert!(!frame_text.type_char('本'));
+        assert!(frame_text.type_char('x'));
+
+        // Sent as key presses, then committed
+        let mut frame_text = TextInputFrameText::default();
+        assert!(frame_text.type_char('a'));
+        assert!(!frame_text.commit("a"));
+        assert!(frame_text.commit("b"));
+    }
+}
@@ -0,0 +1,239 @@
+use std::net::{IpAddr, SocketAddr};
+
+use bevy::{prelude::*, utils::HashMap};
+use regex::Regex;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::locale::Localization;
+
+/// How many characters a room code has
+pub const ROOM_CODE_LENGTH: usize = 6;
+
+/// A check on the value of a text input, configurable from the menu RON files.
+/// Each failing check produces a [`ValidationError`] to show under the field.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub enum Validator {
+    /// The value must contain something other than whitespace
+    NonEmpty,
+
+    /// The value must be a number no smaller than this
+    Min(f64),
+
+    /// The value must be a number no larger than this
+    Max(f64),
+
+    /// The value must have at least this many characters
+    MinLength(usize),
+
+    /// The whole value must match a regular expression
+    Pattern {
+        regex: WholeMatch,
+        /// The locale key of the message to show when the value doesn't match
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        message: Option<String>,
+    },
+
+    /// The value must be an IPv4 or IPv6 address
+    IpAddr,
+
+    /// The value must be an IP address and a port
+    SocketAddr,
+
+    /// The value must be a room code, [`ROOM_CODE_LENGTH`] letters or digits
+    RoomCode,
+
+    /// A validator registered in code with [`CustomValidators::register`]
+    Custom(String),
+}
+
+impl Validator {
+    pub fn validate(&self, value: &str, custom: &CustomValidators) -> Result<(), ValidationError> {
+        match self {
+            Self::NonEmpty => {
+                if value.trim().is_empty() {
+                    return Err(ValidationError::new("validator.required"));
+                }
+            }
+            Self::Min(min) => {
+                let number = parse_number(value)?;
+                if number < *min {
+                    return Err(ValidationError::new("validator.min").arg("min", min));
+                }
+            }
+            Self::Max(max) => {
+                let number = parse_number(value)?;
+                if number > *max {
+                    return Err(ValidationError::new("validator.max").arg("max", max));
+                }
+            }
+            Self::MinLength(length) => {
+                if value.chars().count() < *length {
+                    return Err(ValidationError::new("validator.min_length").arg("length", length));
+                }
+            }
+            Self::Pattern { regex, message } => {
+                if !regex.is_match(value) {
+                    let key = message.as_deref().unwrap_or("validator.pattern");
+                    return Err(ValidationError::new(key));
+                }
+            }
+            Self::IpAddr => {
+                if value.trim().parse::<IpAddr>().is_err() {
+                    return Err(ValidationError::new("validator.ip_addr"));
+                }
+            }
+            Self::SocketAddr => {
+                if value.trim().parse::<SocketAddr>().is_err() {
+                    return Err(ValidationError::new("validator.socket_addr"));
+                }
+            }
+            Self::RoomCode => {
+                let value = value.trim();
+                if value.chars().count() != ROOM_CODE_LENGTH
+                    || !value.chars().all(|c| c.is_ascii_alphanumeric())
+                {
+                    return Err(ValidationError::new("validator.room_code")
+                        .arg("length", &ROOM_CODE_LENGTH));
+                }
+            }
+            Self::Custom(name) => match custom.get(name) {
+                Some(validate) => validate(value)?,
+                None => warn!("No custom validator registered as {name:?}"),
+            },
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(value: &str) -> Result<f64, ValidationError> {
+    value
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| ValidationError::new("validator.number"))
+}
+
+/// Why a value failed a check: a locale key and the values to fill into it, so
+/// the message follows the player's language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub key: String,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl ValidationError {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, name: &'static str, value: &dyn ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    /// The message to show the player
+    pub fn localize(&self, localization: &Localization) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|(name, value)| (*name, value as &dyn ToString))
+            .collect::<Vec<_>>();
+        localization.format(&self.key, &args)
+    }
+}
+
+/// A regular expression that has to match the whole value. Compiled once, when
+/// the menu is loaded, so a menu with a broken pattern fails to load.
+#[derive(Debug, Clone, Reflect)]
+#[reflect_value(Debug, PartialEq)]
+pub struct WholeMatch {
+    /// As written in the menu, without the anchors
+    source: String,
+    regex: Regex,
+}
+
+impl WholeMatch {
+    pub fn new(source: impl Into<String>) -> Result<Self, regex::Error> {
+        let source = source.into();
+        let regex = Regex::new(&format!("^(?:{source})$"))?;
+        Ok(Self { source, regex })
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value)
+    }
+}
+
+impl PartialEq for WholeMatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Serialize for WholeMatch {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.source.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WholeMatch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::new(source).map_err(D::Error::custom)
+    }
+}
+
+/// Validators that need more than the RON configuration can express, by name
+#[derive(Resource, Default, Deref)]
+pub struct CustomValidators(HashMap<String, fn(&str) -> Result<(), ValidationError>>);
+
+impl CustomValidators {
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        validate: fn(&str) -> Result<(), ValidationError>,
+    ) {
+        self.0.insert(name.into(), validate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_the_whole_value() {
+        let pattern = WholeMatch::new("[a-z]+\\.png").unwrap();
+        assert!(pattern.is_match("bevy.png"));
+        assert!(!pattern.is_match("textures/bevy.png"));
+        assert!(!pattern.is_match("bevy.png.jpg"));
+    }
+
+    #[test]
+    fn invalid_patterns_fail_to_load() {
+        let loaded = ron::from_str::<Validator>("Pattern(regex: \"([a-z\")");
+        assert!(loaded.is_err());
+
+        let loaded = ron::from_str::<Validator>("Pattern(regex: \"[a-z]+\")").unwrap();
+        assert_eq!(
+            loaded.validate("123", &CustomValidators::default()),
+            Err(ValidationError::new("validator.pattern"))
+        );
+    }
+
+    #[test]
+    fn errors_name_their_limits() {
+        let custom = CustomValidators::default();
+        assert_eq!(
+            Validator::MinLength(3).validate("ab", &custom),
+            Err(ValidationError::new("validator.min_length").arg("length", &3))
+        );
+        assert_eq!(
+            Validator::Max(10.0).validate("eleven", &custom),
+            Err(ValidationError::new("validator.number"))
+        );
+        assert_eq!(Validator::Max(10.0).validate("10", &custom), Ok(()));
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    locale::{DEFAULT_LOCALE, SUPPORTED_LOCALES},
+    menu::condition::{MenuValue, MenuValues},
+    storage::{self, Pending},
+    theme::{DEFAULT_THEME, SUPPORTED_THEMES},
+};
+
+/// Where the settings are kept, in the user data directory
+const SETTINGS_FILE: &str = "settings.ron";
+
+/// The player's preferences, edited from the settings menu
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .init_resource::<SettingsFile>()
+            .register_type::<Settings>()
+            .add_systems(Startup, start_loading_settings)
+            .add_systems(
+                Update,
+                (
+                    apply_menu_settings.run_if(resource_changed::<MenuValues>),
+                    finish_loading_settings.run_if(settings_loading),
+                    save_settings.run_if(resource_changed::<Settings>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Every setting is edited by the menu widget with the same name as its field
+#[derive(Resource, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Settings {
+    /// Replace sliding and scaling animations with fades
+    pub reduce_motion: bool,
+
+    /// The language to show text in, as a locale like `"en"` or `"es-MX"`
+    pub locale: String,
+
+    /// The name of the theme to use, from the `themes` folder
+    pub theme: String,
+
+    /// How big to make the UI, in percent of the size that suits the window
+    pub ui_scale: u32,
+
+    /// The volume of the background music, in percent
+    pub music_volume: u32,
+
+    /// The volume of game sounds, in percent
+    pub sfx_volume: u32,
+
+    /// The volume of menu sounds, in percent
+    pub ui_volume: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            reduce_motion: false,
+            locale: DEFAULT_LOCALE.to_string(),
+            theme: DEFAULT_THEME.to_string(),
+            ui_scale: 100,
+            music_volume: 60,
+            sfx_volume: 100,
+            ui_volume: 80,
+        }
+    }
+}
+
+impl Settings {
+    /// The value of the setting called `name`, for the widget editing it
+    pub fn value(&self, name: &str) -> Option<MenuValue> {
+        match name {
+            "reduce_motion" => Some(MenuValue::Bool(self.reduce_motion)),
+            "locale" => selection(&SUPPORTED_LOCALES, &self.locale),
+            "theme" => selection(&SUPPORTED_THEMES, &self.theme),
+            "ui_scale" => Some(MenuValue::Number(self.ui_scale as f64)),
+            "music_volume" => Some(MenuValue::Number(self.music_volume as f64)),
+            "sfx_volume" => Some(MenuValue::Number(self.sfx_volume as f64)),
+            "ui_volume" => Some(MenuValue::Number(self.ui_volume as f64)),
+            _ => None,
+        }
+    }
+
+    /// Updates the setting called `name` from the widget editing it
+    pub fn apply(&mut self, name: &str, value: &MenuValue) {
+        match (name, value) {
+            ("reduce_motion", MenuValue::Bool(value)) => self.reduce_motion = *value,
+            ("locale", MenuValue::Selection(selected)) => {
+                if let Some(locale) = selected_option(&SUPPORTED_LOCALES, selected) {
+                    self.locale = locale.to_string();
+                }
+            }
+            ("theme", MenuValue::Selection(selected)) => {
+                if let Some(theme) = selected_option(&SUPPORTED_THEMES, selected) {
+                    self.theme = theme.to_string();
+                }
+            }
+            ("ui_scale", MenuValue::Number(value)) => self.ui_scale = *value as u32,
+            ("music_volume", MenuValue::Number(value)) => self.music_volume = *value as u32,
+            ("sfx_volume", MenuValue::Number(value)) => self.sfx_volume = *value as u32,
+            ("ui_volume", MenuValue::Number(value)) => self.ui_volume = *value as u32,
+            _ => {}
+        }
+    }
+}
+
+/// The selection of a widget listing `options`, with `value` chosen
+fn selection(options: &[&str], value: &str) -> Option<MenuValue> {
+    options
+        .iter()
+        .position(|option| *option == value)
+        .map(|i| MenuValue::Selection(HashSet::from_iter([i])))
+}
+
+/// The option chosen in a widget listing `options`
+fn selected_option<'a>(options: &[&'a str], selected: &HashSet<usize>) -> Option<&'a str> {
+    selected.iter().find_map(|i| options.get(*i).copied())
+}
+
+/// Copies the values of the settings widgets into [`Settings`]
+fn apply_menu_settings(values: Res<MenuValues>, mut settings: ResMut<Settings>) {
+    let mut next = settings.clone();
+    for (name, value) in values.iter() {
+        next.apply(name, value);
+    }
+    settings.set_if_neq(next);
+}
+
+/// The settings saved by earlier runs, while they're being read, and what was
+/// saved last
+#[derive(Resource, Default)]
+struct SettingsFile {
+    loading: Option<Pending<Option<String>>>,
+    saved: Option<String>,
+}
+
+fn settings_loading(file: Res<SettingsFile>) -> bool {
+    file.loading.is_some()
+}
+
+/// Settings as saved in [`SETTINGS_FILE`]. Ones it doesn't have keep their default.
+fn parse_settings(text: &str) -> Result<Settings, ron::error::SpannedError> {
+    ron::from_str(text)
+}
+
+fn start_loading_settings(mut file: ResMut<SettingsFile>) {
+    file.loading = Some(storage::read_in_background(SETTINGS_FILE));
+}
+
+fn finish_loading_settings(mut file: ResMut<SettingsFile>, mut settings: ResMut<Settings>) {
+    let Some(text) = file.loading.as_ref().and_then(Pending::take) else {
+        return;
+    };
+    file.loading = None;
+    let Some(text) = text else {
+        return;
+    };
+    match parse_settings(&text) {
+        Ok(loaded) => {
+            file.saved = Some(text);
+            settings.set_if_neq(loaded);
+        }
+        Err(error) => warn!("The settings can't be read, using the defaults: {error}"),
+    }
+}
+
+/// Saves the settings in the background when they change, once what earlier
+/// runs saved has been read, so that isn't overwritten
+fn save_settings(mut file: ResMut<SettingsFile>, settings: Res<Settings>) {
+    if file.loading.is_some() {
+        return;
+    }
+    let text = match ron::ser::to_string_pretty(&*settings, default()) {
+        Ok(text) => text,
+        Err(error) => {
+            warn!("The settings can't be saved: {error}");
+            return;
+        }
+    };
+    if file.saved.as_ref() != Some(&text) {
+        storage::write_in_background(SETTINGS_FILE, text.clone());
+        file.saved = Some(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_settings_read_back() {
+        let settings = Settings {
+            reduce_motion: true,
+            locale: "es".to_string(),
+            ui_scale: 125,
+            ..default()
+        };
+        let text = ron::ser::to_string_pretty(&settings, default()).unwrap();
+        assert_eq!(parse_settings(&text).unwrap(), settings);
+    }
+
+    #[test]
+    fn missing_settings_keep_their_default() {
+        let settings = parse_settings("(reduce_motion: true)").unwrap();
+        assert!(settings.reduce_motion);
+        assert_eq!(settings.music_volume, Settings::default().music_volume);
+    }
+}
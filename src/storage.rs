@@ -0,0 +1,266 @@
+//! Files kept for the player between runs, like the packs made in the editor.
+//!
+//! They live in the platform's data directory for the game, or in the browser's
+//! local storage on the web. Names are relative paths like `"packs/local.pack.ron"`.
+//! Files too big for local storage, like the question cache, are read and written
+//! in the background instead, and kept in IndexedDB on the web. Natively, files
+//! can also be loaded as assets, like images copied in by the editor.
+
+use std::sync::{Arc, Mutex};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::app::{App, Plugin};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::{
+    asset::{
+        io::{file::FileAssetReader, AssetSource},
+        AssetApp,
+    },
+    log::warn,
+    tasks::IoTaskPool,
+};
+
+/// The asset source files in the data directory load from, as in
+/// `"user://images/cat.png"`
+#[cfg(not(target_arch = "wasm32"))]
+pub const ASSET_SOURCE: &str = "user";
+
+/// Lets files in the data directory be loaded as assets from [`ASSET_SOURCE`].
+/// Like any asset source, it has to be added before `DefaultPlugins`.
+pub struct UserAssetsPlugin;
+
+impl Plugin for UserAssetsPlugin {
+    // Browsers have no data directory to load from
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn build(&self, app: &mut App) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(dir) = data_dir() {
+            app.register_asset_source(
+                ASSET_SOURCE,
+                AssetSource::build().with_reader(move || Box::new(FileAssetReader::new(&dir))),
+            );
+        }
+    }
+}
+
+/// The result of work that finishes in the background, like a read. Clones
+/// share the result, so one can be handed to whatever does the work.
+#[derive(Debug)]
+pub struct Pending<T>(Arc<Mutex<Option<T>>>);
+
+impl<T> Default for Pending<T> {
+    fn default() -> Self {
+        Self(Arc::default())
+    }
+}
+
+impl<T> Clone for Pending<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Pending<T> {
+    /// Already finished with `value`
+    pub fn ready(value: T) -> Self {
+        let pending = Self::default();
+        pending.finish(value);
+        pending
+    }
+
+    /// The result, the first time it's asked for after the work has finished
+    pub fn take(&self) -> Option<T> {
+        self.0.lock().ok()?.take()
+    }
+
+    /// Hands over the result of the work
+    pub fn finish(&self, value: T) {
+        if let Ok(mut result) = self.0.lock() {
+            *result = Some(value);
+        }
+    }
+}
+
+/// The text of the file called `name`, if it has been written
+pub fn read(name: &str) -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        fs::read_to_string(data_dir()?.join(name)).ok()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        local_storage()?.get_item(name).ok().flatten()
+    }
+}
+
+/// Writes `text` to the file called `name`, replacing it. Fails with a reason
+/// that can be shown to the player.
+pub fn write(name: &str, text: &str) -> Result<(), String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = data_dir()
+            .ok_or_else(|| "There is no place to save files".to_string())?
+            .join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, text).map_err(|e| e.to_string())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        local_storage()
+            .ok_or_else(|| "The browser doesn't allow saving".to_string())?
+            .set_item(name, text)
+            .map_err(|e| format!("{e:?}"))
+    }
+}
+
+/// Copies the file at `from` into the data directory as `name`, replacing it.
+/// Fails with a reason that can be shown to the player.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn copy_in(from: &Path, name: &str) -> Result<(), String> {
+    let path = data_dir()
+        .ok_or_else(|| "There is no place to save files".to_string())?
+        .join(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::copy(from, path).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Where the files are kept, which differs by platform
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+fn data_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("org", "favil", "bevytrivia")
+        .map(|dirs| dirs.data_dir().to_path_buf())
+}
+
+/// Where the files are kept, in the app's own storage
+#[cfg(target_os = "android")]
+fn data_dir() -> Option<PathBuf> {
+    bevy::winit::ANDROID_APP.get()?.internal_data_path()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    gloo_utils::window().local_storage().ok().flatten()
+}
+
+/// Reads the file called `name` in the background, `None` once done if it
+/// hasn't been written
+pub fn read_in_background(name: &str) -> Pending<Option<String>> {
+    let pending = Pending::default();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let result = pending.clone();
+        let name = name.to_string();
+        IoTaskPool::get()
+            .spawn(async move { result.finish(read(&name)) })
+            .detach();
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let result = pending.clone();
+        let name = name.to_string();
+        wasm_bindgen_futures::spawn_local(async move {
+            let text = indexed_db::get(&name).await.unwrap_or_else(|error| {
+                bevy::log::warn!("Couldn't read {name}: {error:?}");
+                None
+            });
+            result.finish(text);
+        });
+    }
+    pending
+}
+
+/// Writes `text` to the file called `name` in the background, replacing it.
+/// Failures are only logged, so this is for files that can be done without.
+pub fn write_in_background(name: &str, text: String) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let name = name.to_string();
+        IoTaskPool::get()
+            .spawn(async move {
+                if let Err(error) = write(&name, &text) {
+                    warn!("Couldn't write {name}: {error}");
+                }
+            })
+            .detach();
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let name = name.to_string();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(error) = indexed_db::put(&name, &text).await {
+                bevy::log::warn!("Couldn't write {name}: {error:?}");
+            }
+        });
+    }
+}
+
+/// Files in a single object store of the game's own database
+#[cfg(target_arch = "wasm32")]
+mod indexed_db {
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::{Event, IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+    const DATABASE: &str = "bevytrivia";
+    const STORE: &str = "files";
+
+    /// Waits for `request` to succeed, with its result, or fail
+    async fn finish(request: &IdbRequest) -> Result<JsValue, JsValue> {
+        let done = js_sys::Promise::new(&mut |resolve, reject| {
+            request.set_onsuccess(Some(&resolve));
+            request.set_onerror(Some(&reject));
+        });
+        JsFuture::from(done).await?;
+        request.result()
+    }
+
+    async fn open() -> Result<IdbDatabase, JsValue> {
+        let factory = gloo_utils::window()
+            .indexed_db()?
+            .ok_or("IndexedDB isn't available")?;
+        let request = factory.open_with_u32(DATABASE, 1)?;
+        // Runs when the database is first created, before it's opened
+        let create_store = Closure::<dyn FnMut(Event)>::new(|event: Event| {
+            let database = event
+                .target()
+                .and_then(|target| target.dyn_into::<IdbOpenDbRequest>().ok())
+                .and_then(|request| request.result().ok())
+                .and_then(|result| result.dyn_into::<IdbDatabase>().ok());
+            if let Some(database) = database {
+                if let Err(error) = database.create_object_store(STORE) {
+                    bevy::log::warn!("Couldn't create the {STORE} store: {error:?}");
+                }
+            }
+        });
+        request.set_onupgradeneeded(Some(create_store.as_ref().unchecked_ref()));
+        let database = finish(&request).await?;
+        database.dyn_into()
+    }
+
+    pub async fn get(name: &str) -> Result<Option<String>, JsValue> {
+        let store = open()
+            .await?
+            .transaction_with_str(STORE)?
+            .object_store(STORE)?;
+        let text = finish(&store.get(&JsValue::from_str(name))?).await?;
+        Ok(text.as_string())
+    }
+
+    pub async fn put(name: &str, text: &str) -> Result<(), JsValue> {
+        let store = open()
+            .await?
+            .transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?
+            .object_store(STORE)?;
+        finish(&store.put_with_key(&JsValue::from_str(text), &JsValue::from_str(name))?).await?;
+        Ok(())
+    }
+}
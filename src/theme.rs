@@ -0,0 +1,180 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_quill::Cx;
+use bevy_quill_obsidian::colors;
+use serde::Deserialize;
+
+use crate::{loading::ThemeAssets, settings::Settings, GameState};
+
+/// The theme used until the player picks another
+pub const DEFAULT_THEME: &str = "dark";
+
+/// The themes that can be picked in the settings menu, in the order it lists them
+pub const SUPPORTED_THEMES: [&str; 3] = ["dark", "light", "high_contrast"];
+
+/// Colors, font sizes and spacing for our own UI, loaded from `*.theme.ron` files
+/// and switched live from the settings menu. Obsidian's controls keep their own look.
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveTheme>()
+            .init_resource::<Themes>()
+            .add_systems(OnExit(GameState::Loading), load_themes)
+            .add_systems(
+                Update,
+                (
+                    select_theme.run_if(resource_changed::<Settings>),
+                    apply_clear_color.run_if(resource_changed::<ActiveTheme>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// A theme, loaded from `themes/<name>.theme.ron`. Anything left out keeps the default.
+#[derive(Asset, Deserialize, TypePath, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub palette: Palette,
+    pub font_sizes: FontSizes,
+    pub spacing: Spacing,
+    /// How rounded the corners of panels are
+    pub radius: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            palette: Palette::default(),
+            font_sizes: FontSizes::default(),
+            spacing: Spacing::default(),
+            radius: 8.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct Palette {
+    /// Behind everything
+    pub background: Color,
+    /// Panels above the background, like dialogs and toasts
+    pub surface: Color,
+    /// Text and outlines
+    pub foreground: Color,
+    /// Titles and the most important actions
+    pub primary: Color,
+    /// Errors and dangerous actions
+    pub destructive: Color,
+    /// Good news
+    pub success: Color,
+    /// The outline around whatever has keyboard focus
+    pub focus: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: colors::BACKGROUND.into(),
+            surface: colors::BACKGROUND.into(),
+            foreground: colors::FOREGROUND.into(),
+            primary: colors::PRIMARY.into(),
+            destructive: colors::DESTRUCTIVE.into(),
+            success: colors::PRIMARY.into(),
+            focus: colors::FOCUS.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct FontSizes {
+    /// Menu titles
+    pub title: f32,
+    /// Dialog titles and questions
+    pub heading: f32,
+    /// Everything else
+    pub body: f32,
+    /// Hints and errors under a field
+    pub small: f32,
+}
+
+impl Default for FontSizes {
+    fn default() -> Self {
+        Self {
+            title: 48.0,
+            heading: 24.0,
+            body: 18.0,
+            small: 14.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct Spacing {
+    /// Around the edge of the screen
+    pub padding: f32,
+    /// Between the items of a menu or row
+    pub gap: f32,
+    /// Between a label and what it labels
+    pub label_gap: f32,
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self {
+            padding: 50.0,
+            gap: 10.0,
+            label_gap: 2.5,
+        }
+    }
+}
+
+/// The theme in use
+#[derive(Resource, Debug, Default, Clone, Copy, Deref, PartialEq)]
+pub struct ActiveTheme(pub Theme);
+
+/// Every loaded [`Theme`], by name
+#[derive(Resource, Debug, Default, Clone, Deref)]
+pub struct Themes(HashMap<String, Theme>);
+
+/// The theme in use, re-rendering when it changes
+pub fn use_theme(cx: &mut Cx) -> Theme {
+    **cx.use_resource::<ActiveTheme>()
+}
+
+fn load_themes(
+    mut commands: Commands,
+    assets: Res<ThemeAssets>,
+    themes: Res<Assets<Theme>>,
+    settings: Res<Settings>,
+) {
+    let themes = Themes(
+        assets
+            .themes
+            .iter()
+            .filter_map(|(path, handle)| {
+                let name = path.rsplit('/').next()?.strip_suffix(".theme.ron")?;
+                Some((name.to_string(), *themes.get(handle)?))
+            })
+            .collect(),
+    );
+    if let Some(theme) = themes
+        .get(&settings.theme)
+        .or_else(|| themes.get(DEFAULT_THEME))
+    {
+        commands.insert_resource(ActiveTheme(*theme));
+    }
+    commands.insert_resource(themes);
+}
+
+fn select_theme(settings: Res<Settings>, themes: Res<Themes>, mut active: ResMut<ActiveTheme>) {
+    if let Some(theme) = themes.get(&settings.theme) {
+        active.set_if_neq(ActiveTheme(*theme));
+    }
+}
+
+fn apply_clear_color(theme: Res<ActiveTheme>, mut clear_color: ResMut<ClearColor>) {
+    clear_color.0 = theme.palette.background;
+}
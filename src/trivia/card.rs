@@ -0,0 +1,187 @@
+use bevy::{a11y::accesskit::Role, prelude::*};
+use bevy_mod_stylebuilder::{StyleBuilder, StyleBuilderFont, StyleBuilderLayout};
+use bevy_quill::*;
+use bevy_quill_obsidian::{
+    controls::{Button, ButtonVariant},
+    size::Size,
+    typography,
+};
+
+use crate::{
+    accessibility::MenuItemAccess,
+    locale::Localization,
+    theme::{use_theme, Theme},
+    trivia::{
+        media::QuestionMedia,
+        question::{normalize, LocalizedQuestion},
+    },
+};
+
+/// A question the way players see it: its category and difficulty, the
+/// question with its image and clip, and the answers to pick from
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuestionCard {
+    pub question: LocalizedQuestion,
+
+    /// The answers in the order to show them, alphabetical if empty
+    pub answers: Vec<String>,
+
+    /// Called with the answer picked. Answers can't be picked without it.
+    pub on_answer: Option<Callback<String>>,
+
+    /// The answer the player picked, if any. No other can be picked.
+    pub picked: Option<String>,
+
+    /// Show which answer is right
+    pub revealed: bool,
+}
+
+impl ViewTemplate for QuestionCard {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let theme = use_theme(cx);
+        let localization = cx.use_resource::<Localization>().clone();
+        let question = &self.question;
+        let answers = if self.answers.is_empty() {
+            let mut answers = std::iter::once(&question.correct_answer)
+                .chain(&question.incorrect_answers)
+                .filter(|answer| !answer.trim().is_empty())
+                .cloned()
+                .collect::<Vec<_>>();
+            // In a fixed order, so the right answer isn't always first but the
+            // answers don't jump around while the question is edited
+            answers.sort_by_key(|answer| normalize(answer));
+            answers
+        } else {
+            self.answers.clone()
+        };
+        let on_answer = self.on_answer;
+        let locked = on_answer.is_none() || self.picked.is_some() || self.revealed;
+        let about = [question.category.as_str(), question.difficulty.as_str()]
+            .into_iter()
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" · ");
+        let count = answers.len();
+
+        Element::<NodeBundle>::new()
+            .named("QuestionCard")
+            .style(style_question_card)
+            .style_dyn(style_question_card_gap, theme)
+            .children((
+                Element::<NodeBundle>::new()
+                    .style(typography::text_default)
+                    .style_dyn(style_question_about, theme)
+                    .children(about),
+                Element::<NodeBundle>::new()
+                    .style(typography::text_strong)
+                    .style_dyn(style_question_text, theme)
+                    .children(question.question.clone()),
+                QuestionMedia {
+                    image: question.image.clone(),
+                    audio: question.audio.clone(),
+                },
+                Element::<NodeBundle>::new()
+                    .style(style_question_answers)
+                    .style_dyn(style_question_card_gap, theme)
+                    .children(
+                        answers
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, answer)| {
+                                let right = answer == question.correct_answer;
+                                let picked = self.picked.as_ref() == Some(&answer);
+                                let (variant, state) = match (self.revealed, right, picked) {
+                                    (true, true, _) => (ButtonVariant::Primary, Some("card.right")),
+                                    (true, false, true) => {
+                                        (ButtonVariant::Danger, Some("card.wrong"))
+                                    }
+                                    (false, _, true) => {
+                                        (ButtonVariant::Selected, Some("card.picked"))
+                                    }
+                                    _ => (ButtonVariant::Default, None),
+                                };
+                                // Read out with its place among the answers, and
+                                // whether it's the one picked or the right one
+                                let label = localization.format(
+                                    "card.answer",
+                                    &[("number", &(i + 1)), ("count", &count), ("answer", &answer)],
+                                );
+                                let state = state.map(|key| localization.text(key));
+                                let picking = answer.clone();
+                                Element::<NodeBundle>::new()
+                                    .style(style_question_answer)
+                                    .insert_dyn(
+                                        |(label, state)| {
+                                            let access = MenuItemAccess::new(Role::Button, label);
+                                            match state {
+                                                Some(state) => access.value(state),
+                                                None => access,
+                                            }
+                                        },
+                                        (label, state),
+                                    )
+                                    .children(
+                                        Button::new()
+                                            .size(Size::Xl)
+                                            .variant(variant)
+                                            .disabled(locked)
+                                            .style(style_question_answer_button)
+                                            .on_click(cx.create_callback(
+                                                move |world: &mut World| {
+                                                    if let Some(on_answer) = on_answer {
+                                                        world.run_callback(
+                                                            on_answer,
+                                                            picking.clone(),
+                                                        );
+                                                    }
+                                                },
+                                            ))
+                                            .children(answer),
+                                    )
+                                    .into_view_child()
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+            ))
+    }
+}
+
+fn style_question_card(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .align_items(AlignItems::Center)
+        .width(Val::Percent(100.0));
+}
+
+fn style_question_card_gap(theme: Theme, ss: &mut StyleBuilder) {
+    ss.row_gap(theme.spacing.gap).column_gap(theme.spacing.gap);
+}
+
+fn style_question_about(theme: Theme, ss: &mut StyleBuilder) {
+    ss.font_size(theme.font_sizes.small)
+        .color(theme.palette.foreground);
+}
+
+fn style_question_text(theme: Theme, ss: &mut StyleBuilder) {
+    ss.font_size(theme.font_sizes.heading)
+        .color(theme.palette.foreground);
+}
+
+/// Two answers to a row, like a game show board
+fn style_question_answers(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Row)
+        .flex_wrap(FlexWrap::Wrap)
+        .justify_content(JustifyContent::Center)
+        .width(Val::Percent(100.0));
+}
+
+fn style_question_answer(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex).width(Val::Percent(45.0));
+}
+
+fn style_question_answer_button(ss: &mut StyleBuilder) {
+    ss.flex_grow(1.0);
+}
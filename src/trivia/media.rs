@@ -0,0 +1,133 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_kira_audio::AudioSource;
+use bevy_mod_stylebuilder::{StyleBuilder, StyleBuilderLayout};
+use bevy_quill::*;
+use bevy_quill_obsidian::{controls::Button, size::Size};
+
+use crate::{audio::PlayClip, locale::use_text, trivia::source::pack::QuestionPack};
+
+/// Loads the images and clips of question packs, and plays a question's clip
+/// when its [`QuestionMedia`] view appears
+pub struct MediaPlugin;
+
+impl Plugin for MediaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QuestionMediaAssets>()
+            .add_systems(Update, (load_pack_media, play_shown_clips));
+    }
+}
+
+/// The images and clips of every loaded pack, by asset path. Kept here so they're
+/// ready by the time their question is asked, like [`crate::loading::TextureAssets`]
+/// and [`crate::loading::AudioAssets`] are for the game's own.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct QuestionMediaAssets {
+    pub images: HashMap<String, Handle<Image>>,
+    pub clips: HashMap<String, Handle<AudioSource>>,
+}
+
+/// The clip of the question being shown, if it has one. Played whenever it's
+/// added or changed.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+struct ShownClip(Option<String>);
+
+fn load_pack_media(
+    mut events: EventReader<AssetEvent<QuestionPack>>,
+    packs: Res<Assets<QuestionPack>>,
+    asset_server: Res<AssetServer>,
+    mut media: ResMut<QuestionMediaAssets>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        let Some(pack) = packs.get(*id) else {
+            continue;
+        };
+        for question in &pack.questions {
+            if let Some(path) = &question.image {
+                media
+                    .images
+                    .entry(path.clone())
+                    .or_insert_with(|| asset_server.load(path.clone()));
+            }
+            if let Some(path) = &question.audio {
+                media
+                    .clips
+                    .entry(path.clone())
+                    .or_insert_with(|| asset_server.load(path.clone()));
+            }
+        }
+    }
+}
+
+fn play_shown_clips(clips: Query<&ShownClip, Changed<ShownClip>>, mut play: EventWriter<PlayClip>) {
+    for path in clips.iter().filter_map(|clip| clip.0.clone()) {
+        play.send(PlayClip(path));
+    }
+}
+
+/// The image and clip of a question, for the question view. The clip plays as
+/// soon as this is shown, and again from the replay button.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuestionMedia {
+    pub image: Option<String>,
+    pub audio: Option<String>,
+}
+
+impl ViewTemplate for QuestionMedia {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        // Packs loaded ahead of time have their images ready, others load now
+        let image = self.image.as_ref().map(|path| {
+            let world = cx.world();
+            world
+                .resource::<QuestionMediaAssets>()
+                .images
+                .get(path)
+                .cloned()
+                .unwrap_or_else(|| world.resource::<AssetServer>().load(path.clone()))
+        });
+        let replay = use_text(cx, "question.replay");
+        let audio = self.audio.clone();
+        let on_replay = cx.create_callback(move |world: &mut World| {
+            if let Some(path) = &audio {
+                world.send_event(PlayClip(path.clone()));
+            }
+        });
+
+        Element::<NodeBundle>::new()
+            .named("QuestionMedia")
+            .style(style_question_media)
+            .insert_dyn(ShownClip, self.audio.clone())
+            .children((
+                image.map(|image| {
+                    Element::<ImageBundle>::new()
+                        .style(style_question_image)
+                        .insert_dyn(UiImage::new, image)
+                }),
+                Cond::new(
+                    self.audio.is_some(),
+                    Button::new()
+                        .size(Size::Lg)
+                        .on_click(on_replay)
+                        .children(replay),
+                    (),
+                ),
+            ))
+    }
+}
+
+fn style_question_media(ss: &mut StyleBuilder) {
+    ss.display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .align_items(AlignItems::Center)
+        .row_gap(10);
+}
+
+/// Keeps its aspect ratio, at a height that leaves room for the answers
+fn style_question_image(ss: &mut StyleBuilder) {
+    ss.height(Val::Vh(30.0)).max_width(Val::Percent(100.0));
+}
@@ -1 +1,5 @@
+pub mod card;
+pub mod media;
+pub mod question;
+pub mod set;
 pub mod source;
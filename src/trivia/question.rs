@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use crate::locale::LocalizedText;
+
+/// The most answers a question can have, right and wrong, so they fit on screen
+pub const MAX_ANSWERS: usize = 4;
+
+/// The difficulties questions can have, in the order the host menu lists them
+pub const DIFFICULTIES: [&str; 3] = ["Easy", "Medium", "Hard"];
+
+/// `easy` as `Easy`, the way the host menu lists difficulties
+pub fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// `text` in a form where questions differing only in case, punctuation or
+/// spacing come out the same, to spot duplicates
+pub fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A trivia question, with its text in as many languages as its source has
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Question {
+    /// The category the question belongs to
+    pub category: LocalizedText,
+
+    /// How hard the question is, as listed in the host menu
+    pub difficulty: String,
+
+    /// The question to ask
+    pub question: LocalizedText,
+
+    /// The right answer
+    pub correct_answer: LocalizedText,
+
+    /// The wrong answers. A true/false question has only one.
+    pub incorrect_answers: Vec<LocalizedText>,
+
+    /// An image to show with the question, as an asset path like `"packs/flags/fr.png"`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub image: Option<String>,
+
+    /// A clip to play with the question, as an asset path like `"packs/tunes/intro.ogg"`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub audio: Option<String>,
+}
+
+/// A [`Question`] with all of its text in one language
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LocalizedQuestion {
+    pub category: String,
+    pub difficulty: String,
+    pub question: String,
+    pub correct_answer: String,
+    pub incorrect_answers: Vec<String>,
+    pub image: Option<String>,
+    pub audio: Option<String>,
+}
+
+impl Question {
+    /// The normalized text of the question in each language it's given in
+    pub fn keys(&self) -> Vec<String> {
+        self.question.texts().into_iter().map(normalize).collect()
+    }
+
+    /// The question in the first locale of `chain` with all of its text, so a
+    /// question is never asked with its answers in another language. `None` if
+    /// no locale in the chain has it all.
+    pub fn localize(&self, chain: &[String]) -> Option<LocalizedQuestion> {
+        chain.iter().find_map(|locale| {
+            let only = [locale.clone()];
+            Some(LocalizedQuestion {
+                category: self.category.get(&only).unwrap_or_default().to_string(),
+                difficulty: self.difficulty.clone(),
+                question: self.question.get(&only)?.to_string(),
+                correct_answer: self.correct_answer.get(&only)?.to_string(),
+                incorrect_answers: self
+                    .incorrect_answers
+                    .iter()
+                    .map(|answer| answer.get(&only).map(str::to_string))
+                    .collect::<Option<_>>()?,
+                image: self.image.clone(),
+                audio: self.audio.clone(),
+            })
+        })
+    }
+}
@@ -0,0 +1,673 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use rand::seq::SliceRandom;
+
+use crate::{
+    dialog::{ShowToast, Toast},
+    loading::PackAssets,
+    lobby::{
+        format::{GameFormat, Round},
+        DifficultyRamp, QuestionCategories, QuestionDifficulty, QuestionLanguage, QuestionTypes,
+    },
+    trivia::{
+        question::{LocalizedQuestion, Question},
+        source::{
+            pack::{available_packs, QuestionPack},
+            provider::{Capabilities, ProviderRegistry, ProviderTask, QuestionQuery},
+        },
+    },
+    GameState,
+};
+
+/// Puts together the questions of a game from the sources the host mixed
+pub struct QuestionSetPlugin;
+
+impl Plugin for QuestionSetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QuestionSet>()
+            .add_event::<QuestionSetReady>()
+            .add_systems(OnEnter(GameState::Playing), start_question_set)
+            .add_systems(
+                Update,
+                finish_question_set.run_if(resource_exists::<PendingSet>),
+            );
+    }
+}
+
+/// Where some of the questions of a round come from
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum QuestionOrigin {
+    /// The question provider with this id, in the categories the host picked
+    Provider(String),
+    /// The pack with this name
+    Pack(String),
+}
+
+/// How many questions of a round come from one source
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct MixPart {
+    pub origin: QuestionOrigin,
+    pub quota: usize,
+}
+
+/// How the questions of the sources of a round are put in order
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum MixOrder {
+    /// Mixed together, spread evenly by how many each source has
+    #[default]
+    Interleave,
+    /// Each source after the other, in the order they're listed
+    Sequential,
+}
+
+impl MixOrder {
+    pub const ALL: [Self; 2] = [Self::Interleave, Self::Sequential];
+}
+
+/// The sources the host picked for a round, and how many questions each gives
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct SourceMix {
+    pub parts: Vec<MixPart>,
+    pub order: MixOrder,
+}
+
+impl SourceMix {
+    /// How many questions the game would have if every source had enough
+    pub fn total(&self) -> usize {
+        self.parts.iter().map(|part| part.quota).sum()
+    }
+}
+
+/// A question of a game, and the round it's asked in
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetQuestion {
+    pub question: LocalizedQuestion,
+    /// The index of the round in the [`GameFormat`]
+    pub round: usize,
+}
+
+/// The questions of the game have been put together
+#[derive(Event, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuestionSetReady;
+
+/// The questions of the game being played, in the order they're asked
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub struct QuestionSet {
+    pub questions: Vec<SetQuestion>,
+    /// What the sources gave beyond the quotas of the rounds, for breaking
+    /// ties, with those of the last round first
+    pub spare: Vec<SetQuestion>,
+}
+
+impl QuestionSet {
+    /// Puts the rounds of `format` together from the pools of questions of
+    /// their sources, by round and then by part of the round's mix. Questions
+    /// already taken for an earlier round or pool are skipped, and so are
+    /// questions without their text in a locale of `chain`. What's left over
+    /// is kept spare.
+    pub fn assemble(format: &GameFormat, pools: Vec<Vec<Vec<Question>>>, chain: &[String]) -> Self {
+        let mut seen = HashSet::new();
+        let mut questions = Vec::new();
+        let mut spare = Vec::new();
+        for (round, (settings, pools)) in format.rounds.iter().zip(pools).enumerate() {
+            let (taken, left) = Self::assemble_round(&settings.mix(), pools, chain, &mut seen);
+            let in_round = move |question| SetQuestion { question, round };
+            questions.extend(taken.into_iter().map(in_round));
+            spare.push(left.into_iter().map(in_round).collect::<Vec<_>>());
+        }
+        Self {
+            questions,
+            spare: spare.into_iter().rev().flatten().collect(),
+        }
+    }
+
+    /// Takes the quota of each part of `mix` from the pool of questions at the
+    /// same index, skipping questions in `seen`. A pool without enough
+    /// questions gives what it has. The questions of the round, and those left
+    /// over.
+    fn assemble_round(
+        mix: &SourceMix,
+        pools: Vec<Vec<Question>>,
+        chain: &[String],
+        seen: &mut HashSet<String>,
+    ) -> (Vec<LocalizedQuestion>, Vec<LocalizedQuestion>) {
+        let mut left = Vec::new();
+        let taken = mix
+            .parts
+            .iter()
+            .zip(pools)
+            .map(|(part, pool)| {
+                let mut taken = pool
+                    .into_iter()
+                    .filter(|question| {
+                        let keys = question.keys();
+                        let new = keys.iter().all(|key| !seen.contains(key));
+                        seen.extend(keys);
+                        new
+                    })
+                    .filter_map(|question| question.localize(chain))
+                    .collect::<Vec<_>>();
+                left.extend(taken.split_off(part.quota.min(taken.len())));
+                if taken.len() < part.quota {
+                    warn!(
+                        "Only found {} of {} questions in {:?}",
+                        taken.len(),
+                        part.quota,
+                        part.origin
+                    );
+                }
+                taken
+            })
+            .filter(|taken| !taken.is_empty())
+            .collect::<Vec<_>>();
+
+        let taken = match mix.order {
+            MixOrder::Sequential => taken.into_iter().flatten().collect(),
+            MixOrder::Interleave => {
+                // Each question goes where it would fall if its source's
+                // questions were spaced evenly through the round
+                let mut spread = taken
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(part, questions)| {
+                        let len = questions.len();
+                        questions.into_iter().enumerate().map(move |(i, question)| {
+                            ((2 * i + 1) * 1000 / (2 * len), part, question)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                spread.sort_by_key(|(at, part, _)| (*at, *part));
+                spread
+                    .into_iter()
+                    .map(|(_, _, question)| question)
+                    .collect()
+            }
+        };
+        (taken, left)
+    }
+
+    /// Asks one more question, in the last round, to break a tie: a spare one
+    /// if there are any left, or else those already asked come round again
+    pub fn add_tie_breaker(&mut self) {
+        let Some(last) = self.questions.last().map(|question| question.round) else {
+            return;
+        };
+        if self.spare.is_empty() {
+            self.spare = self.questions.clone();
+        }
+        let mut question = self.spare.remove(0);
+        question.round = last;
+        self.questions.push(question);
+    }
+
+    /// Puts the questions of each round in order from easiest to hardest,
+    /// keeping the order of questions that are as hard as each other
+    pub fn ramp_difficulty(&mut self) {
+        self.questions.sort_by_key(|question| {
+            (
+                question.round,
+                QuestionDifficulty::rank(&question.question.difficulty),
+            )
+        });
+    }
+
+    /// How many rounds have questions
+    pub fn rounds(&self) -> usize {
+        let mut rounds = self
+            .questions
+            .iter()
+            .map(|question| question.round)
+            .collect::<Vec<_>>();
+        rounds.dedup();
+        rounds.len()
+    }
+
+    /// Whether the question at `index` is the last of its round, or of the game
+    pub fn ends_round(&self, index: usize) -> bool {
+        match (self.questions.get(index), self.questions.get(index + 1)) {
+            (Some(question), Some(next)) => question.round != next.round,
+            _ => true,
+        }
+    }
+}
+
+/// The questions of a source of the mix, as they come in
+#[derive(Debug)]
+enum Pool {
+    /// Still being fetched from a provider that can narrow questions down by
+    /// what it's capable of
+    Fetching(ProviderTask<Vec<Question>>, Capabilities),
+    Done(Vec<Question>),
+}
+
+/// The questions each source has given so far, by round and then by the
+/// index of its part of the round's mix
+#[derive(Resource, Debug, Default)]
+struct PendingSet(Vec<Vec<Pool>>);
+
+/// Which of the questions of a source the host allowed in a round
+#[derive(Clone, Copy)]
+struct QuestionFilter<'a> {
+    categories: &'a QuestionCategories,
+    difficulty: &'a QuestionDifficulty,
+    types: &'a QuestionTypes,
+}
+
+impl<'a> QuestionFilter<'a> {
+    fn new(round: &'a Round) -> Self {
+        Self {
+            categories: &round.categories,
+            difficulty: &round.difficulty,
+            types: &round.types,
+        }
+    }
+
+    /// Whether the host allowed `question`, from a provider with `capabilities`
+    /// or from a pack if `None`. Packs have their own categories, so only
+    /// providers are checked against the categories picked. Questions are only
+    /// checked for difficulty if their provider can tell it apart, but types
+    /// are told apart by the answers, even for providers that can't be asked
+    /// for one.
+    fn allows(&self, question: &Question, capabilities: Option<Capabilities>) -> bool {
+        let from_pack = capabilities.is_none();
+        let capabilities = capabilities.unwrap_or(Capabilities {
+            difficulty: true,
+            types: true,
+            language: true,
+        });
+        let category_allowed = from_pack
+            || self.categories.is_empty()
+            || question.category.texts().iter().any(|name| {
+                self.categories
+                    .iter()
+                    .any(|category| category.name == *name)
+            });
+        let difficulty_allowed = !capabilities.difficulty
+            || self.difficulty.is_empty()
+            || self.difficulty.contains(&question.difficulty);
+        category_allowed && difficulty_allowed && self.types.allows(question)
+    }
+
+    /// What to ask a provider with `capabilities` for to fill `quota`. Types
+    /// are left out for providers that can't be asked for them.
+    fn query(
+        &self,
+        quota: usize,
+        language: &QuestionLanguage,
+        capabilities: Capabilities,
+    ) -> QuestionQuery {
+        QuestionQuery {
+            amount: quota,
+            categories: self.categories.to_vec(),
+            difficulties: self.difficulty.to_vec(),
+            multiple_choice: self.types.multiple_choice().filter(|_| capabilities.types),
+            language: language.to_vec(),
+        }
+    }
+}
+
+/// Asks each source of each round for its questions. Packs have theirs at
+/// hand, providers fetch them. Each provider queues its fetches and sends them
+/// as fast as it allows, so rounds from the same provider don't get refused.
+fn start_question_set(
+    format: Res<GameFormat>,
+    language: Res<QuestionLanguage>,
+    registry: Res<ProviderRegistry>,
+    assets: Res<PackAssets>,
+    packs: Res<Assets<QuestionPack>>,
+    mut commands: Commands,
+) {
+    let available = available_packs(&assets, &packs)
+        .into_iter()
+        .map(|pack| (pack.name.clone(), pack))
+        .collect::<HashMap<_, _>>();
+    let pools = format
+        .rounds
+        .iter()
+        .map(|round| {
+            let filter = QuestionFilter::new(round);
+            round
+                .mix()
+                .parts
+                .iter()
+                .map(|part| match &part.origin {
+                    QuestionOrigin::Provider(id) => match registry.get(id) {
+                        Ok(provider) => Pool::Fetching(
+                            provider.fetch_questions(
+                                &filter.query(part.quota, &language, provider.capabilities()),
+                                &mut commands,
+                            ),
+                            provider.capabilities(),
+                        ),
+                        Err(error) => {
+                            warn!("{error}");
+                            Pool::Done(Vec::new())
+                        }
+                    },
+                    QuestionOrigin::Pack(name) => Pool::Done(
+                        available
+                            .get(name)
+                            .map(|pack| pack.questions.clone())
+                            .unwrap_or_else(|| {
+                                warn!("There is no pack called {name}");
+                                Vec::new()
+                            })
+                            .into_iter()
+                            .filter(|question| filter.allows(question, None))
+                            .collect(),
+                    ),
+                })
+                .collect()
+        })
+        .collect();
+    commands.insert_resource(QuestionSet::default());
+    commands.insert_resource(PendingSet(pools));
+}
+
+/// Collects the questions fetched for each round, and puts the game's
+/// questions together once every source has given its own. Sources that
+/// couldn't be fetched, and weren't cached, give none.
+fn finish_question_set(
+    mut pending: ResMut<PendingSet>,
+    format: Res<GameFormat>,
+    language: Res<QuestionLanguage>,
+    ramp: Res<DifficultyRamp>,
+    mut commands: Commands,
+    mut toasts: EventWriter<ShowToast>,
+    mut ready: EventWriter<QuestionSetReady>,
+) {
+    for (round, pools) in format.rounds.iter().zip(pending.0.iter_mut()) {
+        let filter = QuestionFilter::new(round);
+        for pool in pools.iter_mut() {
+            let Pool::Fetching(task, capabilities) = pool else {
+                continue;
+            };
+            let Some(result) = task.take() else {
+                continue;
+            };
+            let questions = match result {
+                Ok(fetched) => fetched.value,
+                Err(error) => {
+                    warn!("Couldn't fetch questions: {error}");
+                    toasts.send(ShowToast(Toast::error("toast.questions_failed")));
+                    Vec::new()
+                }
+            };
+            let capabilities = *capabilities;
+            *pool = Pool::Done(
+                questions
+                    .into_iter()
+                    .filter(|question| filter.allows(question, Some(capabilities)))
+                    .collect(),
+            );
+        }
+    }
+    if pending
+        .0
+        .iter()
+        .flatten()
+        .any(|pool| matches!(pool, Pool::Fetching(..)))
+    {
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+    let pools = std::mem::take(&mut pending.0)
+        .into_iter()
+        .map(|pools| {
+            pools
+                .into_iter()
+                .map(|pool| {
+                    let Pool::Done(mut pool) = pool else {
+                        unreachable!("Every pool is done");
+                    };
+                    pool.shuffle(&mut rng);
+                    pool
+                })
+                .collect()
+        })
+        .collect();
+    let mut set = QuestionSet::assemble(&format, pools, &language);
+    if **ramp {
+        set.ramp_difficulty();
+    }
+    info!(
+        "Assembled {} of {} questions in {} rounds",
+        set.questions.len(),
+        format.rounds.iter().map(Round::len).sum::<usize>(),
+        set.rounds()
+    );
+    commands.insert_resource(set);
+    commands.remove_resource::<PendingSet>();
+    ready.send(QuestionSetReady);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::LocalizedText;
+
+    fn question(text: &str) -> Question {
+        Question {
+            category: LocalizedText::Plain("General".to_string()),
+            difficulty: "Easy".to_string(),
+            question: LocalizedText::Plain(text.to_string()),
+            correct_answer: LocalizedText::Plain("Yes".to_string()),
+            incorrect_answers: vec![LocalizedText::Plain("No".to_string())],
+            image: None,
+            audio: None,
+        }
+    }
+
+    fn pool(texts: &[&str]) -> Vec<Question> {
+        texts.iter().map(|text| question(text)).collect()
+    }
+
+    /// A round with `provider` questions from a provider and `pack` from a pack
+    fn round(provider: usize, pack: usize, order: MixOrder) -> Round {
+        Round {
+            provider_questions: provider,
+            pack: Some("Pack".to_string()),
+            pack_questions: pack,
+            order,
+            ..default()
+        }
+    }
+
+    fn assemble(rounds: Vec<Round>, pools: Vec<Vec<Vec<Question>>>) -> QuestionSet {
+        QuestionSet::assemble(&GameFormat { rounds }, pools, &["en".to_string()])
+    }
+
+    #[test]
+    fn skips_questions_already_taken() {
+        let set = assemble(
+            vec![
+                round(2, 2, MixOrder::Sequential),
+                round(2, 0, MixOrder::Sequential),
+            ],
+            vec![
+                vec![pool(&["a", "What is B?", "c"]), pool(&["what is  b?", "d"])],
+                vec![pool(&["a", "e"])],
+            ],
+        );
+        assert_eq!(
+            texts(&set.questions),
+            [("a", 0), ("What is B?", 0), ("d", 0), ("e", 1)]
+        );
+        // Left over from its pool, so not asked in a later round either
+        assert_eq!(texts(&set.spare), [("c", 0)]);
+    }
+
+    #[test]
+    fn short_pools_give_what_they_have() {
+        let set = assemble(
+            vec![round(3, 2, MixOrder::Sequential)],
+            vec![vec![pool(&["a"]), pool(&["b", "c", "d"])]],
+        );
+        assert_eq!(texts(&set.questions), [("a", 0), ("b", 0), ("c", 0)]);
+        assert_eq!(texts(&set.spare), [("d", 0)]);
+    }
+
+    #[test]
+    fn empty_pools_leave_their_round_out() {
+        let set = assemble(
+            vec![
+                round(2, 0, MixOrder::Sequential),
+                round(2, 0, MixOrder::Sequential),
+            ],
+            vec![vec![pool(&[])], vec![pool(&["a", "b"])]],
+        );
+        assert_eq!(texts(&set.questions), [("a", 1), ("b", 1)]);
+        assert_eq!(set.rounds(), 1);
+    }
+
+    #[test]
+    fn interleaves_sources_evenly() {
+        let set = assemble(
+            vec![round(4, 2, MixOrder::Interleave)],
+            vec![vec![pool(&["p1", "p2", "p3", "p4"]), pool(&["k1", "k2"])]],
+        );
+        assert_eq!(
+            texts(&set.questions),
+            [
+                ("p1", 0),
+                ("k1", 0),
+                ("p2", 0),
+                ("p3", 0),
+                ("k2", 0),
+                ("p4", 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn interleaves_as_many_of_each_alternately() {
+        let set = assemble(
+            vec![round(2, 2, MixOrder::Interleave)],
+            vec![vec![pool(&["p1", "p2"]), pool(&["k1", "k2"])]],
+        );
+        assert_eq!(
+            texts(&set.questions),
+            [("p1", 0), ("k1", 0), ("p2", 0), ("k2", 0)]
+        );
+    }
+
+    fn asked(text: &str, difficulty: &str, round: usize) -> SetQuestion {
+        SetQuestion {
+            question: LocalizedQuestion {
+                question: text.to_string(),
+                difficulty: difficulty.to_string(),
+                ..default()
+            },
+            round,
+        }
+    }
+
+    fn texts(questions: &[SetQuestion]) -> Vec<(&str, usize)> {
+        questions
+            .iter()
+            .map(|question| (question.question.question.as_str(), question.round))
+            .collect()
+    }
+
+    #[test]
+    fn ramps_difficulty_within_each_round() {
+        let mut set = QuestionSet {
+            questions: vec![
+                asked("a", "Hard", 0),
+                asked("b", "Easy", 0),
+                asked("c", "Medium", 0),
+                asked("d", "Easy", 1),
+                asked("e", "Hard", 1),
+                asked("f", "Easy", 1),
+            ],
+            ..default()
+        };
+        set.ramp_difficulty();
+        assert_eq!(
+            texts(&set.questions),
+            [("b", 0), ("c", 0), ("a", 0), ("d", 1), ("f", 1), ("e", 1)]
+        );
+    }
+
+    #[test]
+    fn ramping_keeps_unknown_difficulties_in_the_middle() {
+        let mut set = QuestionSet {
+            questions: vec![
+                asked("a", "Hard", 0),
+                asked("b", "", 0),
+                asked("c", "Medium", 0),
+                asked("d", "Easy", 0),
+            ],
+            ..default()
+        };
+        set.ramp_difficulty();
+        assert_eq!(
+            texts(&set.questions),
+            [("d", 0), ("b", 0), ("c", 0), ("a", 0)]
+        );
+    }
+
+    #[test]
+    fn tie_breakers_are_spare_questions_then_repeats() {
+        let mut set = QuestionSet {
+            questions: vec![asked("a", "Easy", 0), asked("b", "Easy", 1)],
+            spare: vec![asked("c", "Easy", 1), asked("d", "Easy", 0)],
+        };
+        set.add_tie_breaker();
+        set.add_tie_breaker();
+        set.add_tie_breaker();
+        assert_eq!(
+            texts(&set.questions),
+            [("a", 0), ("b", 1), ("c", 1), ("d", 1), ("a", 1)]
+        );
+    }
+
+    /// A round that only asks multiple choice questions
+    fn multiple_choice() -> Round {
+        let mut round = Round::default();
+        round.types.push("Multiple Choice".to_string());
+        round
+    }
+
+    const TRIVIA_API: Capabilities = Capabilities {
+        difficulty: true,
+        types: false,
+        language: false,
+    };
+
+    #[test]
+    fn types_are_checked_even_if_the_provider_cant_be_asked_for_them() {
+        let round = multiple_choice();
+        let filter = QuestionFilter::new(&round);
+        let true_false = question("a");
+        let mut multiple = question("b");
+        multiple
+            .incorrect_answers
+            .push(LocalizedText::Plain("Maybe".to_string()));
+        assert!(!filter.allows(&true_false, Some(TRIVIA_API)));
+        assert!(filter.allows(&multiple, Some(TRIVIA_API)));
+        assert!(!filter.allows(&true_false, None));
+    }
+
+    #[test]
+    fn types_are_only_asked_for_if_the_provider_can_be() {
+        let round = multiple_choice();
+        let filter = QuestionFilter::new(&round);
+        let language = QuestionLanguage::default();
+        assert_eq!(
+            filter.query(10, &language, TRIVIA_API).multiple_choice,
+            None
+        );
+        let open_tdb = Capabilities {
+            types: true,
+            ..TRIVIA_API
+        };
+        assert_eq!(
+            filter.query(10, &language, open_tdb).multiple_choice,
+            Some(true)
+        );
+    }
+}
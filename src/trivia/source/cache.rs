@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_timer, utils::HashMap};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    locale::Localization,
+    storage::{self, Pending},
+};
+
+/// Where the cache is kept, in the user data directory
+const CACHE_FILE: &str = "cache/sources.json";
+
+/// How long something fetched is kept, in seconds
+const MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// The most things kept, dropping the oldest first
+const MAX_ENTRIES: usize = 100;
+
+/// How often changes to the cache are saved
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Keeps what network sources fetch, so they can fall back to it when the
+/// network is down
+pub struct CachePlugin;
+
+impl Plugin for CachePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SourceCache>()
+            .add_systems(Startup, start_loading_cache)
+            .add_systems(
+                Update,
+                (
+                    finish_loading_cache.run_if(not(cache_loaded)),
+                    save_cache.run_if(on_timer(SAVE_INTERVAL)),
+                ),
+            );
+    }
+}
+
+/// Something fetched from the network, and when
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cached<T> {
+    /// When it was fetched, in seconds since the Unix epoch
+    pub fetched_at: u64,
+    pub value: T,
+}
+
+/// What network sources fetched last, by keys like `"opentdb/categories"`
+#[derive(Resource, Default)]
+pub struct SourceCache {
+    entries: HashMap<String, Cached<serde_json::Value>>,
+    /// The cache saved by earlier runs, while it's being read
+    loading: Option<Pending<Option<String>>>,
+    /// Whether anything changed since the cache was last saved
+    changed: bool,
+}
+
+impl SourceCache {
+    /// What was last fetched under `key`, if anything was
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<Cached<T>> {
+        let cached = self.entries.get(key)?;
+        match serde_json::from_value(cached.value.clone()) {
+            Ok(value) => Some(Cached {
+                fetched_at: cached.fetched_at,
+                value,
+            }),
+            Err(error) => {
+                warn!("The cached {key} can't be read: {error}");
+                None
+            }
+        }
+    }
+
+    /// Whether what earlier runs saved has been read
+    pub fn is_loaded(&self) -> bool {
+        self.loading.is_none()
+    }
+
+    /// Remembers `value` as just fetched under `key`. The cache is saved a
+    /// little later, in the background.
+    pub fn insert<T: Serialize>(&mut self, key: &str, value: &T) {
+        let value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(error) => {
+                warn!("{key} can't be cached: {error}");
+                return;
+            }
+        };
+        let fetched_at = now();
+        self.entries
+            .insert(key.to_string(), Cached { fetched_at, value });
+        self.evict(fetched_at);
+        self.changed = true;
+    }
+
+    /// Drops what's older than [`MAX_AGE_SECS`], then the oldest of what's
+    /// left until there are at most [`MAX_ENTRIES`]
+    fn evict(&mut self, now: u64) {
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, cached| now.saturating_sub(cached.fetched_at) <= MAX_AGE_SECS);
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        if excess > 0 {
+            let mut oldest = self
+                .entries
+                .iter()
+                .map(|(key, cached)| (cached.fetched_at, key.clone()))
+                .collect::<Vec<_>>();
+            oldest.sort_unstable();
+            for (_, key) in oldest.into_iter().take(excess) {
+                self.entries.remove(&key);
+            }
+        }
+        if self.entries.len() != before {
+            self.changed = true;
+        }
+    }
+}
+
+/// Whether what earlier runs saved has been read into the [`SourceCache`], so
+/// systems that fall back to it can wait for that
+pub fn cache_loaded(cache: Res<SourceCache>) -> bool {
+    cache.is_loaded()
+}
+
+/// The time, in seconds since the Unix epoch
+pub fn now() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs())
+    }
+    // The standard library has no clock on the web
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+}
+
+/// What sources filled in from the cache say about it, like "Using cached
+/// questions (2 days old)"
+pub fn cached_note(localization: &Localization, fetched_at: u64) -> String {
+    let age = now().saturating_sub(fetched_at);
+    let (count, unit) = match age {
+        age if age < 60 * 60 => (age / 60, "minute"),
+        age if age < 24 * 60 * 60 => (age / (60 * 60), "hour"),
+        age => (age / (24 * 60 * 60), "day"),
+    };
+    let count = count.max(1);
+    let key = if count == 1 {
+        format!("age.{unit}")
+    } else {
+        format!("age.{unit}s")
+    };
+    let age = localization.format(&key, &[("count", &count)]);
+    localization.format("source.cached", &[("age", &age)])
+}
+
+fn start_loading_cache(mut cache: ResMut<SourceCache>) {
+    cache.loading = Some(storage::read_in_background(CACHE_FILE));
+}
+
+fn finish_loading_cache(mut cache: ResMut<SourceCache>) {
+    let Some(text) = cache.loading.as_ref().and_then(Pending::take) else {
+        return;
+    };
+    cache.loading = None;
+    let Some(text) = text else {
+        return;
+    };
+    match serde_json::from_str::<HashMap<String, Cached<serde_json::Value>>>(&text) {
+        Ok(entries) => {
+            // Anything fetched while the cache was loading is newer
+            for (key, cached) in entries {
+                cache.entries.entry(key).or_insert(cached);
+            }
+        }
+        Err(error) => warn!("The cache can't be read, starting over: {error}"),
+    }
+    cache.evict(now());
+}
+
+/// Saves the cache in the background if it changed, once what earlier runs
+/// saved has been read, so that isn't overwritten
+fn save_cache(mut cache: ResMut<SourceCache>) {
+    if !cache.changed || !cache.is_loaded() {
+        return;
+    }
+    cache.changed = false;
+    match serde_json::to_string(&cache.entries) {
+        Ok(text) => storage::write_in_background(CACHE_FILE, text),
+        Err(error) => warn!("The cache can't be saved: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(ages: &[u64]) -> SourceCache {
+        let mut cache = SourceCache::default();
+        for (i, fetched_at) in ages.iter().enumerate() {
+            cache.entries.insert(
+                format!("key{i}"),
+                Cached {
+                    fetched_at: *fetched_at,
+                    value: serde_json::Value::Null,
+                },
+            );
+        }
+        cache
+    }
+
+    #[test]
+    fn drops_expired_entries() {
+        let now = MAX_AGE_SECS * 2;
+        let mut cache = cache(&[now, now - MAX_AGE_SECS, now - MAX_AGE_SECS - 1]);
+        cache.evict(now);
+        assert_eq!(cache.entries.len(), 2);
+        assert!(!cache.entries.contains_key("key2"));
+        assert!(cache.changed);
+    }
+
+    #[test]
+    fn keeps_the_newest_entries() {
+        let ages = (0..MAX_ENTRIES as u64 + 3).collect::<Vec<_>>();
+        let mut cache = cache(&ages);
+        cache.evict(MAX_ENTRIES as u64);
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        for i in 0..3 {
+            assert!(!cache.entries.contains_key(&format!("key{i}")));
+        }
+    }
+}
@@ -1,33 +1,119 @@
-use std::ops::Index;
+use std::{fmt, ops::Index};
 
 use bevy::{prelude::*, reflect::TypePath, utils::HashSet};
-use open_tdb::{OpenTdbPlugin, OpenTdbSource};
+use bevy_common_assets::ron::RonAssetPlugin;
+use cache::{cache_loaded, CachePlugin, SourceCache};
+use open_tdb::OpenTdbPlugin;
+use pack::{PackList, QuestionPack};
+use provider::{ProviderError, ProviderList, ProviderRegistry, ProviderSource};
 use serde::{Deserialize, Serialize};
-use trivia_api::TriviaApiSource;
+use trivia_api::TriviaApiPlugin;
 
+use crate::{
+    dialog::{ShowToast, Toast},
+    locale::Localization,
+};
+
+pub mod cache;
 pub mod open_tdb;
+pub mod pack;
+pub mod provider;
 pub mod trivia_api;
 
 pub struct SourcePlugin;
 
 impl Plugin for SourcePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(OpenTdbPlugin);
+        app.init_resource::<ProviderRegistry>()
+            .add_plugins((
+                OpenTdbPlugin,
+                TriviaApiPlugin,
+                CachePlugin,
+                RonAssetPlugin::<QuestionPack>::new(&["pack.ron"]),
+            ))
+            .add_systems(
+                Update,
+                (
+                    ProviderSource::update_system,
+                    ProviderSource::finalize_system.after(FetchSystems),
+                    ProviderList::update_system,
+                    PackList::update_system,
+                ),
+            );
     }
 }
 
+/// How long a fetch can take before the source gives up on it
+const FETCH_TIMEOUT_SECS: f32 = 10.0;
+
 #[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Fetching;
 
 #[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub struct DoneFetching;
 
+/// The last fetch for this source failed. Holds why, as a locale key and the
+/// values to fill into it, so the reason follows the player's language.
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FetchError {
+    pub key: String,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl FetchError {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, name: &'static str, value: &dyn ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    /// The reason to show the player
+    pub fn localize(&self, localization: &Localization) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|(name, value)| (*name, value as &dyn ToString))
+            .collect::<Vec<_>>();
+        localization.format(&self.key, &args)
+    }
+}
+
+/// The key and its arguments, for logs
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.key)?;
+        for (name, value) in &self.args {
+            write!(f, " {name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts down to giving up on a fetch, from when it started
+#[derive(Component, Clone, Debug, Deref, DerefMut)]
+pub struct FetchTimeout(Timer);
+
+/// The source was filled in from the cache, since it couldn't be fetched.
+/// Holds when it was cached, in seconds since the Unix epoch.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FromCache(pub u64);
+
+/// The options a menu dropdown lists
 #[derive(Deserialize, TypePath, Clone, Debug, PartialEq, Component)]
 pub enum TriviaSource {
     String(StringSource),
-    // TODO: make this configurable
-    OpenTdb(OpenTdbSource),
-    TriviaApi(TriviaApiSource),
+    /// The categories of the question provider with this id
+    Provider(ProviderSource),
+    /// The question providers registered
+    Providers(ProviderList),
+    /// The question packs the host has
+    Packs(PackList),
 }
 
 impl Default for TriviaSource {
@@ -40,29 +126,46 @@ impl TriviaSource {
     pub fn iter(&self) -> Box<dyn Iterator<Item = String>> {
         match self {
             Self::String(source) => Box::new(source.clone().0.into_iter()),
-            Self::OpenTdb(opentdb) => Box::new(opentdb.iter()),
-            Self::TriviaApi(_) => Box::new(std::iter::empty()),
+            Self::Provider(provider) => Box::new(provider.iter()),
+            Self::Providers(providers) => Box::new(providers.iter()),
+            Self::Packs(packs) => Box::new(packs.iter()),
         }
     }
 
     pub fn get_selected(&self) -> Option<HashSet<usize>> {
         match self {
             Self::String(_) => None,
-            Self::OpenTdb(opentdb) => opentdb.get_selected(),
-            Self::TriviaApi(_) => None,
+            Self::Provider(provider) => provider.get_selected(),
+            Self::Providers(_) | Self::Packs(_) => None,
         }
     }
 
     pub fn set_selected(&mut self, selected: HashSet<usize>) {
         match self {
             Self::String(_) => {}
-            Self::OpenTdb(opentdb) => opentdb.set_selected(selected),
-            Self::TriviaApi(_) => {}
+            Self::Provider(provider) => provider.set_selected(selected),
+            Self::Providers(_) | Self::Packs(_) => {}
         }
     }
-}
 
-static EMPTY_STRING: String = String::new();
+    /// Forget any fetched state so the source is fetched again.
+    /// The caller is responsible for removing [`Fetching`] and [`FetchError`]
+    pub fn reset(&mut self) {
+        match self {
+            Self::String(_) => {}
+            Self::Provider(provider) => provider.reset(),
+            Self::Providers(_) | Self::Packs(_) => {}
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
 impl Index<usize> for TriviaSource {
     type Output = String;
@@ -70,8 +173,9 @@ impl Index<usize> for TriviaSource {
     fn index(&self, index: usize) -> &Self::Output {
         match self {
             Self::String(source) => &source.0[index],
-            Self::OpenTdb(source) => &source[index],
-            Self::TriviaApi(_) => &EMPTY_STRING,
+            Self::Provider(source) => &source[index].name,
+            Self::Providers(source) => &source[index],
+            Self::Packs(source) => &source[index],
         }
     }
 }
@@ -79,7 +183,108 @@ impl Index<usize> for TriviaSource {
 #[derive(Serialize, Deserialize, TypePath, Clone, Debug, PartialEq, Default)]
 pub struct StringSource(Vec<String>);
 
+impl StringSource {
+    pub fn new(options: Vec<String>) -> Self {
+        Self(options)
+    }
+}
+
 #[derive(Serialize, Deserialize, TypePath, Clone, Debug, PartialEq)]
 pub enum SourceEvent {
     Categories,
 }
+
+/// A fetch from the network, done by the entity it's a component of. The
+/// entity marks itself [`Fetching`] once the request is sent, and with
+/// [`FetchError`] if it fails.
+///
+/// Fetches that take too long give up, and ones that fail fall back to what
+/// was fetched last time, if it was cached.
+pub trait NetworkFetch: Component + fmt::Debug {
+    /// Fills in what was fetched last time from `cache`, and returns when that
+    /// was. `None` if nothing was cached.
+    fn restore(&mut self, cache: &SourceCache) -> Option<u64>;
+
+    /// Hands over what was fetched, or why it couldn't be, to whoever asked
+    /// for it. Holds when it was cached if it came from the cache.
+    fn finish(&mut self, result: Result<Option<u64>, ProviderError>);
+}
+
+/// Where fetches time out and fall back to the cache, for systems that need to
+/// see how fetches ended
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FetchSystems;
+
+pub trait AddNetworkFetch {
+    /// Times out fetches done by `F` components, and falls back to the cache
+    /// when they fail. The entities are despawned once the fetch has failed.
+    fn add_network_fetch<F: NetworkFetch>(&mut self) -> &mut Self;
+}
+
+impl AddNetworkFetch for App {
+    fn add_network_fetch<F: NetworkFetch>(&mut self) -> &mut Self {
+        self.add_systems(
+            Update,
+            (
+                start_fetch_timeouts::<F>,
+                time_out_fetches::<F>,
+                fall_back_to_cache::<F>.run_if(cache_loaded),
+            )
+                .chain()
+                .in_set(FetchSystems),
+        )
+    }
+}
+
+fn start_fetch_timeouts<F: NetworkFetch>(
+    mut commands: Commands,
+    started: Query<Entity, (With<F>, Added<Fetching>)>,
+) {
+    for entity in started.iter() {
+        commands
+            .entity(entity)
+            .insert(FetchTimeout(Timer::from_seconds(
+                FETCH_TIMEOUT_SECS,
+                TimerMode::Once,
+            )));
+    }
+}
+
+/// Fails fetches that take too long, rather than leave them loading forever.
+/// Responses that come in afterwards are ignored.
+fn time_out_fetches<F: NetworkFetch>(
+    mut fetches: Query<(Entity, &F, &mut FetchTimeout), (With<Fetching>, Without<FetchError>)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, fetch, mut timeout) in fetches.iter_mut() {
+        if timeout.tick(time.delta()).just_finished() {
+            warn!("Gave up fetching {fetch:?} after {FETCH_TIMEOUT_SECS} seconds");
+            commands
+                .entity(entity)
+                .insert(FetchError::new("source.timed_out"));
+        }
+    }
+}
+
+/// Fetches that failed give what they fetched last time, if anything. They
+/// wait for the cache to be read first, so fetches that fail at startup still
+/// find what earlier runs fetched.
+fn fall_back_to_cache<F: NetworkFetch>(
+    mut fetches: Query<(Entity, &mut F, &FetchError)>,
+    cache: Res<SourceCache>,
+    mut commands: Commands,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for (entity, mut fetch, error) in fetches.iter_mut() {
+        match fetch.restore(&cache) {
+            Some(fetched_at) => {
+                info!("Using the cached {fetch:?}");
+                fetch.finish(Ok(Some(fetched_at)));
+                toasts.send(ShowToast(Toast::info("toast.using_cache")));
+            }
+            None => fetch.finish(Err(ProviderError::Unreachable(error.clone()))),
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,226 +1,374 @@
-use std::ops::Index;
-
-use bevy::{
-    prelude::*,
-    utils::{hashbrown::hash_map::DefaultHashBuilder, HashSet},
-};
+use bevy::prelude::*;
 use bevy_http_client::{
-    prelude::{HttpTypedRequestTrait, TypedRequest, TypedResponse},
+    prelude::{HttpTypedRequestTrait, TypedRequest, TypedResponse, TypedResponseError},
     HttpClient,
 };
-use serde::Deserialize;
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    trivia::source::{DoneFetching, Fetching, TriviaSource},
-    utils::BiHashMap,
+    locale::LocalizedText,
+    trivia::{
+        question::{capitalize, Question, DIFFICULTIES},
+        source::{
+            cache::SourceCache,
+            provider::{
+                Capabilities, Category, Fetched, ProviderError, ProviderTask, QuestionProvider,
+                QuestionQuery, RegisterQuestionProvider,
+            },
+            AddNetworkFetch, FetchError, Fetching, NetworkFetch,
+        },
+    },
 };
 
+/// What the categories are cached as
+const CATEGORIES_KEY: &str = "opentdb/categories";
+
+/// OpenTDB answers one request every 5 seconds from each address, and refuses
+/// the rest, so fetches wait at least this long after the last one was sent
+const REQUEST_INTERVAL_SECS: f32 = 5.0;
+
 pub struct OpenTdbPlugin;
 
 impl Plugin for OpenTdbPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            FixedUpdate,
-            (OpenTdbSource::update_system, OpenTdbSource::finalize_system),
-        );
+        app.register_question_provider(OpenTdb)
+            .add_network_fetch::<OpenTdbSource>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    OpenTdbSource::update_system,
+                    OpenTdbSource::finalize_system,
+                    OpenTdbSource::error_system,
+                ),
+            );
         app.register_request_type::<OpenTdbResponse>();
     }
 }
 
-#[derive(Deserialize, TypePath, Debug, Clone, PartialEq)]
-/// Use OpenTDB as the source of trivia questions
-#[serde(from = "SourceType")]
-pub enum OpenTdbSource {
-    /// The Indicates we need to fetch the categories
-    Categories(Categories),
+/// Questions from the [Open Trivia Database](https://opentdb.com/), in English
+pub struct OpenTdb;
+
+impl OpenTdb {
+    /// The most questions the Open Trivia Database gives at once
+    pub const MAX_AMOUNT: usize = 50;
 }
 
-impl OpenTdbSource {
-    pub fn iter(&self) -> Box<dyn Iterator<Item = String>> {
-        match self {
-            Self::Categories(categories) => categories.iter(),
-        }
+impl QuestionProvider for OpenTdb {
+    fn id(&self) -> &'static str {
+        "opentdb"
     }
 
-    pub fn get_selected(&self) -> Option<HashSet<usize>> {
-        match self {
-            Self::Categories(categories) => categories.get_selected(),
-        }
+    fn name(&self) -> &'static str {
+        "Open Trivia Database"
     }
 
-    pub fn set_selected(&mut self, selected: HashSet<usize>) {
-        match self {
-            Self::Categories(categories) => categories.set_selected(selected),
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            difficulty: true,
+            types: true,
+            language: false,
         }
     }
 
-    pub fn update(
-        &mut self,
+    fn fetch_categories(&self, commands: &mut Commands) -> ProviderTask<Vec<Category>> {
+        let task = ProviderTask::default();
+        commands.spawn((
+            Name::new("opentdb::categories"),
+            OpenTdbSource::Categories(Categories {
+                fetched: None,
+                task: task.clone(),
+            }),
+        ));
+        task
+    }
+
+    /// It takes one category, difficulty and type, or any, so when the host
+    /// picked a few the most it gives are asked for, to be narrowed down
+    /// afterwards
+    fn fetch_questions(
+        &self,
+        query: &QuestionQuery,
         commands: &mut Commands,
-        entity: Entity,
-        events: &mut EventWriter<TypedRequest<OpenTdbResponse>>,
-    ) {
+    ) -> ProviderTask<Vec<Question>> {
+        let category = match query.categories.as_slice() {
+            [category] => category.id.parse().ok(),
+            _ => None,
+        };
+        let difficulty = match query.difficulties.as_slice() {
+            [difficulty] => Some(difficulty.clone()),
+            _ => None,
+        };
+        let narrowed = query.categories.len() > 1
+            || (2..DIFFICULTIES.len()).contains(&query.difficulties.len());
+        let amount = if narrowed {
+            Self::MAX_AMOUNT
+        } else {
+            query.amount.min(Self::MAX_AMOUNT)
+        };
+        let task = ProviderTask::default();
+        commands.spawn((
+            Name::new("opentdb::questions"),
+            OpenTdbSource::Questions(Questions {
+                amount,
+                category,
+                difficulty,
+                multiple_choice: query.multiple_choice,
+                fetched: None,
+                task: task.clone(),
+            }),
+        ));
+        task
+    }
+}
+
+/// A fetch from the Open Trivia Database, by the entity it's a component of
+#[derive(Component, Debug)]
+pub enum OpenTdbSource {
+    /// The categories questions can be asked for in
+    Categories(Categories),
+
+    /// Questions for a game
+    Questions(Questions),
+}
+
+impl OpenTdbSource {
+    fn url(&self) -> String {
         match self {
-            Self::Categories(ref mut categories) => categories.update(commands, entity, events),
+            Self::Categories(_) => "https://opentdb.com/api_category.php".to_string(),
+            Self::Questions(questions) => questions.url(),
         }
     }
 
-    pub fn finalize(&mut self, commands: &mut Commands, entity: Entity, response: OpenTdbResponse) {
-        match self {
-            Self::Categories(ref mut categories) => categories.finalize(commands, entity, response),
+    /// Takes what was fetched from `response`. `None` if it isn't what this
+    /// fetch asked for, and an error if OpenTDB refused to give any questions.
+    fn finalize(
+        &mut self,
+        response: OpenTdbResponse,
+        cache: &mut SourceCache,
+    ) -> Option<Result<(), FetchError>> {
+        match (self, response) {
+            (Self::Categories(categories), OpenTdbResponse::Categories(response)) => {
+                if !response.trivia_categories.is_empty() {
+                    cache.insert(CATEGORIES_KEY, &response);
+                }
+                categories.load(response);
+                Some(Ok(()))
+            }
+            (Self::Questions(questions), OpenTdbResponse::Questions(response)) => {
+                // Anything but 0 means there weren't enough questions, or the
+                // request was refused. The fetch fails, so it falls back to the
+                // last questions that did come.
+                if response.response_code != 0 {
+                    warn!(
+                        "OpenTDB refused {} with code {}",
+                        questions.url(),
+                        response.response_code
+                    );
+                    return Some(Err(
+                        FetchError::new("source.refused").arg("provider", &OpenTdb.name())
+                    ));
+                }
+                if !response.results.is_empty() {
+                    cache.insert(&questions.url(), &response);
+                }
+                questions.load(response);
+                Some(Ok(()))
+            }
+            _ => None,
         }
     }
 
+    /// Sends the next fetch once the last one is done, and OpenTDB will take
+    /// another. Fetches go one at a time, so a response is always for the
+    /// fetch in flight.
     pub fn update_system(
-        mut sources: Query<(Entity, &mut TriviaSource), (Without<Fetching>, Without<DoneFetching>)>,
+        sources: Query<(Entity, &OpenTdbSource), Without<Fetching>>,
+        in_flight: Query<(), (With<OpenTdbSource>, With<Fetching>, Without<FetchError>)>,
+        time: Res<Time<Real>>,
+        mut last_sent: Local<Option<f32>>,
         mut commands: Commands,
         mut events: EventWriter<TypedRequest<OpenTdbResponse>>,
     ) {
-        for (entity, mut source) in sources.iter_mut() {
-            let TriviaSource::OpenTdb(ref mut source) = &mut *source else {
-                continue;
-            };
-            info!("Updating OpenTDB source: {:#?}", source);
-            source.update(&mut commands, entity, &mut events);
+        let now = time.elapsed_seconds();
+        let waiting = last_sent.is_some_and(|sent| now - sent < REQUEST_INTERVAL_SECS);
+        if waiting || !in_flight.is_empty() {
+            return;
         }
+        let Some((entity, source)) = sources.iter().min_by_key(|(entity, _)| *entity) else {
+            return;
+        };
+        info!("Fetching {}", source.url());
+        let request = HttpClient::new()
+            .get(&source.url())
+            .entity(entity)
+            .with_type::<OpenTdbResponse>();
+        events.send(request);
+        *last_sent = Some(now);
+        // Mark the entity as being fetched
+        commands.entity(entity).insert(Fetching);
     }
 
+    /// Gives the response to the fetch in flight. Responses that aren't what
+    /// it asked for, or that come when nothing is in flight, are dropped.
     pub fn finalize_system(
-        mut sources: Query<(Entity, &mut TriviaSource), (With<Fetching>, Without<DoneFetching>)>,
+        mut sources: Query<(Entity, &mut OpenTdbSource), (With<Fetching>, Without<FetchError>)>,
         mut commands: Commands,
         mut events: ResMut<Events<TypedResponse<OpenTdbResponse>>>,
+        mut cache: ResMut<SourceCache>,
     ) {
+        let mut in_flight = sources.get_single_mut().ok();
         for event in events.drain() {
-            for (entity, mut source) in sources.iter_mut() {
-                let event = event.clone();
-                let TriviaSource::OpenTdb(ref mut source) = &mut *source else {
-                    continue;
-                };
-                info!("Finalizing OpenTDB source: {:#?}", source);
-                (*source).finalize(&mut commands, entity, event);
+            let Some((entity, mut source)) = in_flight.take() else {
+                warn!("Dropped an OpenTDB response nothing was waiting for");
+                continue;
+            };
+            match source.finalize(event.clone(), &mut cache) {
+                None => {
+                    warn!(
+                        "Dropped an OpenTDB response that isn't for {}",
+                        source.url()
+                    );
+                    in_flight = Some((entity, source));
+                }
+                Some(Err(error)) => {
+                    commands.entity(entity).insert(error);
+                }
+                Some(Ok(())) => {
+                    info!("Finished fetching {}", source.url());
+                    source.finish(Ok(None));
+                    commands.entity(entity).despawn_recursive();
+                }
             }
         }
     }
-}
-
-impl Index<usize> for OpenTdbSource {
-    type Output = String;
-
-    fn index(&self, index: usize) -> &Self::Output {
-        match self {
-            Self::Categories(source) => &source[index],
-        }
-    }
-}
-
-#[derive(Deserialize, TypePath, Debug, Clone, PartialEq)]
-pub enum SourceType {
-    Categories,
-}
 
-impl From<SourceType> for OpenTdbSource {
-    fn from(source: SourceType) -> Self {
-        match source {
-            SourceType::Categories => Self::Categories(Categories::default()),
+    /// Fails the fetch in flight, which is the one the failed request was for
+    pub fn error_system(
+        sources: Query<(Entity, &OpenTdbSource), (With<Fetching>, Without<FetchError>)>,
+        mut commands: Commands,
+        mut events: ResMut<Events<TypedResponseError<OpenTdbResponse>>>,
+    ) {
+        let mut in_flight = sources.get_single().ok();
+        for event in events.drain() {
+            let Some((entity, source)) = in_flight.take() else {
+                warn!(
+                    "Dropped an error from OpenTDB nothing was waiting for: {}",
+                    event.err
+                );
+                continue;
+            };
+            warn!("Failed to fetch {}: {}", source.url(), event.err);
+            commands
+                .entity(entity)
+                .insert(FetchError::new("source.unreachable").arg("provider", &OpenTdb.name()));
         }
     }
 }
 
-#[derive(TypePath, Debug, PartialEq, Default, Clone)]
-pub enum Categories {
-    #[default]
-    Todo,
-
-    /// Indicates we are waiting for the HTTP request to return
-    InProgress,
-
-    /// Indicates we have received the HTTP response
-    Done {
-        map: BiHashMap<String, usize>,
-        names: Vec<String>,
-        selected: HashSet<usize>,
-    },
-}
-
-impl Categories {
-    fn iter(&self) -> Box<dyn Iterator<Item = String>> {
+impl NetworkFetch for OpenTdbSource {
+    fn restore(&mut self, cache: &SourceCache) -> Option<u64> {
         match self {
-            Self::Todo => Box::new(std::iter::empty()),
-            Self::InProgress => Box::new(std::iter::empty()),
-            Self::Done { names, .. } => Box::new(names.clone().into_iter()),
+            Self::Categories(categories) => {
+                let cached = cache.get::<CategoriesResponse>(CATEGORIES_KEY)?;
+                categories.load(cached.value);
+                Some(cached.fetched_at)
+            }
+            Self::Questions(questions) => {
+                let cached = cache.get::<QuestionsResponse>(&questions.url())?;
+                questions.load(cached.value);
+                Some(cached.fetched_at)
+            }
         }
     }
 
-    fn get_selected(&self) -> Option<HashSet<usize>> {
+    fn finish(&mut self, result: Result<Option<u64>, ProviderError>) {
         match self {
-            Self::Todo => None,
-            Self::InProgress => None,
-            Self::Done { selected, .. } => Some(selected.clone()),
+            Self::Categories(Categories { fetched, task }) => {
+                task.finish(result.map(|cached_at| Fetched {
+                    value: fetched.take().unwrap_or_default(),
+                    cached_at,
+                }))
+            }
+            Self::Questions(Questions { fetched, task, .. }) => {
+                task.finish(result.map(|cached_at| Fetched {
+                    value: fetched.take().unwrap_or_default(),
+                    cached_at,
+                }))
+            }
         }
     }
+}
 
-    fn set_selected(&mut self, new: HashSet<usize>) {
-        match self {
-            Self::Todo => {}
-            Self::InProgress => {}
-            Self::Done { selected, .. } => *selected = new,
-        }
-    }
+#[derive(Debug)]
+pub struct Categories {
+    fetched: Option<Vec<Category>>,
+    task: ProviderTask<Vec<Category>>,
+}
 
-    fn update(
-        &mut self,
-        commands: &mut Commands,
-        entity: Entity,
-        events: &mut EventWriter<TypedRequest<OpenTdbResponse>>,
-    ) {
-        *self = Self::InProgress;
-        let request = HttpClient::new()
-            .get("https://opentdb.com/api_category.php")
-            .entity(entity)
-            .with_type::<OpenTdbResponse>();
-        events.send(request);
-        // Mark the entity as being fetched
-        commands.entity(entity).insert(Fetching);
+impl Categories {
+    /// Lists the categories of `response` by name
+    fn load(&mut self, response: CategoriesResponse) {
+        let mut categories = response
+            .trivia_categories
+            .into_iter()
+            .map(|category| Category {
+                id: category.id.to_string(),
+                name: category.name,
+            })
+            .collect::<Vec<_>>();
+        categories.sort_by(|a, b| a.name.cmp(&b.name));
+        self.fetched = Some(categories);
     }
+}
 
-    fn finalize(&mut self, commands: &mut Commands, entity: Entity, response: OpenTdbResponse) {
-        #[allow(irrefutable_let_patterns)]
-        let OpenTdbResponse::Categories(response) = response
-        else {
-            return;
-        };
+/// A batch of questions for a game
+#[derive(Debug)]
+pub struct Questions {
+    /// How many questions to ask for, at most 50
+    amount: usize,
+    /// The id of the category to ask for, or any
+    category: Option<usize>,
+    /// The difficulty to ask for, as listed in the host menu, or any
+    difficulty: Option<String>,
+    /// Whether to ask for multiple choice or true/false questions only, or any
+    multiple_choice: Option<bool>,
+    fetched: Option<Vec<Question>>,
+    task: ProviderTask<Vec<Question>>,
+}
 
-        let build_hasher_default = DefaultHashBuilder::default();
-        let mut map = BiHashMap::with_capacity_and_hashers(
-            response.trivia_categories.len(),
-            build_hasher_default.clone(),
-            build_hasher_default.clone(),
+impl Questions {
+    /// Where to fetch the questions from, which is also what they're cached as
+    fn url(&self) -> String {
+        let mut url = format!(
+            "https://opentdb.com/api.php?amount={}&encode=url3986",
+            self.amount
         );
-        let mut names = Vec::with_capacity(response.trivia_categories.len());
-        for category in response.trivia_categories {
-            map.insert(category.name.clone(), category.id);
-            names.push(category.name);
+        if let Some(category) = self.category {
+            url.push_str(&format!("&category={category}"));
         }
-        names.sort();
-        *self = Self::Done {
-            selected: (0..map.len()).collect(),
-            names,
-            map,
-        };
-        info!("Finished fetching categories: {:#?}", self);
-        commands.entity(entity).insert(DoneFetching);
+        if let Some(difficulty) = &self.difficulty {
+            url.push_str(&format!("&difficulty={}", difficulty.to_lowercase()));
+        }
+        if let Some(multiple_choice) = self.multiple_choice {
+            let kind = if multiple_choice {
+                "multiple"
+            } else {
+                "boolean"
+            };
+            url.push_str(&format!("&type={kind}"));
+        }
+        url
     }
-}
-
-impl Index<usize> for Categories {
-    type Output = String;
 
-    fn index(&self, index: usize) -> &Self::Output {
-        match self {
-            Self::Todo => panic!("Cannot index into empty Categories"),
-            Self::InProgress => panic!("Cannot index into empty Categories"),
-            Self::Done { names, .. } => &names[index],
-        }
+    fn load(&mut self, response: QuestionsResponse) {
+        self.fetched = Some(
+            response
+                .results
+                .into_iter()
+                .map(OpenTdbQuestion::into_question)
+                .collect(),
+        );
     }
 }
 
@@ -228,15 +376,55 @@ impl Index<usize> for Categories {
 #[serde(untagged)]
 pub enum OpenTdbResponse {
     Categories(CategoriesResponse),
+    Questions(QuestionsResponse),
 }
 
-#[derive(Deserialize, TypePath, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, TypePath, Debug, Clone, PartialEq)]
 pub struct CategoriesResponse {
-    pub trivia_categories: Vec<Category>,
+    pub trivia_categories: Vec<OpenTdbCategory>,
 }
 
-#[derive(Deserialize, TypePath, Debug, Clone, PartialEq)]
-pub struct Category {
+#[derive(Serialize, Deserialize, TypePath, Debug, Clone, PartialEq)]
+pub struct OpenTdbCategory {
     pub id: usize,
     pub name: String,
 }
+
+#[derive(Serialize, Deserialize, TypePath, Debug, Clone, PartialEq)]
+pub struct QuestionsResponse {
+    /// 0 on success, otherwise why there are no results
+    pub response_code: u32,
+    pub results: Vec<OpenTdbQuestion>,
+}
+
+/// A question as the Open Trivia Database gives it, with its text percent-encoded
+#[derive(Serialize, Deserialize, TypePath, Debug, Clone, PartialEq)]
+pub struct OpenTdbQuestion {
+    pub category: String,
+    pub difficulty: String,
+    pub question: String,
+    pub correct_answer: String,
+    pub incorrect_answers: Vec<String>,
+}
+
+impl OpenTdbQuestion {
+    /// The question with its text decoded, and its difficulty as the host menu lists it
+    pub fn into_question(self) -> Question {
+        let text = |text: String| LocalizedText::Plain(decode_url3986(&text));
+        Question {
+            category: text(self.category),
+            difficulty: capitalize(&self.difficulty),
+            question: text(self.question),
+            correct_answer: text(self.correct_answer),
+            incorrect_answers: self.incorrect_answers.into_iter().map(text).collect(),
+            image: None,
+            audio: None,
+        }
+    }
+}
+
+/// Decodes text from the Open Trivia Database, which is asked to percent-encode it
+/// with `encode=url3986` rather than escape it as HTML
+pub fn decode_url3986(text: &str) -> String {
+    percent_decode_str(text).decode_utf8_lossy().into_owned()
+}
@@ -0,0 +1,122 @@
+use std::ops::Index;
+
+use bevy::{asset::LoadState, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::LOCAL_PACK,
+    loading::PackAssets,
+    storage,
+    trivia::{
+        question::{LocalizedQuestion, Question},
+        source::{DoneFetching, TriviaSource},
+    },
+};
+
+/// A set of questions loaded from a `*.pack.ron` file.
+/// Packs can have the text of each question in several languages.
+#[derive(Asset, Serialize, Deserialize, TypePath, Clone, Debug, Default, PartialEq)]
+pub struct QuestionPack {
+    /// The name of the pack, as shown to the host
+    pub name: String,
+
+    /// The questions in the pack
+    pub questions: Vec<Question>,
+}
+
+impl QuestionPack {
+    /// The questions that can be asked in one of the locales of `chain`, each
+    /// in the most preferred of them it has
+    pub fn questions(&self, chain: &[String]) -> Vec<LocalizedQuestion> {
+        self.questions
+            .iter()
+            .filter_map(|question| question.localize(chain))
+            .collect()
+    }
+
+    /// The pack saved from the question editor, if there is one
+    pub fn local() -> Option<Self> {
+        let text = storage::read(LOCAL_PACK)?;
+        ron::from_str(&text)
+            .map_err(|error| warn!("Couldn't read {LOCAL_PACK}: {error}"))
+            .ok()
+    }
+}
+
+/// The packs a host can pick from: those that ship with the game, once loaded,
+/// and the one saved from the question editor
+pub fn available_packs(assets: &PackAssets, packs: &Assets<QuestionPack>) -> Vec<QuestionPack> {
+    assets
+        .packs
+        .iter()
+        .filter_map(|handle| packs.get(handle).cloned())
+        .chain(QuestionPack::local().filter(|pack| !pack.questions.is_empty()))
+        .collect()
+}
+
+/// The names of the packs a host can pick from, listed once they've loaded
+#[derive(Deserialize, TypePath, Clone, Debug, Default, PartialEq)]
+#[serde(from = "PackListType")]
+pub struct PackList(Vec<String>);
+
+impl PackList {
+    pub fn iter(&self) -> impl Iterator<Item = String> {
+        self.0.clone().into_iter()
+    }
+
+    /// Lists the packs of `Packs` sources once the packs that ship with the game
+    /// have loaded
+    pub fn update_system(
+        mut sources: Query<(Entity, &mut TriviaSource), Without<DoneFetching>>,
+        assets: Option<Res<PackAssets>>,
+        packs: Res<Assets<QuestionPack>>,
+        server: Res<AssetServer>,
+        mut commands: Commands,
+    ) {
+        let Some(assets) = assets else {
+            return;
+        };
+        // Packs that fail to load are left out rather than waited on
+        let loading = assets.packs.iter().any(|handle| {
+            matches!(
+                server.get_load_state(handle),
+                Some(LoadState::Loading | LoadState::NotLoaded)
+            )
+        });
+        if loading {
+            return;
+        }
+        for (entity, mut source) in sources.iter_mut() {
+            let TriviaSource::Packs(list) = &mut *source else {
+                continue;
+            };
+            list.0 = available_packs(&assets, &packs)
+                .into_iter()
+                .map(|pack| pack.name)
+                .collect();
+            commands.entity(entity).insert(DoneFetching);
+        }
+    }
+}
+
+impl Index<usize> for PackList {
+    type Output = String;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+#[derive(Deserialize, TypePath, Debug, Clone, PartialEq)]
+pub enum PackListType {
+    /// Every pack the host has
+    All,
+}
+
+impl From<PackListType> for PackList {
+    fn from(list: PackListType) -> Self {
+        match list {
+            PackListType::All => Self::default(),
+        }
+    }
+}
@@ -0,0 +1,389 @@
+//! Where questions come from. Each source of questions, like the Open Trivia
+//! Database, is a [`QuestionProvider`] registered with
+//! [`RegisterQuestionProvider::register_question_provider`], so crates can add
+//! their own from a plugin:
+//!
+//! ```ignore
+//! impl Plugin for MyTriviaPlugin {
+//!     fn build(&self, app: &mut App) {
+//!         app.register_question_provider(MyTrivia::default());
+//!     }
+//! }
+//! ```
+//!
+//! Menus list a provider's categories with `options: Provider("my_trivia")`,
+//! and every provider with `options: Providers(All)`.
+
+use std::{fmt, ops::Index, sync::Arc};
+
+use bevy::{prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dialog::{ShowToast, Toast},
+    trivia::{
+        question::Question,
+        source::{DoneFetching, FetchError, Fetching, FromCache, TriviaSource},
+    },
+};
+
+pub use crate::storage::Pending;
+
+/// What a provider is still fetching, or what it fetched or why it couldn't
+pub type ProviderTask<T> = Pending<Result<Fetched<T>, ProviderError>>;
+
+/// Gives out questions, and the categories they're in
+pub trait QuestionProvider: Send + Sync + 'static {
+    /// What menus and the host's choices call the provider, like `"opentdb"`
+    fn id(&self) -> &'static str;
+
+    /// What hosts see the provider as, like `"Open Trivia Database"`
+    fn name(&self) -> &'static str {
+        self.id()
+    }
+
+    /// What the provider can narrow questions down by
+    fn capabilities(&self) -> Capabilities;
+
+    /// Starts fetching the categories questions can be asked for in
+    fn fetch_categories(&self, commands: &mut Commands) -> ProviderTask<Vec<Category>>;
+
+    /// Starts fetching questions. The provider narrows them down by what it's
+    /// capable of, and may give more or fewer than asked for.
+    fn fetch_questions(
+        &self,
+        query: &QuestionQuery,
+        commands: &mut Commands,
+    ) -> ProviderTask<Vec<Question>>;
+}
+
+/// What a provider can narrow questions down by, beyond categories
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Questions have a difficulty, and can be asked for by it
+    pub difficulty: bool,
+    /// Questions can be asked for as multiple choice or true/false
+    pub types: bool,
+    /// Questions can be had in other languages than English
+    pub language: bool,
+}
+
+/// A category of questions of a provider
+#[derive(Serialize, Deserialize, Reflect, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Category {
+    /// What the provider calls it
+    pub id: String,
+    /// What the host sees
+    pub name: String,
+}
+
+/// The questions a host asked for
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuestionQuery {
+    pub amount: usize,
+    /// Any category if empty
+    pub categories: Vec<Category>,
+    /// Any difficulty if empty, as listed in the host menu
+    pub difficulties: Vec<String>,
+    /// `Some(true)` for only multiple choice questions, `Some(false)` for only
+    /// true/false ones, `None` for both
+    pub multiple_choice: Option<bool>,
+    /// The locales to ask for questions in, most preferred first
+    pub language: Vec<String>,
+}
+
+/// What a provider fetched
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fetched<T> {
+    pub value: T,
+    /// When it was cached, in seconds since the Unix epoch, if the provider
+    /// couldn't be reached and gave what it fetched last time instead
+    pub cached_at: Option<u64>,
+}
+
+impl<T> Fetched<T> {
+    /// Freshly fetched
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            cached_at: None,
+        }
+    }
+}
+
+/// Why a provider couldn't give what it was asked for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProviderError {
+    /// It couldn't be reached, and had nothing cached
+    Unreachable(FetchError),
+    /// It answered with something that makes no sense
+    BadResponse(String),
+    /// There's no provider with this id
+    Unknown(String),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreachable(reason) => write!(f, "Could not be reached: {reason}"),
+            Self::BadResponse(reason) => write!(f, "Gave a bad response: {reason}"),
+            Self::Unknown(id) => write!(f, "There is no question provider called {id}"),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+impl ProviderError {
+    /// Why, to show the player where the fetch was asked for
+    pub fn fetch_error(&self) -> FetchError {
+        match self {
+            Self::Unreachable(reason) => reason.clone(),
+            Self::BadResponse(_) => FetchError::new("source.bad_response"),
+            Self::Unknown(id) => FetchError::new("source.unknown").arg("provider", id),
+        }
+    }
+}
+
+/// The question providers, by id
+#[derive(Resource, Default, Clone)]
+pub struct ProviderRegistry(Vec<Arc<dyn QuestionProvider>>);
+
+impl ProviderRegistry {
+    /// The provider called `id`
+    pub fn get(&self, id: &str) -> Result<Arc<dyn QuestionProvider>, ProviderError> {
+        self.0
+            .iter()
+            .find(|provider| provider.id() == id)
+            .cloned()
+            .ok_or_else(|| ProviderError::Unknown(id.to_string()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn QuestionProvider>> {
+        self.0.iter()
+    }
+}
+
+pub trait RegisterQuestionProvider {
+    /// Makes `provider` available to hosts, replacing any with the same id
+    fn register_question_provider(&mut self, provider: impl QuestionProvider) -> &mut Self;
+}
+
+impl RegisterQuestionProvider for App {
+    fn register_question_provider(&mut self, provider: impl QuestionProvider) -> &mut Self {
+        let mut registry = self
+            .world_mut()
+            .get_resource_or_insert_with(ProviderRegistry::default);
+        registry.0.retain(|other| other.id() != provider.id());
+        registry.0.push(Arc::new(provider));
+        self
+    }
+}
+
+/// Lists the categories of a provider, in menus
+#[derive(Deserialize, TypePath, Clone, Debug, Default)]
+#[serde(from = "String")]
+pub struct ProviderSource {
+    /// The id of the provider
+    pub id: String,
+    /// The categories, once fetched, by name
+    categories: Vec<Category>,
+    selected: Option<HashSet<usize>>,
+    task: Option<ProviderTask<Vec<Category>>>,
+}
+
+impl From<String> for ProviderSource {
+    fn from(id: String) -> Self {
+        Self { id, ..default() }
+    }
+}
+
+impl PartialEq for ProviderSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.categories == other.categories
+            && self.selected == other.selected
+    }
+}
+
+impl ProviderSource {
+    pub fn iter(&self) -> impl Iterator<Item = String> {
+        self.categories
+            .iter()
+            .map(|category| category.name.clone())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn get_selected(&self) -> Option<HashSet<usize>> {
+        self.selected.clone()
+    }
+
+    pub fn set_selected(&mut self, selected: HashSet<usize>) {
+        if self.selected.is_some() {
+            self.selected = Some(selected);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.categories.clear();
+        self.selected = None;
+        self.task = None;
+    }
+
+    /// The category at `index`, once they've been fetched
+    pub fn category(&self, index: usize) -> Option<&Category> {
+        self.categories.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.categories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    /// Asks the provider for the categories of `Provider` sources that haven't
+    /// been fetched yet
+    pub(crate) fn update_system(
+        mut sources: Query<
+            (Entity, &mut TriviaSource),
+            (
+                Without<Fetching>,
+                Without<DoneFetching>,
+                Without<FetchError>,
+            ),
+        >,
+        registry: Res<ProviderRegistry>,
+        mut commands: Commands,
+    ) {
+        for (entity, mut source) in sources.iter_mut() {
+            let TriviaSource::Provider(source) = &mut *source else {
+                continue;
+            };
+            match registry.get(&source.id) {
+                Ok(provider) => {
+                    source.task = Some(provider.fetch_categories(&mut commands));
+                    commands.entity(entity).insert(Fetching);
+                }
+                Err(error) => {
+                    warn!("{error}");
+                    commands.entity(entity).insert(error.fetch_error());
+                }
+            }
+        }
+    }
+
+    /// Lists the categories once they've been fetched, all of them selected
+    pub(crate) fn finalize_system(
+        mut sources: Query<(Entity, &mut TriviaSource), (With<Fetching>, Without<DoneFetching>)>,
+        mut commands: Commands,
+        mut toasts: EventWriter<ShowToast>,
+    ) {
+        for (entity, mut source) in sources.iter_mut() {
+            let TriviaSource::Provider(source) = &mut *source else {
+                continue;
+            };
+            let Some(result) = source.task.as_ref().and_then(Pending::take) else {
+                continue;
+            };
+            source.task = None;
+            let mut entity = commands.entity(entity);
+            entity.remove::<(Fetching, FromCache)>();
+            match result {
+                Ok(Fetched { value, cached_at }) => {
+                    source.selected = Some((0..value.len()).collect());
+                    source.categories = value;
+                    entity.insert(DoneFetching);
+                    if let Some(cached_at) = cached_at {
+                        entity.insert(FromCache(cached_at));
+                    }
+                }
+                Err(error) => {
+                    warn!("Couldn't fetch the categories of {}: {error}", source.id);
+                    entity.insert(error.fetch_error());
+                    toasts.send(ShowToast(Toast::error("toast.categories_failed")));
+                }
+            }
+        }
+    }
+}
+
+impl Index<usize> for ProviderSource {
+    type Output = Category;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.categories[index]
+    }
+}
+
+/// The question providers a host can pick from, by name
+#[derive(Deserialize, TypePath, Clone, Debug, Default, PartialEq)]
+#[serde(from = "ProviderListType")]
+pub struct ProviderList {
+    ids: Vec<String>,
+    names: Vec<String>,
+}
+
+impl ProviderList {
+    pub fn iter(&self) -> impl Iterator<Item = String> {
+        self.names.clone().into_iter()
+    }
+
+    /// The id of the provider at `index`
+    pub fn id(&self, index: usize) -> Option<&str> {
+        self.ids.get(index).map(String::as_str)
+    }
+
+    /// Where the provider called `id` is listed
+    pub fn position(&self, id: &str) -> Option<usize> {
+        self.ids.iter().position(|listed| listed == id)
+    }
+
+    /// Lists the registered providers in `Providers` sources
+    pub(crate) fn update_system(
+        mut sources: Query<(Entity, &mut TriviaSource), Without<DoneFetching>>,
+        registry: Res<ProviderRegistry>,
+        mut commands: Commands,
+    ) {
+        for (entity, mut source) in sources.iter_mut() {
+            let TriviaSource::Providers(list) = &mut *source else {
+                continue;
+            };
+            *list = ProviderList {
+                ids: registry
+                    .iter()
+                    .map(|provider| provider.id().to_string())
+                    .collect(),
+                names: registry
+                    .iter()
+                    .map(|provider| provider.name().to_string())
+                    .collect(),
+            };
+            commands.entity(entity).insert(DoneFetching);
+        }
+    }
+}
+
+impl Index<usize> for ProviderList {
+    type Output = String;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.names[index]
+    }
+}
+
+#[derive(Deserialize, TypePath, Debug, Clone, PartialEq)]
+pub enum ProviderListType {
+    /// Every registered provider
+    All,
+}
+
+impl From<ProviderListType> for ProviderList {
+    fn from(list: ProviderListType) -> Self {
+        match list {
+            ProviderListType::All => Self::default(),
+        }
+    }
+}
@@ -1,5 +1,279 @@
-use bevy::reflect::TypePath;
+use bevy::prelude::*;
+use bevy_http_client::{
+    prelude::{HttpTypedRequestTrait, TypedRequest, TypedResponse, TypedResponseError},
+    HttpClient,
+};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, TypePath, Clone, Debug, PartialEq)]
-pub struct TriviaApiSource;
+use crate::{
+    locale::LocalizedText,
+    trivia::{
+        question::{capitalize, Question},
+        source::{
+            cache::SourceCache,
+            provider::{
+                Capabilities, Category, Fetched, ProviderError, ProviderTask, QuestionProvider,
+                QuestionQuery, RegisterQuestionProvider,
+            },
+            AddNetworkFetch, FetchError, Fetching, NetworkFetch,
+        },
+    },
+};
+
+/// The categories of the Trivia API by id. They never change, so they aren't fetched.
+const CATEGORIES: [(&str, &str); 10] = [
+    ("arts_and_literature", "Arts & Literature"),
+    ("film_and_tv", "Film & TV"),
+    ("food_and_drink", "Food & Drink"),
+    ("general_knowledge", "General Knowledge"),
+    ("geography", "Geography"),
+    ("history", "History"),
+    ("music", "Music"),
+    ("science", "Science"),
+    ("society_and_culture", "Society & Culture"),
+    ("sport_and_leisure", "Sport & Leisure"),
+];
+
+pub struct TriviaApiPlugin;
+
+impl Plugin for TriviaApiPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_question_provider(TriviaApi)
+            .add_network_fetch::<TriviaApiSource>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    TriviaApiSource::update_system,
+                    TriviaApiSource::finalize_system,
+                    TriviaApiSource::error_system,
+                ),
+            );
+        app.register_request_type::<TriviaApiResponse>();
+    }
+}
+
+/// Multiple choice questions from [The Trivia API](https://the-trivia-api.com/), in English
+pub struct TriviaApi;
+
+impl TriviaApi {
+    /// The most questions the Trivia API gives at once
+    pub const MAX_AMOUNT: usize = 50;
+}
+
+impl QuestionProvider for TriviaApi {
+    fn id(&self) -> &'static str {
+        "trivia_api"
+    }
+
+    fn name(&self) -> &'static str {
+        "The Trivia API"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            difficulty: true,
+            types: false,
+            language: false,
+        }
+    }
+
+    fn fetch_categories(&self, _commands: &mut Commands) -> ProviderTask<Vec<Category>> {
+        let categories = CATEGORIES
+            .iter()
+            .map(|(id, name)| Category {
+                id: id.to_string(),
+                name: name.to_string(),
+            })
+            .collect();
+        ProviderTask::ready(Ok(Fetched::new(categories)))
+    }
+
+    fn fetch_questions(
+        &self,
+        query: &QuestionQuery,
+        commands: &mut Commands,
+    ) -> ProviderTask<Vec<Question>> {
+        let mut url = format!(
+            "https://the-trivia-api.com/v2/questions?limit={}",
+            query.amount.min(Self::MAX_AMOUNT)
+        );
+        if !query.categories.is_empty() {
+            let ids = query
+                .categories
+                .iter()
+                .map(|category| category.id.as_str())
+                .collect::<Vec<_>>();
+            url.push_str(&format!("&categories={}", ids.join(",")));
+        }
+        if !query.difficulties.is_empty() {
+            let difficulties = query
+                .difficulties
+                .iter()
+                .map(|difficulty| difficulty.to_lowercase())
+                .collect::<Vec<_>>();
+            url.push_str(&format!("&difficulties={}", difficulties.join(",")));
+        }
+        let task = ProviderTask::default();
+        commands.spawn((
+            Name::new("trivia_api::questions"),
+            TriviaApiSource {
+                url,
+                fetched: None,
+                task: task.clone(),
+            },
+        ));
+        task
+    }
+}
+
+/// A fetch of questions from the Trivia API, by the entity it's a component of
+#[derive(Component, Debug)]
+pub struct TriviaApiSource {
+    /// Where to fetch the questions from, which is also what they're cached as
+    url: String,
+    fetched: Option<Vec<Question>>,
+    task: ProviderTask<Vec<Question>>,
+}
+
+impl TriviaApiSource {
+    fn load(&mut self, response: TriviaApiResponse) {
+        self.fetched = Some(
+            response
+                .0
+                .into_iter()
+                .map(TriviaApiQuestion::into_question)
+                .collect(),
+        );
+    }
+
+    /// Sends the next fetch once the last one is done. Fetches go one at a
+    /// time, so a response is always for the fetch in flight.
+    pub fn update_system(
+        sources: Query<(Entity, &TriviaApiSource), Without<Fetching>>,
+        in_flight: Query<(), (With<TriviaApiSource>, With<Fetching>, Without<FetchError>)>,
+        mut commands: Commands,
+        mut events: EventWriter<TypedRequest<TriviaApiResponse>>,
+    ) {
+        if !in_flight.is_empty() {
+            return;
+        }
+        let Some((entity, source)) = sources.iter().min_by_key(|(entity, _)| *entity) else {
+            return;
+        };
+        info!("Fetching {}", source.url);
+        let request = HttpClient::new()
+            .get(&source.url)
+            .entity(entity)
+            .with_type::<TriviaApiResponse>();
+        events.send(request);
+        commands.entity(entity).insert(Fetching);
+    }
+
+    /// Gives the response to the fetch in flight. Responses that come when
+    /// nothing is in flight are dropped.
+    pub fn finalize_system(
+        mut sources: Query<(Entity, &mut TriviaApiSource), (With<Fetching>, Without<FetchError>)>,
+        mut commands: Commands,
+        mut events: ResMut<Events<TypedResponse<TriviaApiResponse>>>,
+        mut cache: ResMut<SourceCache>,
+    ) {
+        let mut in_flight = sources.get_single_mut().ok();
+        for event in events.drain() {
+            let Some((entity, mut source)) = in_flight.take() else {
+                warn!("Dropped a Trivia API response nothing was waiting for");
+                continue;
+            };
+            let response = event.clone();
+            if !response.0.is_empty() {
+                cache.insert(&source.url, &response);
+            }
+            source.load(response);
+            info!("Finished fetching {}", source.url);
+            source.finish(Ok(None));
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    /// Fails the fetch in flight, which is the one the failed request was for
+    pub fn error_system(
+        sources: Query<(Entity, &TriviaApiSource), (With<Fetching>, Without<FetchError>)>,
+        mut commands: Commands,
+        mut events: ResMut<Events<TypedResponseError<TriviaApiResponse>>>,
+    ) {
+        let mut in_flight = sources.get_single().ok();
+        for event in events.drain() {
+            let Some((entity, source)) = in_flight.take() else {
+                warn!(
+                    "Dropped an error from the Trivia API nothing was waiting for: {}",
+                    event.err
+                );
+                continue;
+            };
+            warn!("Failed to fetch {}: {}", source.url, event.err);
+            commands
+                .entity(entity)
+                .insert(FetchError::new("source.unreachable").arg("provider", &TriviaApi.name()));
+        }
+    }
+}
+
+impl NetworkFetch for TriviaApiSource {
+    fn restore(&mut self, cache: &SourceCache) -> Option<u64> {
+        let cached = cache.get::<TriviaApiResponse>(&self.url)?;
+        self.load(cached.value);
+        Some(cached.fetched_at)
+    }
+
+    fn finish(&mut self, result: Result<Option<u64>, ProviderError>) {
+        let fetched = self.fetched.take().unwrap_or_default();
+        self.task.finish(result.map(|cached_at| Fetched {
+            value: fetched,
+            cached_at,
+        }));
+    }
+}
+
+#[derive(Serialize, Deserialize, TypePath, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct TriviaApiResponse(pub Vec<TriviaApiQuestion>);
+
+/// A question as the Trivia API gives it
+#[derive(Serialize, Deserialize, TypePath, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TriviaApiQuestion {
+    /// The id of the category
+    pub category: String,
+    pub difficulty: String,
+    pub question: TriviaApiText,
+    pub correct_answer: String,
+    pub incorrect_answers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, TypePath, Debug, Clone, PartialEq)]
+pub struct TriviaApiText {
+    pub text: String,
+}
+
+impl TriviaApiQuestion {
+    /// The question with its category named, and its difficulty as the host
+    /// menu lists it
+    pub fn into_question(self) -> Question {
+        let category = CATEGORIES
+            .iter()
+            .find(|(id, _)| *id == self.category)
+            .map_or(self.category, |(_, name)| name.to_string());
+        Question {
+            category: LocalizedText::Plain(category),
+            difficulty: capitalize(&self.difficulty),
+            question: LocalizedText::Plain(self.question.text),
+            correct_answer: LocalizedText::Plain(self.correct_answer),
+            incorrect_answers: self
+                .incorrect_answers
+                .into_iter()
+                .map(LocalizedText::Plain)
+                .collect(),
+            image: None,
+            audio: None,
+        }
+    }
+}